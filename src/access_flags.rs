@@ -11,7 +11,9 @@ bitflags! {
         const INTERFACE = 0x0200;
         const ABSTRACT = 0x0400;
         const SYNTHETIC = 0x1000;
+        const ANNOTATION = 0x2000;
         const ENUM = 0x4000;
+        const MODULE = 0x8000;
     }
 }
 
@@ -80,3 +82,46 @@ bitflags! {
         const MANDATED = 0x8000;
     }
 }
+
+bitflags! {
+
+    /// Module bitflags<br>
+    /// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.25> for more information
+    pub struct ModuleAccessFlag: u16 {
+        const OPEN = 0x0020;
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+}
+
+bitflags! {
+
+    /// Module dependency bitflags<br>
+    /// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.25> for more information
+    pub struct RequiresFlag: u16 {
+        const TRANSITIVE = 0x0020;
+        const STATIC_PHASE = 0x0040;
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+}
+
+bitflags! {
+
+    /// Exported package bitflags<br>
+    /// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.25> for more information
+    pub struct ExportsFlag: u16 {
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+}
+
+bitflags! {
+
+    /// Opened package bitflags<br>
+    /// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.25> for more information
+    pub struct OpensFlag: u16 {
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+}
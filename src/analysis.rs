@@ -0,0 +1,583 @@
+//! Data flow analysis of method bodies
+//!
+//! [`max_stack`] follows every path through the code, including branches, switches and
+//! exception handlers, tracking the depth of the operand stack. [`max_locals`] collects the
+//! local variable slots occupied by the parameters and accessed by the instructions. Both count
+//! `long` and `double` values as two slots, like the JVM does
+
+use std::convert::TryFrom;
+
+use crate::attribute::ExceptionTableEntry;
+use crate::bytecode::Bytecode;
+use crate::constant_pool::{Constant, ConstantPool};
+use crate::descriptor::{FieldType, MethodDescriptor};
+use crate::error::{ErrorKind, ErrorType};
+use crate::opcode::{Instruction, Opcode};
+
+/// Computes the maximum depth of the operand stack reached by `code`.<br>
+/// Fails if the stack underflows, a branch target or handler does not start an instruction,
+/// execution can fall off the end of the code or two paths reach an instruction with
+/// different stack depths
+pub fn max_stack(
+    code: &Bytecode,
+    exception_table: &[ExceptionTableEntry],
+    constant_pool: &ConstantPool<'_>
+) -> Result<u16, ErrorType> {
+    let mut depths: Vec<Option<u16>> = vec![None; code.len()];
+    let mut pending = Vec::new();
+    let mut max = 0;
+
+    if code.is_empty() {
+        return Err(ErrorKind::Malformed("code is empty").into());
+    }
+
+    merge(&mut depths, &mut pending, 0, 0)?;
+
+    // A handler starts with the thrown exception as the only value on the stack
+    for entry in exception_table {
+        let handler = index_of(code, u32::from(entry.handler_pc))?;
+        merge(&mut depths, &mut pending, handler, 1)?;
+        max = max.max(1);
+    }
+
+    while let Some(index) = pending.pop() {
+        let instruction = &code[index];
+        let pc = code.pc(index).unwrap();
+        let depth = depths[index].unwrap();
+
+        let (pop, push) = stack_effect(instruction, constant_pool)
+            .map_err(|e| e.within(format!("instruction at pc {}", pc)))?;
+
+        if pop > depth {
+            return Err(ErrorKind::Malformed("operand stack underflow").into());
+        }
+
+        let after = u32::from(depth - pop) + u32::from(push);
+        let after = u16::try_from(after)
+            .map_err(|_| ErrorType::from(ErrorKind::Malformed("operand stack exceeds 65535")))?;
+        max = max.max(after);
+
+        for target in jump_targets(code, instruction, pc)? {
+            merge(&mut depths, &mut pending, target, after)?;
+        }
+
+        if falls_through(instruction) {
+            if index + 1 == code.len() {
+                return Err(ErrorKind::Malformed("execution falls off the end of the code").into());
+            }
+
+            // The subroutine called by jsr returns with the stack it was called with
+            let depth = match instruction {
+                Instruction::jsr { .. } | Instruction::jsr_w { .. } => depth,
+                _ => after
+            };
+            merge(&mut depths, &mut pending, index + 1, depth)?;
+        }
+    }
+
+    Ok(max)
+}
+
+/// Computes the number of local variable slots used by the parameters of a method with the
+/// given descriptor, `this` if it is not static, and the instructions of `code`
+pub fn max_locals(
+    code: &Bytecode,
+    descriptor: &MethodDescriptor<'_>,
+    is_static: bool
+) -> Result<u16, ErrorType> {
+    let mut max = u32::from(descriptor.param_slots()) + if is_static { 0 } else { 1 };
+
+    for instruction in code.instructions() {
+        if let Some((index, size)) = local_access(instruction) {
+            max = max.max(u32::from(index) + u32::from(size));
+        }
+    }
+
+    u16::try_from(max)
+        .map_err(|_| ErrorKind::Malformed("local variables exceed 65535 slots").into())
+}
+
+pub(crate) fn index_of(code: &Bytecode, pc: u32) -> Result<usize, ErrorType> {
+    code.index_of(pc).ok_or_else(|| {
+        ErrorKind::Malformed("jump target is not the start of an instruction").into()
+    })
+}
+
+/// The indices of the instructions a branch or switch located at `pc` may jump to, empty for
+/// all other instructions
+pub(crate) fn jump_targets(
+    code: &Bytecode,
+    instruction: &Instruction,
+    pc: u32
+) -> Result<Vec<usize>, ErrorType> {
+    let targets = match instruction {
+        Instruction::tableswitch { .. } | Instruction::lookupswitch { .. } => {
+            instruction.switch_targets(pc)
+        }
+        _ if instruction.branch_offset().is_some() => {
+            instruction.branch_target(pc).map(|target| vec![target])
+        }
+        _ => Some(Vec::new())
+    };
+    let targets = targets.ok_or(ErrorKind::Malformed(
+        "jump target is not the start of an instruction"
+    ))?;
+
+    targets
+        .into_iter()
+        .map(|target| index_of(code, target))
+        .collect()
+}
+
+fn merge(
+    depths: &mut [Option<u16>],
+    pending: &mut Vec<usize>,
+    index: usize,
+    depth: u16
+) -> Result<(), ErrorType> {
+    match depths[index] {
+        Some(existing) if existing == depth => Ok(()),
+        Some(_) => Err(ErrorKind::Malformed("inconsistent operand stack depth").into()),
+        None => {
+            depths[index] = Some(depth);
+            pending.push(index);
+            Ok(())
+        }
+    }
+}
+
+/// Whether execution may continue with the next instruction
+pub(crate) fn falls_through(instruction: &Instruction) -> bool {
+    !matches!(
+        instruction,
+        Instruction::goto { .. }
+            | Instruction::goto_w { .. }
+            | Instruction::tableswitch { .. }
+            | Instruction::lookupswitch { .. }
+            | Instruction::ret { .. }
+            | Instruction::athrow
+            | Instruction::areturn
+            | Instruction::dreturn
+            | Instruction::freturn
+            | Instruction::ireturn
+            | Instruction::lreturn
+            | Instruction::r#eturn
+            | Instruction::wide {
+                opcode: Opcode::ret,
+                ..
+            }
+    )
+}
+
+/// The first local variable slot read or written by an instruction and the number of slots
+pub(crate) fn local_access(instruction: &Instruction) -> Option<(u16, u16)> {
+    let (index, size) = match instruction {
+        Instruction::aload_0
+        | Instruction::astore_0
+        | Instruction::fload_0
+        | Instruction::fstore_0
+        | Instruction::iload_0
+        | Instruction::istore_0 => (0, 1),
+        Instruction::aload_1
+        | Instruction::astore_1
+        | Instruction::fload_1
+        | Instruction::fstore_1
+        | Instruction::iload_1
+        | Instruction::istore_1 => (1, 1),
+        Instruction::aload_2
+        | Instruction::astore_2
+        | Instruction::fload_2
+        | Instruction::fstore_2
+        | Instruction::iload_2
+        | Instruction::istore_2 => (2, 1),
+        Instruction::aload_3
+        | Instruction::astore_3
+        | Instruction::fload_3
+        | Instruction::fstore_3
+        | Instruction::iload_3
+        | Instruction::istore_3 => (3, 1),
+        Instruction::dload_0
+        | Instruction::dstore_0
+        | Instruction::lload_0
+        | Instruction::lstore_0 => (0, 2),
+        Instruction::dload_1
+        | Instruction::dstore_1
+        | Instruction::lload_1
+        | Instruction::lstore_1 => (1, 2),
+        Instruction::dload_2
+        | Instruction::dstore_2
+        | Instruction::lload_2
+        | Instruction::lstore_2 => (2, 2),
+        Instruction::dload_3
+        | Instruction::dstore_3
+        | Instruction::lload_3
+        | Instruction::lstore_3 => (3, 2),
+        Instruction::aload { index }
+        | Instruction::astore { index }
+        | Instruction::fload { index }
+        | Instruction::fstore { index }
+        | Instruction::iload { index }
+        | Instruction::istore { index }
+        | Instruction::iinc { index, .. }
+        | Instruction::ret { index } => (u16::from(*index), 1),
+        Instruction::dload { index }
+        | Instruction::dstore { index }
+        | Instruction::lload { index }
+        | Instruction::lstore { index } => (u16::from(*index), 2),
+        Instruction::wide { opcode, index } => match opcode {
+            Opcode::dload | Opcode::dstore | Opcode::lload | Opcode::lstore => (*index, 2),
+            _ => (*index, 1)
+        },
+        Instruction::wide_iinc { index, .. } => (*index, 1),
+        _ => return None
+    };
+
+    Some((index, size))
+}
+
+/// The number of stack slots popped and pushed by an instruction, resolving the descriptors of
+/// fields and methods
+pub(crate) fn stack_effect(
+    instruction: &Instruction,
+    constant_pool: &ConstantPool<'_>
+) -> Result<(u16, u16), ErrorType> {
+    let effect = match instruction {
+        Instruction::nop
+        | Instruction::iinc { .. }
+        | Instruction::wide_iinc { .. }
+        | Instruction::goto { .. }
+        | Instruction::goto_w { .. }
+        | Instruction::ret { .. }
+        | Instruction::r#eturn => (0, 0),
+        Instruction::aconst_null
+        | Instruction::iconst_m1
+        | Instruction::iconst_0
+        | Instruction::iconst_1
+        | Instruction::iconst_2
+        | Instruction::iconst_3
+        | Instruction::iconst_4
+        | Instruction::iconst_5
+        | Instruction::fconst_0
+        | Instruction::fconst_1
+        | Instruction::fconst_2
+        | Instruction::bipush { .. }
+        | Instruction::sipush { .. }
+        | Instruction::ldc { .. }
+        | Instruction::ldc_w { .. }
+        | Instruction::aload { .. }
+        | Instruction::aload_0
+        | Instruction::aload_1
+        | Instruction::aload_2
+        | Instruction::aload_3
+        | Instruction::fload { .. }
+        | Instruction::fload_0
+        | Instruction::fload_1
+        | Instruction::fload_2
+        | Instruction::fload_3
+        | Instruction::iload { .. }
+        | Instruction::iload_0
+        | Instruction::iload_1
+        | Instruction::iload_2
+        | Instruction::iload_3
+        | Instruction::new { .. }
+        | Instruction::jsr { .. }
+        | Instruction::jsr_w { .. } => (0, 1),
+        Instruction::lconst_0
+        | Instruction::lconst_1
+        | Instruction::dconst_0
+        | Instruction::dconst_1
+        | Instruction::ldc2_w { .. }
+        | Instruction::dload { .. }
+        | Instruction::dload_0
+        | Instruction::dload_1
+        | Instruction::dload_2
+        | Instruction::dload_3
+        | Instruction::lload { .. }
+        | Instruction::lload_0
+        | Instruction::lload_1
+        | Instruction::lload_2
+        | Instruction::lload_3 => (0, 2),
+        Instruction::astore { .. }
+        | Instruction::astore_0
+        | Instruction::astore_1
+        | Instruction::astore_2
+        | Instruction::astore_3
+        | Instruction::fstore { .. }
+        | Instruction::fstore_0
+        | Instruction::fstore_1
+        | Instruction::fstore_2
+        | Instruction::fstore_3
+        | Instruction::istore { .. }
+        | Instruction::istore_0
+        | Instruction::istore_1
+        | Instruction::istore_2
+        | Instruction::istore_3
+        | Instruction::pop
+        | Instruction::ifeq { .. }
+        | Instruction::ifne { .. }
+        | Instruction::iflt { .. }
+        | Instruction::ifge { .. }
+        | Instruction::ifgt { .. }
+        | Instruction::ifle { .. }
+        | Instruction::ifnull { .. }
+        | Instruction::ifnonnull { .. }
+        | Instruction::tableswitch { .. }
+        | Instruction::lookupswitch { .. }
+        | Instruction::areturn
+        | Instruction::freturn
+        | Instruction::ireturn
+        | Instruction::athrow
+        | Instruction::monitorenter
+        | Instruction::monitorexit => (1, 0),
+        Instruction::dstore { .. }
+        | Instruction::dstore_0
+        | Instruction::dstore_1
+        | Instruction::dstore_2
+        | Instruction::dstore_3
+        | Instruction::lstore { .. }
+        | Instruction::lstore_0
+        | Instruction::lstore_1
+        | Instruction::lstore_2
+        | Instruction::lstore_3
+        | Instruction::pop2
+        | Instruction::if_icmpeq { .. }
+        | Instruction::if_icmpne { .. }
+        | Instruction::if_icmplt { .. }
+        | Instruction::if_icmpge { .. }
+        | Instruction::if_icmpgt { .. }
+        | Instruction::if_icmple { .. }
+        | Instruction::if_acmpeq { .. }
+        | Instruction::if_acmpne { .. }
+        | Instruction::dreturn
+        | Instruction::lreturn => (2, 0),
+        Instruction::ineg
+        | Instruction::fneg
+        | Instruction::i2f
+        | Instruction::f2i
+        | Instruction::i2b
+        | Instruction::i2c
+        | Instruction::i2s
+        | Instruction::anewarray { .. }
+        | Instruction::newarray { .. }
+        | Instruction::arraylength
+        | Instruction::checkcast { .. }
+        | Instruction::instanceof { .. } => (1, 1),
+        Instruction::i2l
+        | Instruction::i2d
+        | Instruction::f2l
+        | Instruction::f2d
+        | Instruction::dup => (1, 2),
+        Instruction::iaload
+        | Instruction::faload
+        | Instruction::aaload
+        | Instruction::baload
+        | Instruction::caload
+        | Instruction::saload
+        | Instruction::iadd
+        | Instruction::fadd
+        | Instruction::isub
+        | Instruction::fsub
+        | Instruction::imul
+        | Instruction::fmul
+        | Instruction::idiv
+        | Instruction::fdiv
+        | Instruction::irem
+        | Instruction::frem
+        | Instruction::ishl
+        | Instruction::ishr
+        | Instruction::iushr
+        | Instruction::iand
+        | Instruction::ior
+        | Instruction::ixor
+        | Instruction::fcmpl
+        | Instruction::fcmpg
+        | Instruction::l2i
+        | Instruction::l2f
+        | Instruction::d2i
+        | Instruction::d2f => (2, 1),
+        Instruction::lneg
+        | Instruction::dneg
+        | Instruction::l2d
+        | Instruction::d2l
+        | Instruction::laload
+        | Instruction::daload
+        | Instruction::swap => (2, 2),
+        Instruction::iastore
+        | Instruction::fastore
+        | Instruction::aastore
+        | Instruction::bastore
+        | Instruction::castore
+        | Instruction::sastore => (3, 0),
+        Instruction::lastore | Instruction::dastore => (4, 0),
+        Instruction::ladd
+        | Instruction::dadd
+        | Instruction::lsub
+        | Instruction::dsub
+        | Instruction::lmul
+        | Instruction::dmul
+        | Instruction::ldiv
+        | Instruction::ddiv
+        | Instruction::lrem
+        | Instruction::drem
+        | Instruction::land
+        | Instruction::lor
+        | Instruction::lxor => (4, 2),
+        Instruction::lshl | Instruction::lshr | Instruction::lushr => (3, 2),
+        Instruction::lcmp | Instruction::dcmpl | Instruction::dcmpg => (4, 1),
+        Instruction::dup_x1 => (2, 3),
+        Instruction::dup_x2 => (3, 4),
+        Instruction::dup2 => (2, 4),
+        Instruction::dup2_x1 => (3, 5),
+        Instruction::dup2_x2 => (4, 6),
+        Instruction::multianewarray { dimensions, .. } => (u16::from(*dimensions), 1),
+        Instruction::wide { opcode, .. } => match opcode {
+            Opcode::aload | Opcode::fload | Opcode::iload => (0, 1),
+            Opcode::dload | Opcode::lload => (0, 2),
+            Opcode::astore | Opcode::fstore | Opcode::istore => (1, 0),
+            Opcode::dstore | Opcode::lstore => (2, 0),
+            _ => (0, 0)
+        },
+        Instruction::getstatic { cp_index } => (0, field_slots(constant_pool, *cp_index)?),
+        Instruction::putstatic { cp_index } => (field_slots(constant_pool, *cp_index)?, 0),
+        Instruction::getfield { cp_index } => (1, field_slots(constant_pool, *cp_index)?),
+        Instruction::putfield { cp_index } => (1 + field_slots(constant_pool, *cp_index)?, 0),
+        Instruction::invokestatic { cp_index } => {
+            method_slots(&constant_pool.member_ref(*cp_index)?.descriptor)?
+        }
+        Instruction::invokevirtual { cp_index }
+        | Instruction::invokespecial { cp_index }
+        | Instruction::invokeinterface { cp_index, .. } => {
+            let (pop, push) = method_slots(&constant_pool.member_ref(*cp_index)?.descriptor)?;
+            (pop + 1, push)
+        }
+        Instruction::invokedynamic { cp_index } => match constant_pool.get(*cp_index)? {
+            Constant::InvokeDynamic { name_index, .. } => {
+                method_slots(&constant_pool.name_and_type(*name_index)?.1)?
+            }
+            _ => return Err(ErrorKind::Malformed("invokedynamic without InvokeDynamic").into())
+        }
+    };
+
+    Ok(effect)
+}
+
+fn field_slots(constant_pool: &ConstantPool<'_>, cp_index: u16) -> Result<u16, ErrorType> {
+    let descriptor = constant_pool.member_ref(cp_index)?.descriptor;
+    Ok(FieldType::parse(&descriptor)?.slot_size())
+}
+
+/// The slots of the parameters and of the return value of a method
+fn method_slots(descriptor: &str) -> Result<(u16, u16), ErrorType> {
+    let descriptor = MethodDescriptor::parse(descriptor)?;
+    let ret = descriptor.ret.as_ref().map_or(0, FieldType::slot_size);
+
+    Ok((descriptor.param_slots(), ret))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{max_locals, max_stack};
+    use crate::constant_pool::{ConstantPool, ConstantPoolBuilder};
+    use crate::descriptor::MethodDescriptor;
+    use crate::opcode::{Instruction, Opcode};
+    use crate::test_util::{expect_pat, read_class_file};
+    use crate::{Attribute, ClassParser, CodeBuilder};
+    use std::fs;
+
+    #[test]
+    fn compute_maxs() {
+        let dirs = ["./tests", "./tests/module", "./tests/module/com/example/app"];
+        let mut computed = 0;
+
+        for dir in dirs.iter() {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+
+                if path.extension().and_then(|ext| ext.to_str()) != Some("class") {
+                    continue;
+                }
+
+                let buf = read_class_file(path.to_str().unwrap()).unwrap();
+                let mut class = ClassParser::from_bytes(&buf)
+                    .parse()
+                    .unwrap();
+                let mut expected = Vec::new();
+
+                for method in class.methods.iter_mut() {
+                    for attribute in method.attributes.iter_mut() {
+                        if let Attribute::Code { max_stack, max_locals, .. } = attribute {
+                            expected.push((*max_stack, *max_locals));
+                            *max_stack = 0;
+                            *max_locals = 0;
+                        }
+                    }
+                }
+
+                class.compute_maxs().unwrap();
+
+                let actual: Vec<_> = class
+                    .methods
+                    .iter()
+                    .flat_map(|method| method.attributes.iter())
+                    .filter_map(|attribute| match attribute {
+                        Attribute::Code { max_stack, max_locals, .. } => Some((*max_stack, *max_locals)),
+                        _ => None
+                    })
+                    .collect();
+
+                assert_eq!(actual, expected, "{}", path.display());
+                computed += actual.len();
+            }
+        }
+
+        assert!(computed > 30);
+
+        // paths reaching an instruction with different stack depths and stack underflow
+        let mut code = CodeBuilder::new();
+        let join = code.new_label();
+        code.push(Instruction::iconst_0);
+        code.push(Instruction::dup);
+        code.branch(Opcode::ifeq, join);
+        code.push(Instruction::iconst_1);
+        code.place(join);
+        code.push(Instruction::pop);
+        code.push(Instruction::r#eturn);
+
+        let cp = ConstantPool::default();
+        let build = |code: CodeBuilder| code.build(&mut ConstantPoolBuilder::new(), 0, 0).unwrap();
+        let attribute = build(code);
+        expect_pat!(Attribute::Code { code, .. }, &attribute, {
+            assert!(max_stack(code, &[], &cp).is_err());
+        });
+
+        let mut code = CodeBuilder::new();
+        code.push(Instruction::lconst_0);
+        code.push(Instruction::lstore { index: 4 });
+        code.push(Instruction::iadd);
+        code.push(Instruction::r#eturn);
+        let attribute = build(code);
+        expect_pat!(Attribute::Code { code, .. }, &attribute, {
+            assert!(max_stack(code, &[], &cp).is_err());
+            let descriptor = MethodDescriptor::parse("(J)V").unwrap();
+            assert_eq!(max_locals(code, &descriptor, false).unwrap(), 6);
+        });
+
+        // jumps before the start of the code
+        let mut code = CodeBuilder::new();
+        code.push(Instruction::nop);
+        code.push(Instruction::goto { offset: -5 });
+        let attribute = build(code);
+        expect_pat!(Attribute::Code { code, .. }, &attribute, {
+            assert!(max_stack(code, &[], &cp).is_err());
+        });
+
+        let mut code = CodeBuilder::new();
+        code.push(Instruction::iconst_0);
+        code.push(Instruction::tableswitch { default: 23, low: 0, high: 1, jump_offsets: vec![23, -2] });
+        code.push(Instruction::r#eturn);
+        let attribute = build(code);
+        expect_pat!(Attribute::Code { code, .. }, &attribute, {
+            assert!(max_stack(code, &[], &cp).is_err());
+        });
+    }
+}
@@ -0,0 +1,254 @@
+//! Types representing annotations and their element values
+
+use crate::constant_pool::ConstantPool;
+use crate::error::{ErrorKind, ErrorType};
+use std::borrow::Cow;
+
+/// A single annotation<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.16> for more information
+#[derive(Debug)]
+pub struct Annotation {
+    pub type_index: u16,
+    pub num_element_value_pairs: u16,
+    pub element_value_pairs: Vec<ElementValuePair>
+}
+
+#[derive(Debug)]
+pub struct ElementValuePair {
+    pub element_name_index: u16,
+    pub value: ElementValue
+}
+
+/// The value of an annotation element<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.16.1> for more information
+#[derive(Debug)]
+pub enum ElementValue {
+    /// A primitive or `String` constant, `tag` is one of `B`, `C`, `D`, `F`, `I`, `J`, `S`, `Z`
+    /// or `s`
+    Const {
+        tag: u8,
+        const_value_index: u16
+    },
+    Enum {
+        type_name_index: u16,
+        const_name_index: u16
+    },
+    Class {
+        class_info_index: u16
+    },
+    Annotation {
+        annotation_value: Annotation
+    },
+    Array {
+        num_values: u16,
+        values: Vec<ElementValue>
+    }
+}
+
+/// The annotations of a single method parameter
+#[derive(Debug)]
+pub struct ParameterAnnotations {
+    pub num_annotations: u16,
+    pub annotations: Vec<Annotation>
+}
+
+/// An annotation on a use of a type<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.20> for more information
+#[derive(Debug)]
+pub struct TypeAnnotation {
+    /// The kind of target the annotation appears on, which determines the variant of
+    /// `target_info`
+    pub target_type: u8,
+    pub target_info: TargetInfo,
+    pub target_path: TypePath,
+    pub type_index: u16,
+    pub num_element_value_pairs: u16,
+    pub element_value_pairs: Vec<ElementValuePair>
+}
+
+/// Identifies the annotated type within a declaration or expression<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.20.1> for more information
+#[derive(Debug)]
+pub enum TargetInfo {
+    /// Type parameter declaration of a generic class, interface, method or constructor
+    TypeParameter { type_parameter_index: u8 },
+    /// Type in the `extends` or `implements` clause of a class or interface,
+    /// `65535` denotes the superclass
+    Supertype { supertype_index: u16 },
+    /// Bound of a type parameter declaration
+    TypeParameterBound {
+        type_parameter_index: u8,
+        bound_index: u8
+    },
+    /// Type in a field declaration, the return type of a method, the type of a newly
+    /// constructed object or the receiver type of a method
+    Empty,
+    /// Type in a formal parameter declaration of a method, constructor or lambda expression
+    FormalParameter { formal_parameter_index: u8 },
+    /// Type in the `throws` clause of a method or constructor, indexing into the `Exceptions`
+    /// attribute
+    Throws { throws_type_index: u16 },
+    /// Type in a local variable or resource variable declaration
+    Localvar {
+        table_length: u16,
+        table: Vec<LocalvarTargetEntry>
+    },
+    /// Type in an exception parameter declaration, indexing into the exception table
+    Catch { exception_table_index: u16 },
+    /// Type in an `instanceof` expression, `new` expression or method reference expression
+    Offset { offset: u16 },
+    /// Type argument of a cast expression, a generic constructor or method invocation, or a
+    /// generic method reference expression
+    TypeArgument {
+        offset: u16,
+        type_argument_index: u8
+    }
+}
+
+/// The bytecode range in which a local variable has a value
+#[derive(Debug)]
+pub struct LocalvarTargetEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub index: u16
+}
+
+/// Locates the annotated part of a compound type, e.g. a type argument or array component<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.20.2> for more information
+#[derive(Debug)]
+pub struct TypePath {
+    pub path_length: u8,
+    pub path: Vec<TypePathEntry>
+}
+
+#[derive(Debug)]
+pub struct TypePathEntry {
+    /// `0` for an array component, `1` for a nested type, `2` for a wildcard bound and
+    /// `3` for a type argument
+    pub type_path_kind: u8,
+    pub type_argument_index: u8
+}
+
+/// An [`Annotation`] with all constant pool references resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedAnnotation<'c> {
+    /// The field descriptor of the annotation type, e.g. `Ljavax/inject/Inject;`
+    pub type_name: Cow<'c, str>,
+    pub elements: Vec<(Cow<'c, str>, ResolvedElementValue<'c>)>
+}
+
+/// An [`ElementValue`] with all constant pool references resolved
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolvedElementValue<'c> {
+    Byte(i8),
+    Char(u16),
+    Double(f64),
+    Float(f32),
+    Int(i32),
+    Long(i64),
+    Short(i16),
+    Boolean(bool),
+    String(Cow<'c, str>),
+    Enum {
+        /// The field descriptor of the enum type
+        type_name: Cow<'c, str>,
+        const_name: Cow<'c, str>
+    },
+    /// The return descriptor of the class literal, e.g. `Ljava/lang/String;` or `V`
+    Class(Cow<'c, str>),
+    Annotation(ResolvedAnnotation<'c>),
+    Array(Vec<ResolvedElementValue<'c>>)
+}
+
+impl Annotation {
+    /// Resolves the annotation type and all element names and values against the constant pool
+    pub fn resolve<'c>(
+        &self,
+        constant_pool: &ConstantPool<'c>
+    ) -> Result<ResolvedAnnotation<'c>, ErrorType> {
+        resolve_annotation(self.type_index, &self.element_value_pairs, constant_pool)
+    }
+}
+
+impl TypeAnnotation {
+    /// Resolves the annotation type and all element names and values against the constant pool
+    pub fn resolve<'c>(
+        &self,
+        constant_pool: &ConstantPool<'c>
+    ) -> Result<ResolvedAnnotation<'c>, ErrorType> {
+        resolve_annotation(self.type_index, &self.element_value_pairs, constant_pool)
+    }
+}
+
+fn resolve_annotation<'c>(
+    type_index: u16,
+    element_value_pairs: &[ElementValuePair],
+    constant_pool: &ConstantPool<'c>
+) -> Result<ResolvedAnnotation<'c>, ErrorType> {
+    let type_name = constant_pool.utf8(type_index)?;
+    let elements = element_value_pairs
+        .iter()
+        .map(|pair| {
+            Ok((
+                constant_pool.utf8(pair.element_name_index)?,
+                pair.value.resolve(constant_pool)?
+            ))
+        })
+        .collect::<Result<_, ErrorType>>()?;
+
+    Ok(ResolvedAnnotation {
+        type_name,
+        elements
+    })
+}
+
+impl ElementValue {
+    /// Resolves this value against the constant pool
+    pub fn resolve<'c>(
+        &self,
+        constant_pool: &ConstantPool<'c>
+    ) -> Result<ResolvedElementValue<'c>, ErrorType> {
+        let value = match self {
+            ElementValue::Const {
+                tag,
+                const_value_index
+            } => {
+                let index = *const_value_index;
+
+                match tag {
+                    b'B' => ResolvedElementValue::Byte(constant_pool.integer(index)? as i8),
+                    b'C' => ResolvedElementValue::Char(constant_pool.integer(index)? as u16),
+                    b'D' => ResolvedElementValue::Double(constant_pool.double(index)?),
+                    b'F' => ResolvedElementValue::Float(constant_pool.float(index)?),
+                    b'I' => ResolvedElementValue::Int(constant_pool.integer(index)?),
+                    b'J' => ResolvedElementValue::Long(constant_pool.long(index)?),
+                    b'S' => ResolvedElementValue::Short(constant_pool.integer(index)? as i16),
+                    b'Z' => ResolvedElementValue::Boolean(constant_pool.integer(index)? != 0),
+                    b's' => ResolvedElementValue::String(constant_pool.utf8(index)?),
+                    _ => return Err(ErrorKind::Malformed("invalid element value tag").into())
+                }
+            }
+            ElementValue::Enum {
+                type_name_index,
+                const_name_index
+            } => ResolvedElementValue::Enum {
+                type_name: constant_pool.utf8(*type_name_index)?,
+                const_name: constant_pool.utf8(*const_name_index)?
+            },
+            ElementValue::Class { class_info_index } => {
+                ResolvedElementValue::Class(constant_pool.utf8(*class_info_index)?)
+            }
+            ElementValue::Annotation { annotation_value } => {
+                ResolvedElementValue::Annotation(annotation_value.resolve(constant_pool)?)
+            }
+            ElementValue::Array { values, .. } => ResolvedElementValue::Array(
+                values
+                    .iter()
+                    .map(|value| value.resolve(constant_pool))
+                    .collect::<Result<_, ErrorType>>()?
+            )
+        };
+
+        Ok(value)
+    }
+}
@@ -1,5 +1,13 @@
-use crate::access_flags::{InnerClassAccessFlag, ParameterAccessFlag};
-use crate::opcode::Instruction;
+use crate::access_flags::{
+    ExportsFlag, InnerClassAccessFlag, ModuleAccessFlag, OpensFlag, ParameterAccessFlag,
+    RequiresFlag
+};
+use crate::annotation::{Annotation, ElementValue, ParameterAnnotations, TypeAnnotation};
+use crate::bytecode::Bytecode;
+use crate::constant_pool::ConstantPool;
+use crate::error::ErrorType;
+use crate::stack_map::StackMapFrame;
+use std::borrow::Cow;
 
 #[derive(Debug)]
 pub struct ExceptionTableEntry {
@@ -15,18 +23,106 @@ pub struct LineNumberTableEntry {
     pub line_number: u16
 }
 
+/// Describes the range of code in which a local variable has a value<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.13> for more information
+#[derive(Debug)]
+pub struct LocalVariableTableEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub index: u16
+}
+
+/// Like [`LocalVariableTableEntry`], but for variables of a generic type<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.14> for more information
+#[derive(Debug)]
+pub struct LocalVariableTypeTableEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub signature_index: u16,
+    pub index: u16
+}
+
+impl LocalVariableTableEntry {
+    /// Resolves the name of this variable
+    pub fn name<'c>(&self, constant_pool: &ConstantPool<'c>) -> Result<Cow<'c, str>, ErrorType> {
+        constant_pool.utf8(self.name_index)
+    }
+
+    /// Resolves the field descriptor of this variable, e.g. `Ljava/lang/String;`
+    pub fn descriptor<'c>(
+        &self,
+        constant_pool: &ConstantPool<'c>
+    ) -> Result<Cow<'c, str>, ErrorType> {
+        constant_pool.utf8(self.descriptor_index)
+    }
+
+    /// Whether this variable occupies slot `index` at `pc`.<br>
+    /// A `long` or `double` variable occupies both `self.index` and `self.index + 1`
+    pub fn is_live(&self, index: u16, pc: u16, constant_pool: &ConstantPool) -> bool {
+        let width = self
+            .descriptor(constant_pool)
+            .map_or(1, |descriptor| slot_width(&descriptor));
+
+        covers(self.index, width, self.start_pc, self.length, index, pc)
+    }
+}
+
+impl LocalVariableTypeTableEntry {
+    /// Resolves the name of this variable
+    pub fn name<'c>(&self, constant_pool: &ConstantPool<'c>) -> Result<Cow<'c, str>, ErrorType> {
+        constant_pool.utf8(self.name_index)
+    }
+
+    /// Resolves the generic signature of this variable, e.g. `Ljava/util/List<TT;>;`
+    pub fn signature<'c>(
+        &self,
+        constant_pool: &ConstantPool<'c>
+    ) -> Result<Cow<'c, str>, ErrorType> {
+        constant_pool.utf8(self.signature_index)
+    }
+
+    /// Whether this variable occupies slot `index` at `pc`, see
+    /// [`LocalVariableTableEntry::is_live`]
+    pub fn is_live(&self, index: u16, pc: u16, constant_pool: &ConstantPool) -> bool {
+        let width = self
+            .signature(constant_pool)
+            .map_or(1, |signature| slot_width(&signature));
+
+        covers(self.index, width, self.start_pc, self.length, index, pc)
+    }
+}
+
+fn slot_width(descriptor: &str) -> u16 {
+    match descriptor {
+        "J" | "D" => 2,
+        _ => 1
+    }
+}
+
+fn covers(slot: u16, width: u16, start_pc: u16, length: u16, index: u16, pc: u16) -> bool {
+    slot <= index
+        && (index as u32) < slot as u32 + width as u32
+        && start_pc <= pc
+        && (pc as u32) < start_pc as u32 + length as u32
+}
+
 #[derive(Debug)]
 pub struct InnerClassAttribute {
     pub inner_class_info_index: u16,
     pub outer_class_info_index: u16,
     pub inner_name_index: u16,
-    pub inner_class_access_flags: InnerClassAccessFlag
+    pub inner_class_access_flags: InnerClassAccessFlag,
+    pub undefined_inner_class_access_flags: u16
 }
 
 #[derive(Debug)]
 pub struct ParameterAttribute {
     pub name_index: u16,
-    pub access_flags: ParameterAccessFlag
+    pub access_flags: ParameterAccessFlag,
+    pub undefined_access_flags: u16
 }
 
 #[derive(Debug)]
@@ -36,8 +132,113 @@ pub struct BootstrapMethodAttribute {
     pub bootstrap_arguments: Vec<u16>
 }
 
+/// Describes a single component of a record class<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se16/html/jvms-4.html#jvms-4.7.30> for more information
+#[derive(Debug)]
+pub struct RecordComponentInfo<'c> {
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes_count: u16,
+    pub attributes: Vec<Attribute<'c>>
+}
+
+impl<'c> RecordComponentInfo<'c> {
+    /// Resolves the name of this component
+    pub fn name(&self, constant_pool: &ConstantPool<'c>) -> Result<Cow<'c, str>, ErrorType> {
+        constant_pool.utf8(self.name_index)
+    }
+
+    /// Resolves the field descriptor of this component, e.g. `Ljava/util/List;`
+    pub fn descriptor(&self, constant_pool: &ConstantPool<'c>) -> Result<Cow<'c, str>, ErrorType> {
+        constant_pool.utf8(self.descriptor_index)
+    }
+}
+
+/// The contents of a `Module` attribute<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.25> for more information
+#[derive(Debug)]
+pub struct ModuleAttribute {
+    pub module_name_index: u16,
+    pub module_flags: ModuleAccessFlag,
+    pub undefined_module_flags: u16,
+    pub module_version_index: u16,
+    pub requires_count: u16,
+    pub requires: Vec<ModuleRequires>,
+    pub exports_count: u16,
+    pub exports: Vec<ModuleExports>,
+    pub opens_count: u16,
+    pub opens: Vec<ModuleOpens>,
+    pub uses_count: u16,
+    pub uses_index: Vec<u16>,
+    pub provides_count: u16,
+    pub provides: Vec<ModuleProvides>
+}
+
+#[derive(Debug)]
+pub struct ModuleRequires {
+    pub requires_index: u16,
+    pub requires_flags: RequiresFlag,
+    pub undefined_requires_flags: u16,
+    pub requires_version_index: u16
+}
+
+#[derive(Debug)]
+pub struct ModuleExports {
+    pub exports_index: u16,
+    pub exports_flags: ExportsFlag,
+    pub undefined_exports_flags: u16,
+    pub exports_to_count: u16,
+    pub exports_to_index: Vec<u16>
+}
+
 #[derive(Debug)]
-pub enum Attribute {
+pub struct ModuleOpens {
+    pub opens_index: u16,
+    pub opens_flags: OpensFlag,
+    pub undefined_opens_flags: u16,
+    pub opens_to_count: u16,
+    pub opens_to_index: Vec<u16>
+}
+
+#[derive(Debug)]
+pub struct ModuleProvides {
+    pub provides_index: u16,
+    pub provides_with_count: u16,
+    pub provides_with_index: Vec<u16>
+}
+
+impl ModuleAttribute {
+    /// Resolves the name of this module, e.g. `java.base`
+    pub fn name<'c>(&self, constant_pool: &ConstantPool<'c>) -> Result<Cow<'c, str>, ErrorType> {
+        constant_pool.module_name(self.module_name_index)
+    }
+
+    /// Resolves the names of all modules this module depends on
+    pub fn required_modules<'c>(
+        &self,
+        constant_pool: &ConstantPool<'c>
+    ) -> Result<Vec<Cow<'c, str>>, ErrorType> {
+        self.requires
+            .iter()
+            .map(|requires| constant_pool.module_name(requires.requires_index))
+            .collect()
+    }
+
+    /// Resolves the internal names of all packages exported by this module,
+    /// e.g. `java/lang`
+    pub fn exported_packages<'c>(
+        &self,
+        constant_pool: &ConstantPool<'c>
+    ) -> Result<Vec<Cow<'c, str>>, ErrorType> {
+        self.exports
+            .iter()
+            .map(|exports| constant_pool.package_name(exports.exports_index))
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum Attribute<'c> {
     ConstantValue {
         attribute_name_index: u16,
         attribute_length: u32,
@@ -49,11 +250,11 @@ pub enum Attribute {
         max_stack: u16,
         max_locals: u16,
         code_length: u32,
-        code: Vec<Instruction>,
+        code: Bytecode,
         exception_table_length: u16,
         exception_table: Vec<ExceptionTableEntry>,
         attributes_count: u16,
-        attributes: Vec<Attribute>
+        attributes: Vec<Attribute<'c>>
     },
     InnerClasses {
         attribute_name_index: u16,
@@ -66,12 +267,58 @@ pub enum Attribute {
         attribute_length: u32,
         sourcefile_index: u16
     },
+    Signature {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        signature_index: u16
+    },
+    Exceptions {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        number_of_exceptions: u16,
+        exception_index_table: Vec<u16>
+    },
+    EnclosingMethod {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        class_index: u16,
+        /// Zero if the class is not immediately enclosed by a method or constructor
+        method_index: u16
+    },
+    Synthetic {
+        attribute_name_index: u16,
+        attribute_length: u32
+    },
+    Deprecated {
+        attribute_name_index: u16,
+        attribute_length: u32
+    },
     LineNumberTable {
         attribute_name_index: u16,
         attribute_length: u32,
         line_number_table_length: u16,
         line_number_table: Vec<LineNumberTableEntry>
     },
+    /// Extended debugging information, usually a JSR-45 source map,
+    /// see [`Smap`](crate::smap::Smap)
+    SourceDebugExtension {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        /// Modified UTF-8 encoded string
+        debug_extension: &'c [u8]
+    },
+    LocalVariableTable {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        local_variable_table_length: u16,
+        local_variable_table: Vec<LocalVariableTableEntry>
+    },
+    LocalVariableTypeTable {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        local_variable_type_table_length: u16,
+        local_variable_type_table: Vec<LocalVariableTypeTableEntry>
+    },
     BootstrapMethods {
         attribute_name_index: u16,
         attribute_length: u32,
@@ -89,5 +336,162 @@ pub enum Attribute {
         attribute_length: u32,
         number_of_classes: u16,
         classes: Vec<u16>
+    },
+    NestHost {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        host_class_index: u16
+    },
+    Record {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        components_count: u16,
+        components: Vec<RecordComponentInfo<'c>>
+    },
+    PermittedSubclasses {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        number_of_classes: u16,
+        classes: Vec<u16>
+    },
+    StackMapTable {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        number_of_entries: u16,
+        entries: Vec<StackMapFrame>
+    },
+    RuntimeVisibleAnnotations {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        num_annotations: u16,
+        annotations: Vec<Annotation>
+    },
+    RuntimeInvisibleAnnotations {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        num_annotations: u16,
+        annotations: Vec<Annotation>
+    },
+    RuntimeVisibleParameterAnnotations {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        num_parameters: u8,
+        parameter_annotations: Vec<ParameterAnnotations>
+    },
+    RuntimeInvisibleParameterAnnotations {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        num_parameters: u8,
+        parameter_annotations: Vec<ParameterAnnotations>
+    },
+    RuntimeVisibleTypeAnnotations {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        num_annotations: u16,
+        annotations: Vec<TypeAnnotation>
+    },
+    RuntimeInvisibleTypeAnnotations {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        num_annotations: u16,
+        annotations: Vec<TypeAnnotation>
+    },
+    AnnotationDefault {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        default_value: ElementValue
+    },
+    Module {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        module: ModuleAttribute
+    },
+    ModulePackages {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        package_count: u16,
+        package_index: Vec<u16>
+    },
+    ModuleMainClass {
+        attribute_name_index: u16,
+        attribute_length: u32,
+        main_class_index: u16
+    },
+    /// An attribute not recognized by this crate, e.g. one emitted by a compiler for another
+    /// JVM language or by custom tooling. `info` holds its contents as they appear in the class
+    /// file
+    Unknown {
+        attribute_name_index: u16,
+        info: &'c [u8]
+    }
+}
+
+impl<'c> Attribute<'c> {
+    /// Finds the local variable occupying slot `index` at `pc`.<br>
+    /// Returns `None` if this is not a `Code` attribute, if the method was compiled
+    /// without debug information or if the slot is unused at that point
+    pub fn local_variable_at(
+        &self,
+        index: u16,
+        pc: u16,
+        constant_pool: &ConstantPool
+    ) -> Option<&LocalVariableTableEntry> {
+        self.code_attributes()
+            .iter()
+            .filter_map(|attribute| match attribute {
+                Attribute::LocalVariableTable {
+                    local_variable_table,
+                    ..
+                } => Some(local_variable_table),
+                _ => None
+            })
+            .flatten()
+            .find(|entry| entry.is_live(index, pc, constant_pool))
+    }
+
+    /// Finds the generic type information of the local variable occupying slot `index` at `pc`.<br>
+    /// Only variables whose type uses a type variable or parameterized type have such an entry
+    pub fn local_variable_type_at(
+        &self,
+        index: u16,
+        pc: u16,
+        constant_pool: &ConstantPool
+    ) -> Option<&LocalVariableTypeTableEntry> {
+        self.code_attributes()
+            .iter()
+            .filter_map(|attribute| match attribute {
+                Attribute::LocalVariableTypeTable {
+                    local_variable_type_table,
+                    ..
+                } => Some(local_variable_type_table),
+                _ => None
+            })
+            .flatten()
+            .find(|entry| entry.is_live(index, pc, constant_pool))
+    }
+
+    /// Finds the source line of the instruction at `pc`.<br>
+    /// Returns `None` if this is not a `Code` attribute or if the method was compiled
+    /// without line numbers
+    pub fn line_number_at(&self, pc: u16) -> Option<u16> {
+        self.code_attributes()
+            .iter()
+            .filter_map(|attribute| match attribute {
+                Attribute::LineNumberTable {
+                    line_number_table, ..
+                } => Some(line_number_table),
+                _ => None
+            })
+            .flatten()
+            .filter(|entry| entry.start_pc <= pc)
+            .max_by_key(|entry| entry.start_pc)
+            .map(|entry| entry.line_number)
+    }
+
+    fn code_attributes(&self) -> &[Attribute<'c>] {
+        match self {
+            Attribute::Code { attributes, .. } => attributes,
+            _ => &[]
+        }
     }
 }
@@ -0,0 +1,71 @@
+//! The decoded instructions of a `Code` attribute, along with their offsets
+
+use std::ops::Index;
+
+use crate::opcode::Instruction;
+
+/// The instructions of a method, each paired with its pc, the offset of its opcode relative
+/// to the start of the method.<br>
+/// Instructions are kept in ascending pc order, so lookups in either direction are cheap
+#[derive(Debug, Default)]
+pub struct Bytecode {
+    pcs: Vec<u32>,
+    instructions: Vec<Instruction>
+}
+
+impl Bytecode {
+    /// Appends an instruction, `pc` must be greater than the pc of the last instruction
+    pub(crate) fn push(&mut self, pc: u32, instruction: Instruction) {
+        debug_assert!(!matches!(self.pcs.last(), Some(&last) if last >= pc));
+
+        self.pcs.push(pc);
+        self.instructions.push(instruction);
+    }
+
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Iterates over all instructions along with their pc
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &Instruction)> {
+        self.pcs.iter().copied().zip(self.instructions.iter())
+    }
+
+    /// The instruction at the given index
+    pub fn get(&self, index: usize) -> Option<&Instruction> {
+        self.instructions.get(index)
+    }
+
+    /// The pc of the instruction at the given index
+    pub fn pc(&self, index: usize) -> Option<u32> {
+        self.pcs.get(index).copied()
+    }
+
+    /// The index of the instruction starting at `pc`.<br>
+    /// Returns `None` if no instruction starts at `pc`, e.g. because it points into the
+    /// operands of another instruction
+    pub fn index_of(&self, pc: u32) -> Option<usize> {
+        self.pcs.binary_search(&pc).ok()
+    }
+
+    /// The instruction starting at `pc`
+    pub fn at(&self, pc: u32) -> Option<&Instruction> {
+        self.index_of(pc).map(|index| &self.instructions[index])
+    }
+}
+
+impl Index<usize> for Bytecode {
+    type Output = Instruction;
+
+    fn index(&self, index: usize) -> &Instruction {
+        &self.instructions[index]
+    }
+}
@@ -0,0 +1,651 @@
+//! An assembler for method bodies, resolving symbolic branch targets into offsets
+
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::attribute::ExceptionTableEntry;
+use crate::bytecode::Bytecode;
+use crate::constant_pool::ConstantPoolBuilder;
+use crate::error::{ErrorKind, ErrorType};
+use crate::opcode::{Instruction, Opcode};
+use crate::Attribute;
+
+/// A position in the code of a [`CodeBuilder`], created by [`CodeBuilder::new_label`] and
+/// bound to the next instruction by [`CodeBuilder::place`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Label {
+    builder: usize,
+    index: usize
+}
+
+/// Identifies every builder, so labels of another builder are not mistaken for its own
+static NEXT_BUILDER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug)]
+enum Item {
+    Instruction(Instruction),
+    Label(Label),
+    Branch {
+        opcode: Opcode,
+        target: Label
+    },
+    TableSwitch {
+        low: i32,
+        default: Label,
+        targets: Vec<Label>
+    },
+    LookupSwitch {
+        default: Label,
+        pairs: Vec<(i32, Label)>
+    }
+}
+
+impl Item {
+    /// The labels this item jumps to
+    fn targets(&self) -> Vec<Label> {
+        match self {
+            Item::Instruction(_) | Item::Label(_) => Vec::new(),
+            Item::Branch { target, .. } => vec![*target],
+            Item::TableSwitch {
+                default, targets, ..
+            } => std::iter::once(*default)
+                .chain(targets.iter().copied())
+                .collect(),
+            Item::LookupSwitch { default, pairs } => std::iter::once(*default)
+                .chain(pairs.iter().map(|(_, label)| *label))
+                .collect()
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Handler {
+    start: Label,
+    end: Label,
+    handler: Label,
+    catch_type: u16
+}
+
+/// Assembles a `Code` attribute from instructions and [`Label`]s.<br>
+/// Branches, switches and exception handlers refer to labels, which are resolved to offsets by
+/// [`CodeBuilder::build`]. A `goto` or `jsr` whose target is out of reach of a 16 bit offset is
+/// replaced by `goto_w` or `jsr_w`, a conditional branch by the inverted condition jumping over
+/// a `goto_w` to the target. `tableswitch` and `lookupswitch` are padded as required
+///
+/// ```no_run
+/// use classfmt::code_builder::CodeBuilder;
+/// use classfmt::opcode::{Instruction, Opcode};
+/// use classfmt::ConstantPoolBuilder;
+///
+/// let mut constant_pool = ConstantPoolBuilder::new();
+/// let mut code = CodeBuilder::new();
+/// let negative = code.new_label();
+///
+/// code.push(Instruction::iload_0);
+/// code.branch(Opcode::iflt, negative);
+/// code.push(Instruction::iconst_1);
+/// code.push(Instruction::ireturn);
+/// code.place(negative);
+/// code.push(Instruction::iconst_m1);
+/// code.push(Instruction::ireturn);
+///
+/// let attribute = code.build(&mut constant_pool, 1, 1)?;
+/// # Ok::<(), classfmt::error::ErrorType>(())
+/// ```
+#[derive(Debug)]
+pub struct CodeBuilder {
+    id: usize,
+    items: Vec<Item>,
+    labels: usize,
+    handlers: Vec<Handler>,
+    /// Whether a label of another builder was passed, reported by [`CodeBuilder::build`]
+    foreign_label: bool
+}
+
+impl Default for CodeBuilder {
+    fn default() -> CodeBuilder {
+        CodeBuilder {
+            id: NEXT_BUILDER.fetch_add(1, Ordering::Relaxed),
+            items: Vec::new(),
+            labels: 0,
+            handlers: Vec::new(),
+            foreign_label: false
+        }
+    }
+}
+
+impl CodeBuilder {
+    /// Creates a builder for empty code
+    pub fn new() -> CodeBuilder {
+        CodeBuilder::default()
+    }
+
+    /// Creates a label, which has to be placed exactly once before building
+    pub fn new_label(&mut self) -> Label {
+        self.labels += 1;
+        Label {
+            builder: self.id,
+            index: self.labels - 1
+        }
+    }
+
+    /// Binds `label` to the pc of the next instruction, or the end of the code if no
+    /// instruction follows
+    pub fn place(&mut self, label: Label) {
+        self.check(label);
+        self.items.push(Item::Label(label));
+    }
+
+    /// Appends an instruction. Its operands are kept as they are, offsets of branches and
+    /// switches are not adjusted
+    pub fn push(&mut self, instruction: Instruction) {
+        self.items.push(Item::Instruction(instruction));
+    }
+
+    /// Appends a branch to `target`, `opcode` is one of `goto`, `jsr` or a conditional branch.
+    /// Passing `goto_w` or `jsr_w` forces the wide form
+    pub fn branch(&mut self, opcode: Opcode, target: Label) {
+        self.check(target);
+        self.items.push(Item::Branch { opcode, target });
+    }
+
+    /// Appends a `tableswitch` jumping to `targets[i]` for the value `low + i` and to `default`
+    /// for all other values
+    pub fn tableswitch(&mut self, low: i32, default: Label, targets: Vec<Label>) {
+        self.check(default);
+
+        for &target in &targets {
+            self.check(target);
+        }
+
+        self.items.push(Item::TableSwitch {
+            low,
+            default,
+            targets
+        });
+    }
+
+    /// Appends a `lookupswitch`, the pairs of match values and targets are sorted as required
+    pub fn lookupswitch(&mut self, default: Label, mut pairs: Vec<(i32, Label)>) {
+        self.check(default);
+
+        for &(_, target) in &pairs {
+            self.check(target);
+        }
+
+        pairs.sort_by_key(|(value, _)| *value);
+        self.items.push(Item::LookupSwitch { default, pairs });
+    }
+
+    /// Adds an entry to the exception table, catching exceptions of the class at `catch_type`
+    /// thrown between `start` (inclusive) and `end` (exclusive). A `catch_type` of 0 catches all
+    /// exceptions.<br>
+    /// Entries keep the order they are added in, which is the order they are searched in
+    pub fn exception_handler(&mut self, start: Label, end: Label, handler: Label, catch_type: u16) {
+        self.check(start);
+        self.check(end);
+        self.check(handler);
+        self.handlers.push(Handler {
+            start,
+            end,
+            handler,
+            catch_type
+        });
+    }
+
+    /// Resolves all labels and returns the `Code` attribute, adding its name to `constant_pool`.
+    /// <br>
+    /// Fails if a label is not placed exactly once or was created by another builder, the code
+    /// is empty or exceeds 65535 bytes, or a branch, switch or exception handler is invalid
+    pub fn build<'c>(
+        self,
+        constant_pool: &mut ConstantPoolBuilder<'c>,
+        max_stack: u16,
+        max_locals: u16
+    ) -> Result<Attribute<'c>, ErrorType> {
+        let within = |e: ErrorType| e.within("code builder");
+
+        self.validate().map_err(within)?;
+        let layout = self.layout().map_err(within)?;
+
+        let mut code = Bytecode::default();
+        let mut pc = 0;
+
+        for (item, &wide) in self.items.into_iter().zip(layout.wide.iter()) {
+            let offset = |label: Label| layout.labels[label.index] as i32 - pc as i32;
+
+            match item {
+                Item::Instruction(instruction) => {
+                    let size = instruction.size(pc);
+                    code.push(pc, instruction);
+                    pc += size;
+                }
+                Item::Label(_) => {}
+                Item::Branch { opcode, target } => {
+                    let offset = offset(target);
+
+                    if !wide {
+                        code.push(pc, branch(opcode, offset));
+                        pc += 3;
+                    } else if let Some(wide_opcode) = widen(opcode) {
+                        code.push(pc, branch(wide_opcode, offset));
+                        pc += 5;
+                    } else {
+                        // The inverted condition jumps over the goto_w
+                        code.push(pc, branch(invert(opcode).unwrap(), 8));
+                        code.push(pc + 3, Instruction::goto_w { offset: offset - 3 });
+                        pc += 8;
+                    }
+                }
+                Item::TableSwitch {
+                    low,
+                    default,
+                    targets
+                } => {
+                    let instruction = Instruction::tableswitch {
+                        default: offset(default),
+                        low,
+                        high: low + targets.len() as i32 - 1,
+                        jump_offsets: targets.into_iter().map(offset).collect()
+                    };
+                    let size = instruction.size(pc);
+                    code.push(pc, instruction);
+                    pc += size;
+                }
+                Item::LookupSwitch { default, pairs } => {
+                    let instruction = Instruction::lookupswitch {
+                        default: offset(default),
+                        npairs: pairs.len() as i32,
+                        match_offset_pairs: pairs
+                            .into_iter()
+                            .map(|(value, target)| (value, offset(target)))
+                            .collect()
+                    };
+                    let size = instruction.size(pc);
+                    code.push(pc, instruction);
+                    pc += size;
+                }
+            }
+        }
+
+        let exception_table: Vec<_> = self
+            .handlers
+            .iter()
+            .map(|handler| ExceptionTableEntry {
+                start_pc: layout.labels[handler.start.index] as u16,
+                end_pc: layout.labels[handler.end.index] as u16,
+                handler_pc: layout.labels[handler.handler.index] as u16,
+                catch_type: handler.catch_type
+            })
+            .collect();
+
+        Ok(Attribute::Code {
+            attribute_name_index: constant_pool.utf8("Code")?,
+            attribute_length: 12 + pc + 8 * exception_table.len() as u32,
+            max_stack,
+            max_locals,
+            code_length: pc,
+            code,
+            exception_table_length: exception_table.len() as u16,
+            exception_table,
+            attributes_count: 0,
+            attributes: Vec::new()
+        })
+    }
+
+    /// Records whether `label` was created by another builder
+    fn check(&mut self, label: Label) {
+        self.foreign_label |= label.builder != self.id;
+    }
+
+    /// Checks everything that does not depend on the pcs of the instructions
+    fn validate(&self) -> Result<(), ErrorType> {
+        if self.foreign_label {
+            return Err(ErrorKind::Malformed("unknown label").into());
+        }
+
+        let mut placed = vec![false; self.labels];
+
+        for item in &self.items {
+            match item {
+                Item::Label(label) => {
+                    if placed[label.index] {
+                        return Err(ErrorKind::Malformed("label is placed more than once").into());
+                    }
+
+                    placed[label.index] = true;
+                }
+                Item::Branch { opcode, .. } => {
+                    if widen(*opcode).is_none() && invert(*opcode).is_none() {
+                        return Err(ErrorKind::Malformed("opcode is not a branch").into());
+                    }
+                }
+                Item::TableSwitch { low, targets, .. } => {
+                    if targets.is_empty() {
+                        return Err(ErrorKind::Malformed("tableswitch without targets").into());
+                    }
+
+                    if i32::try_from(targets.len() - 1)
+                        .ok()
+                        .and_then(|n| low.checked_add(n))
+                        .is_none()
+                    {
+                        return Err(ErrorKind::Malformed("tableswitch high exceeds i32").into());
+                    }
+                }
+                Item::LookupSwitch { pairs, .. } => {
+                    if pairs.windows(2).any(|w| w[0].0 == w[1].0) {
+                        return Err(ErrorKind::Malformed("duplicate lookupswitch match").into());
+                    }
+                }
+                Item::Instruction(_) => {}
+            }
+        }
+
+        if placed.contains(&false) {
+            return Err(ErrorKind::Malformed("label is never placed").into());
+        }
+
+        // Labels placed after the last instruction resolve to the end of the code
+        let end = self
+            .items
+            .iter()
+            .rposition(|item| !matches!(item, Item::Label(_)))
+            .map_or(0, |i| i + 1);
+        let mut at_end = vec![false; self.labels];
+
+        for item in &self.items[end..] {
+            if let Item::Label(label) = item {
+                at_end[label.index] = true;
+            }
+        }
+
+        if self
+            .items
+            .iter()
+            .flat_map(Item::targets)
+            .chain(self.handlers.iter().map(|handler| handler.handler))
+            .any(|target| at_end[target.index])
+        {
+            return Err(ErrorKind::Malformed("jump target is past the last instruction").into());
+        }
+
+        Ok(())
+    }
+
+    /// Computes the pc of every label, widening branches until all offsets fit.<br>
+    /// Widening only ever grows the code, so every pass widens at least one branch or is the last
+    fn layout(&self) -> Result<Layout, ErrorType> {
+        let mut wide: Vec<bool> = self
+            .items
+            .iter()
+            .map(|item| {
+                matches!(
+                    item,
+                    Item::Branch {
+                        opcode: Opcode::goto_w | Opcode::jsr_w,
+                        ..
+                    }
+                )
+            })
+            .collect();
+
+        loop {
+            let mut labels = vec![0; self.labels];
+            let mut pcs = Vec::with_capacity(self.items.len());
+            let mut pc = 0u32;
+
+            for (item, &wide) in self.items.iter().zip(wide.iter()) {
+                pcs.push(pc);
+
+                pc += match item {
+                    Item::Instruction(instruction) => instruction.size(pc),
+                    Item::Label(label) => {
+                        labels[label.index] = pc;
+                        0
+                    }
+                    Item::Branch { opcode, .. } => match (wide, widen(*opcode)) {
+                        (false, _) => 3,
+                        (true, Some(_)) => 5,
+                        (true, None) => 8
+                    },
+                    Item::TableSwitch { targets, .. } => {
+                        1 + (3 - pc % 4) + 12 + 4 * targets.len() as u32
+                    }
+                    Item::LookupSwitch { pairs, .. } => {
+                        1 + (3 - pc % 4) + 8 + 8 * pairs.len() as u32
+                    }
+                };
+
+                if pc > u16::MAX as u32 {
+                    return Err(ErrorKind::Malformed("code exceeds 65535 bytes").into());
+                }
+            }
+
+            let mut widened = false;
+
+            for (i, item) in self.items.iter().enumerate() {
+                if let Item::Branch { target, .. } = item {
+                    let offset = labels[target.index] as i32 - pcs[i] as i32;
+
+                    if !wide[i] && i16::try_from(offset).is_err() {
+                        wide[i] = true;
+                        widened = true;
+                    }
+                }
+            }
+
+            if widened {
+                continue;
+            }
+
+            if pc == 0 {
+                return Err(ErrorKind::Malformed("code is empty").into());
+            }
+
+            for handler in &self.handlers {
+                if labels[handler.start.index] >= labels[handler.end.index] {
+                    return Err(ErrorKind::Malformed("exception handler range is empty").into());
+                }
+            }
+
+            return Ok(Layout { labels, wide });
+        }
+    }
+}
+
+struct Layout {
+    labels: Vec<u32>,
+    wide: Vec<bool>
+}
+
+/// The wide form of an unconditional branch
+fn widen(opcode: Opcode) -> Option<Opcode> {
+    match opcode {
+        Opcode::goto | Opcode::goto_w => Some(Opcode::goto_w),
+        Opcode::jsr | Opcode::jsr_w => Some(Opcode::jsr_w),
+        _ => None
+    }
+}
+
+/// The conditional branch taken exactly when `opcode` is not taken
+fn invert(opcode: Opcode) -> Option<Opcode> {
+    Some(match opcode {
+        Opcode::ifeq => Opcode::ifne,
+        Opcode::ifne => Opcode::ifeq,
+        Opcode::iflt => Opcode::ifge,
+        Opcode::ifge => Opcode::iflt,
+        Opcode::ifgt => Opcode::ifle,
+        Opcode::ifle => Opcode::ifgt,
+        Opcode::if_icmpeq => Opcode::if_icmpne,
+        Opcode::if_icmpne => Opcode::if_icmpeq,
+        Opcode::if_icmplt => Opcode::if_icmpge,
+        Opcode::if_icmpge => Opcode::if_icmplt,
+        Opcode::if_icmpgt => Opcode::if_icmple,
+        Opcode::if_icmple => Opcode::if_icmpgt,
+        Opcode::if_acmpeq => Opcode::if_acmpne,
+        Opcode::if_acmpne => Opcode::if_acmpeq,
+        Opcode::ifnull => Opcode::ifnonnull,
+        Opcode::ifnonnull => Opcode::ifnull,
+        _ => return None
+    })
+}
+
+/// The branch instruction for `opcode`, offsets of the 16 bit forms have been checked by the
+/// layout
+fn branch(opcode: Opcode, offset: i32) -> Instruction {
+    let short = offset as i16;
+
+    match opcode {
+        Opcode::goto_w => Instruction::goto_w { offset },
+        Opcode::jsr_w => Instruction::jsr_w { offset },
+        Opcode::goto => Instruction::goto { offset: short },
+        Opcode::jsr => Instruction::jsr { offset: short },
+        Opcode::ifeq => Instruction::ifeq { offset: short },
+        Opcode::ifne => Instruction::ifne { offset: short },
+        Opcode::iflt => Instruction::iflt { offset: short },
+        Opcode::ifge => Instruction::ifge { offset: short },
+        Opcode::ifgt => Instruction::ifgt { offset: short },
+        Opcode::ifle => Instruction::ifle { offset: short },
+        Opcode::if_icmpeq => Instruction::if_icmpeq { offset: short },
+        Opcode::if_icmpne => Instruction::if_icmpne { offset: short },
+        Opcode::if_icmplt => Instruction::if_icmplt { offset: short },
+        Opcode::if_icmpge => Instruction::if_icmpge { offset: short },
+        Opcode::if_icmpgt => Instruction::if_icmpgt { offset: short },
+        Opcode::if_icmple => Instruction::if_icmple { offset: short },
+        Opcode::if_acmpeq => Instruction::if_acmpeq { offset: short },
+        Opcode::if_acmpne => Instruction::if_acmpne { offset: short },
+        Opcode::ifnull => Instruction::ifnull { offset: short },
+        Opcode::ifnonnull => Instruction::ifnonnull { offset: short },
+        _ => unreachable!("validated branch opcode")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CodeBuilder;
+    use crate::constant_pool::ConstantPoolBuilder;
+    use crate::error::ErrorKind;
+    use crate::opcode::{Instruction, Opcode};
+    use crate::test_util::expect_pat;
+    use crate::Attribute;
+
+    #[test]
+    fn build_code() {
+        let mut code = CodeBuilder::new();
+        let start = code.new_label();
+        let end = code.new_label();
+        let handler = code.new_label();
+        let case = code.new_label();
+        let exit = code.new_label();
+
+        code.place(start);
+        code.push(Instruction::iconst_0);
+        code.tableswitch(0, exit, vec![case, case]);
+        code.place(case);
+        code.push(Instruction::iconst_1);
+        code.lookupswitch(exit, vec![(7, case), (-1, end)]);
+        code.place(end);
+        code.branch(Opcode::goto, exit);
+        code.place(handler);
+        code.push(Instruction::athrow);
+        code.place(exit);
+        code.push(Instruction::r#eturn);
+        code.exception_handler(start, end, handler, 0);
+
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let attribute = code.build(&mut constant_pool, 1, 1).unwrap();
+        expect_pat!(Attribute::Code { code_length: 57, code, exception_table, .. }, &attribute, {
+            let pcs: Vec<_> = code.iter().map(|(pc, _)| pc).collect();
+            assert_eq!(pcs, [0, 1, 24, 25, 52, 55, 56]);
+
+            expect_pat!(Instruction::tableswitch { default: 55, low: 0, high: 1, jump_offsets }, &code[1], {
+                assert_eq!(jump_offsets, &[23, 23]);
+            });
+            expect_pat!(Instruction::lookupswitch { default: 31, npairs: 2, match_offset_pairs }, &code[3], {
+                assert_eq!(match_offset_pairs, &[(-1, 27), (7, -1)]);
+            });
+            expect_pat!(Instruction::goto { offset: 4 }, &code[4], {});
+
+            assert_eq!(exception_table.len(), 1);
+            assert_eq!(exception_table[0].start_pc, 0);
+            assert_eq!(exception_table[0].end_pc, 52);
+            assert_eq!(exception_table[0].handler_pc, 55);
+            assert_eq!(exception_table[0].catch_type, 0);
+        });
+
+        // branches beyond 32 KiB become goto_w or an inverted conditional skipping a goto_w
+        let mut code = CodeBuilder::new();
+        let top = code.new_label();
+        let far = code.new_label();
+
+        code.place(top);
+        code.push(Instruction::iconst_0);
+        code.branch(Opcode::ifeq, far);
+        code.branch(Opcode::goto, far);
+        code.branch(Opcode::goto, top);
+        for _ in 0..40000 {
+            code.push(Instruction::nop);
+        }
+        code.place(far);
+        code.push(Instruction::r#eturn);
+
+        let attribute = code.build(&mut constant_pool, 1, 0).unwrap();
+        expect_pat!(Attribute::Code { code_length: 40018, code, .. }, &attribute, {
+            expect_pat!(Instruction::ifne { offset: 8 }, &code[1], {});
+            expect_pat!(Instruction::goto_w { offset: 40013 }, &code[2], {});
+            expect_pat!(Instruction::goto_w { offset: 40008 }, &code[3], {});
+            expect_pat!(Instruction::goto { offset: -14 }, &code[4], {});
+            assert_eq!(code.pc(5), Some(17));
+        });
+
+        let mut code = CodeBuilder::new();
+        let label = code.new_label();
+        code.branch(Opcode::goto, label);
+        assert!(code.build(&mut constant_pool, 0, 0).is_err());
+
+        let mut code = CodeBuilder::new();
+        let label = code.new_label();
+        code.place(label);
+        code.branch(Opcode::nop, label);
+        assert!(code.build(&mut constant_pool, 0, 0).is_err());
+
+        // a label of another builder, out of range and in range of this builder's labels
+        let mut other = CodeBuilder::new();
+        let foreign = other.new_label();
+        let mut code = CodeBuilder::new();
+        code.branch(Opcode::goto, foreign);
+        assert!(code.build(&mut constant_pool, 0, 0).is_err());
+
+        let mut code = CodeBuilder::new();
+        let label = code.new_label();
+        code.place(label);
+        code.push(Instruction::nop);
+        code.branch(Opcode::goto, foreign);
+        let e = code.build(&mut constant_pool, 0, 0).unwrap_err();
+        expect_pat!(ErrorKind::Malformed("unknown label"), e.kind(), {});
+
+        // jumps to the end of the code, past the last instruction
+        let mut code = CodeBuilder::new();
+        let label = code.new_label();
+        code.branch(Opcode::goto, label);
+        code.place(label);
+        assert!(code.build(&mut constant_pool, 0, 0).is_err());
+
+        let mut code = CodeBuilder::new();
+        let (start, end) = (code.new_label(), code.new_label());
+        code.place(start);
+        code.push(Instruction::r#eturn);
+        code.place(end);
+        code.exception_handler(start, end, end, 0);
+        assert!(code.build(&mut constant_pool, 0, 0).is_err());
+
+        let mut code = CodeBuilder::new();
+        let (start, end) = (code.new_label(), code.new_label());
+        code.place(start);
+        code.push(Instruction::r#eturn);
+        code.place(end);
+        code.exception_handler(start, end, start, 0);
+        assert!(code.build(&mut constant_pool, 0, 0).is_ok());
+    }
+}
@@ -1,9 +1,12 @@
 //! Types representing constant pool elements
 
-use crate::error::ErrorType;
+use crate::error::{ErrorKind, ErrorType};
+use crate::mutf8;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum ConstantTag {
     Class = 7,
@@ -19,10 +22,13 @@ pub enum ConstantTag {
     Utf8 = 1,
     MethodHandle = 15,
     MethodType = 16,
-    InvokeDynamic = 18
+    Dynamic = 17,
+    InvokeDynamic = 18,
+    Module = 19,
+    Package = 20
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum ReferenceKind {
     GetField = 1,
@@ -54,8 +60,11 @@ impl TryFrom<u8> for ConstantTag {
             x if x == ConstantTag::Utf8 as u8 => Ok(ConstantTag::Utf8),
             x if x == ConstantTag::MethodHandle as u8 => Ok(ConstantTag::MethodHandle),
             x if x == ConstantTag::MethodType as u8 => Ok(ConstantTag::MethodType),
+            x if x == ConstantTag::Dynamic as u8 => Ok(ConstantTag::Dynamic),
             x if x == ConstantTag::InvokeDynamic as u8 => Ok(ConstantTag::InvokeDynamic),
-            _ => Err(ErrorType::IntegerConversion)
+            x if x == ConstantTag::Module as u8 => Ok(ConstantTag::Module),
+            x if x == ConstantTag::Package as u8 => Ok(ConstantTag::Package),
+            _ => Err(ErrorKind::InvalidConstantTag(v).into())
         }
     }
 }
@@ -74,14 +83,14 @@ impl TryFrom<u8> for ReferenceKind {
             x if x == ReferenceKind::InvokeSpecial as u8 => Ok(ReferenceKind::InvokeSpecial),
             x if x == ReferenceKind::NewInvokeSpecial as u8 => Ok(ReferenceKind::NewInvokeSpecial),
             x if x == ReferenceKind::InvokeInterface as u8 => Ok(ReferenceKind::InvokeInterface),
-            _ => Err(ErrorType::IntegerConversion)
+            _ => Err(ErrorKind::InvalidReferenceKind(v).into())
         }
     }
 }
 
 /// Represents a constant pool element<br/>
 /// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.4> for more information
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Constant<'c> {
     Class {
         tag: ConstantTag,
@@ -112,7 +121,7 @@ pub enum Constant<'c> {
     },
     Float {
         tag: ConstantTag,
-        bytes: &'c [u8]
+        bytes: [u8; 4]
     },
     Long {
         tag: ConstantTag,
@@ -132,7 +141,9 @@ pub enum Constant<'c> {
     Utf8 {
         tag: ConstantTag,
         length: u16,
-        bytes: &'c [u8]
+        /// Modified UTF-8 encoded string, borrowed from the class file unless the constant was
+        /// added by a [`ConstantPoolBuilder`]
+        bytes: Cow<'c, [u8]>
     },
     MethodHandle {
         tag: ConstantTag,
@@ -143,9 +154,651 @@ pub enum Constant<'c> {
         tag: ConstantTag,
         descriptor_index: u16
     },
+    Dynamic {
+        tag: ConstantTag,
+        bootstrap_method_attr_index: u16,
+        name_and_type_index: u16
+    },
     InvokeDynamic {
         tag: ConstantTag,
         bootstrap_method_attr_index: u16,
         name_index: u16
+    },
+    Module {
+        tag: ConstantTag,
+        name_index: u16
+    },
+    Package {
+        tag: ConstantTag,
+        name_index: u16
+    },
+    /// Occupies the slot following a `Long` or `Double` constant, which take up two entries
+    /// in the constant pool
+    Unusable
+}
+
+impl<'c> Constant<'c> {
+    /// Decodes the contents of a `Utf8` constant, fails for all other constants
+    pub fn as_str(&self) -> Result<Cow<'c, str>, ErrorType> {
+        match self {
+            Constant::Utf8 { bytes, .. } => decode(bytes),
+            _ => Err(ErrorKind::Malformed("expected a Utf8 constant").into())
+        }
+    }
+}
+
+/// A field or method reference resolved against the constant pool
+#[derive(Debug, PartialEq, Eq)]
+pub struct MemberRef<'c> {
+    /// The internal name of the class or interface declaring the member, e.g. `java/lang/String`
+    pub owner: Cow<'c, str>,
+    pub name: Cow<'c, str>,
+    /// The field descriptor of a field or the method descriptor of a method
+    pub descriptor: Cow<'c, str>
+}
+
+/// The constant pool of a class<br>
+/// Entries are addressed by their 1-based index as used throughout the class file. `Long` and
+/// `Double` constants occupy two indices, the second of which is [`Constant::Unusable`]
+#[derive(Debug, Default)]
+pub struct ConstantPool<'c> {
+    entries: Vec<Constant<'c>>
+}
+
+impl<'c> ConstantPool<'c> {
+    /// Appends a constant, followed by [`Constant::Unusable`] if it is a `Long` or `Double`
+    pub(crate) fn push(&mut self, constant: Constant<'c>) {
+        let wide = matches!(constant, Constant::Long { .. } | Constant::Double { .. });
+
+        self.entries.push(constant);
+
+        // Long and Double constants take up two slots in the constant pool
+        if wide {
+            self.entries.push(Constant::Unusable);
+        }
+    }
+
+    /// The number of slots in use, one less than `constant_pool_count`
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over all usable constants along with their index
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &Constant<'c>)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, constant)| !matches!(constant, Constant::Unusable))
+            .map(|(i, constant)| (i as u16 + 1, constant))
+    }
+
+    /// Looks up the constant at the given index
+    pub fn get(&self, index: u16) -> Result<&Constant<'c>, ErrorType> {
+        index
+            .checked_sub(1)
+            .and_then(|i| self.entries.get(i as usize))
+            .filter(|constant| !matches!(constant, Constant::Unusable))
+            .ok_or_else(|| ErrorKind::InvalidConstantPoolIndex(index).into())
+    }
+
+    /// Resolves the `Utf8` constant at the given index
+    pub fn utf8(&self, index: u16) -> Result<Cow<'c, str>, ErrorType> {
+        match self.get(index)? {
+            Constant::Utf8 { bytes, .. } => decode(bytes),
+            _ => Err(unexpected(index, ConstantTag::Utf8))
+        }
+    }
+
+    /// Resolves the internal name of the `Class` constant at the given index,
+    /// e.g. `java/lang/String`
+    pub fn class_name(&self, index: u16) -> Result<Cow<'c, str>, ErrorType> {
+        match self.get(index)? {
+            Constant::Class { name_index, .. } => self.utf8(*name_index),
+            _ => Err(unexpected(index, ConstantTag::Class))
+        }
+    }
+
+    /// Resolves the name and descriptor of the `NameAndType` constant at the given index
+    pub fn name_and_type(&self, index: u16) -> Result<(Cow<'c, str>, Cow<'c, str>), ErrorType> {
+        match self.get(index)? {
+            Constant::NameAndType {
+                name_index,
+                descriptor_index,
+                ..
+            } => Ok((self.utf8(*name_index)?, self.utf8(*descriptor_index)?)),
+            _ => Err(unexpected(index, ConstantTag::NameAndType))
+        }
+    }
+
+    /// Resolves the `Fieldref`, `Methodref` or `InterfaceMethodref` constant at the given index
+    pub fn member_ref(&self, index: u16) -> Result<MemberRef<'c>, ErrorType> {
+        let expected = match self.get(index)? {
+            Constant::Fieldref { .. } => ConstantTag::Fieldref,
+            Constant::InterfaceMethodref { .. } => ConstantTag::InterfaceMethodref,
+            _ => ConstantTag::Methodref
+        };
+
+        self.member_ref_of(index, expected)
+    }
+
+    /// Like [`member_ref`](ConstantPool::member_ref), but fails unless the constant is of the
+    /// `expected` kind. Methods are accepted as either `Methodref` or `InterfaceMethodref`
+    /// since `invokestatic` and `invokespecial` may refer to both
+    pub(crate) fn member_ref_of(
+        &self,
+        index: u16,
+        expected: ConstantTag
+    ) -> Result<MemberRef<'c>, ErrorType> {
+        let (class_index, name_and_type_index) = match (self.get(index)?, expected) {
+            (
+                Constant::Fieldref {
+                    class_index,
+                    name_and_type_index,
+                    ..
+                },
+                ConstantTag::Fieldref
+            )
+            | (
+                Constant::Methodref {
+                    class_index,
+                    name_and_type_index,
+                    ..
+                },
+                ConstantTag::Methodref
+            )
+            | (
+                Constant::Methodref {
+                    class_index,
+                    name_and_type_index,
+                    ..
+                },
+                ConstantTag::InterfaceMethodref
+            )
+            | (
+                Constant::InterfaceMethodref {
+                    class_index,
+                    name_and_type_index,
+                    ..
+                },
+                ConstantTag::Methodref
+            )
+            | (
+                Constant::InterfaceMethodref {
+                    class_index,
+                    name_and_type_index,
+                    ..
+                },
+                ConstantTag::InterfaceMethodref
+            ) => (*class_index, *name_and_type_index),
+            _ => return Err(unexpected(index, expected))
+        };
+
+        let owner = self.class_name(class_index)?;
+        let (name, descriptor) = self.name_and_type(name_and_type_index)?;
+
+        Ok(MemberRef {
+            owner,
+            name,
+            descriptor
+        })
+    }
+
+    /// Resolves the value of the `Integer` constant at the given index
+    pub fn integer(&self, index: u16) -> Result<i32, ErrorType> {
+        match self.get(index)? {
+            Constant::Integer { value, .. } => Ok(*value),
+            _ => Err(unexpected(index, ConstantTag::Integer))
+        }
+    }
+
+    /// Resolves the value of the `Float` constant at the given index
+    pub fn float(&self, index: u16) -> Result<f32, ErrorType> {
+        match self.get(index)? {
+            Constant::Float { bytes, .. } => Ok(f32::from_be_bytes(*bytes)),
+            _ => Err(unexpected(index, ConstantTag::Float))
+        }
+    }
+
+    /// Resolves the value of the `Long` constant at the given index
+    pub fn long(&self, index: u16) -> Result<i64, ErrorType> {
+        match self.get(index)? {
+            Constant::Long {
+                high_bytes,
+                low_bytes,
+                ..
+            } => Ok((((*high_bytes as u64) << 32) | *low_bytes as u64) as i64),
+            _ => Err(unexpected(index, ConstantTag::Long))
+        }
+    }
+
+    /// Resolves the value of the `Double` constant at the given index
+    pub fn double(&self, index: u16) -> Result<f64, ErrorType> {
+        match self.get(index)? {
+            Constant::Double {
+                high_bytes,
+                low_bytes,
+                ..
+            } => Ok(f64::from_bits(
+                ((*high_bytes as u64) << 32) | *low_bytes as u64
+            )),
+            _ => Err(unexpected(index, ConstantTag::Double))
+        }
+    }
+
+    /// Resolves the name of the `Module` constant at the given index
+    pub fn module_name(&self, index: u16) -> Result<Cow<'c, str>, ErrorType> {
+        match self.get(index)? {
+            Constant::Module { name_index, .. } => self.utf8(*name_index),
+            _ => Err(unexpected(index, ConstantTag::Module))
+        }
+    }
+
+    /// Resolves the internal name of the `Package` constant at the given index
+    pub fn package_name(&self, index: u16) -> Result<Cow<'c, str>, ErrorType> {
+        match self.get(index)? {
+            Constant::Package { name_index, .. } => self.utf8(*name_index),
+            _ => Err(unexpected(index, ConstantTag::Package))
+        }
+    }
+}
+
+/// Adds constants to a [`ConstantPool`] without duplicating existing entries<br>
+/// Every method returns the index of an equal constant if the pool contains one already and
+/// appends a new constant otherwise, along with the `Utf8`, `Class` and `NameAndType`
+/// constants it refers to
+///
+/// ```no_run
+/// # use classfmt::{ClassParser, ConstantPoolBuilder};
+/// # let bytes = std::fs::read("Main.class").unwrap();
+/// let mut class = ClassParser::from_bytes(&bytes).parse()?;
+/// let mut builder = ConstantPoolBuilder::from(std::mem::take(&mut class.constant_pool));
+///
+/// let println = builder.method_ref("java/io/PrintStream", "println", "(Ljava/lang/String;)V")?;
+/// let greeting = builder.string("Hello")?;
+///
+/// class.constant_pool = builder.build();
+/// # Ok::<(), classfmt::error::ErrorType>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct ConstantPoolBuilder<'c> {
+    constant_pool: ConstantPool<'c>,
+    indices: HashMap<Constant<'c>, u16>
+}
+
+impl<'c> From<ConstantPool<'c>> for ConstantPoolBuilder<'c> {
+    /// Seeds the builder with the constants of an existing pool, which keep their indices
+    fn from(constant_pool: ConstantPool<'c>) -> Self {
+        let mut indices = HashMap::with_capacity(constant_pool.len());
+
+        for (index, constant) in constant_pool.iter() {
+            indices.entry(constant.clone()).or_insert(index);
+        }
+
+        ConstantPoolBuilder {
+            constant_pool,
+            indices
+        }
+    }
+}
+
+impl<'c> ConstantPoolBuilder<'c> {
+    /// Creates a builder for an empty constant pool
+    pub fn new() -> ConstantPoolBuilder<'c> {
+        ConstantPoolBuilder::default()
+    }
+
+    /// The constants added so far
+    pub fn constant_pool(&self) -> &ConstantPool<'c> {
+        &self.constant_pool
+    }
+
+    /// Finishes building, returning the constant pool
+    pub fn build(self) -> ConstantPool<'c> {
+        self.constant_pool
+    }
+
+    /// Returns the index of a constant equal to `constant`, appending it if there is none.<br>
+    /// Fails if the pool has no room left, `Long` and `Double` constants occupy two slots
+    pub fn add(&mut self, constant: Constant<'c>) -> Result<u16, ErrorType> {
+        if let Some(&index) = self.indices.get(&constant) {
+            return Ok(index);
+        }
+
+        let slots = match constant {
+            Constant::Long { .. } | Constant::Double { .. } => 2,
+            Constant::Unusable => {
+                return Err(ErrorKind::Malformed("unusable constants can not be added").into())
+            }
+            _ => 1
+        };
+
+        // constant_pool_count is a u16 and one greater than the number of slots
+        if self.constant_pool.len() + slots >= u16::MAX as usize {
+            return Err(ErrorKind::Malformed("constant pool is full").into());
+        }
+
+        let index = self.constant_pool.len() as u16 + 1;
+
+        self.indices.insert(constant.clone(), index);
+        self.constant_pool.push(constant);
+        Ok(index)
+    }
+
+    /// Adds a `Utf8` constant
+    pub fn utf8(&mut self, s: &str) -> Result<u16, ErrorType> {
+        let bytes = mutf8::encode(s).into_owned();
+        let length = u16::try_from(bytes.len()).map_err(|_| {
+            ErrorType::from(ErrorKind::Malformed("Utf8 constant exceeds 65535 bytes"))
+        })?;
+
+        self.add(Constant::Utf8 {
+            tag: ConstantTag::Utf8,
+            length,
+            bytes: Cow::Owned(bytes)
+        })
+    }
+
+    /// Adds a `Class` constant for the given internal name, e.g. `java/lang/Object`
+    pub fn class(&mut self, name: &str) -> Result<u16, ErrorType> {
+        let name_index = self.utf8(name)?;
+
+        self.add(Constant::Class {
+            tag: ConstantTag::Class,
+            name_index
+        })
+    }
+
+    /// Adds a `String` constant
+    pub fn string(&mut self, s: &str) -> Result<u16, ErrorType> {
+        let string_index = self.utf8(s)?;
+
+        self.add(Constant::String {
+            tag: ConstantTag::String,
+            string_index
+        })
+    }
+
+    /// Adds an `Integer` constant
+    pub fn integer(&mut self, value: i32) -> Result<u16, ErrorType> {
+        self.add(Constant::Integer {
+            tag: ConstantTag::Integer,
+            value
+        })
+    }
+
+    /// Adds a `Float` constant. Floats are compared bitwise, so `0.0` and `-0.0` are distinct
+    /// while equal `NaN` values are deduplicated
+    pub fn float(&mut self, value: f32) -> Result<u16, ErrorType> {
+        self.add(Constant::Float {
+            tag: ConstantTag::Float,
+            bytes: value.to_be_bytes()
+        })
+    }
+
+    /// Adds a `Long` constant
+    pub fn long(&mut self, value: i64) -> Result<u16, ErrorType> {
+        self.add(Constant::Long {
+            tag: ConstantTag::Long,
+            high_bytes: (value as u64 >> 32) as u32,
+            low_bytes: value as u32
+        })
+    }
+
+    /// Adds a `Double` constant, compared bitwise like [`float`](ConstantPoolBuilder::float)
+    pub fn double(&mut self, value: f64) -> Result<u16, ErrorType> {
+        let bits = value.to_bits();
+
+        self.add(Constant::Double {
+            tag: ConstantTag::Double,
+            high_bytes: (bits >> 32) as u32,
+            low_bytes: bits as u32
+        })
+    }
+
+    /// Adds a `NameAndType` constant
+    pub fn name_and_type(&mut self, name: &str, descriptor: &str) -> Result<u16, ErrorType> {
+        let name_index = self.utf8(name)?;
+        let descriptor_index = self.utf8(descriptor)?;
+
+        self.add(Constant::NameAndType {
+            tag: ConstantTag::NameAndType,
+            name_index,
+            descriptor_index
+        })
+    }
+
+    /// Adds a `Fieldref` constant
+    pub fn field_ref(
+        &mut self,
+        owner: &str,
+        name: &str,
+        descriptor: &str
+    ) -> Result<u16, ErrorType> {
+        let (class_index, name_and_type_index) = self.member(owner, name, descriptor)?;
+
+        self.add(Constant::Fieldref {
+            tag: ConstantTag::Fieldref,
+            class_index,
+            name_and_type_index
+        })
+    }
+
+    /// Adds a `Methodref` constant
+    pub fn method_ref(
+        &mut self,
+        owner: &str,
+        name: &str,
+        descriptor: &str
+    ) -> Result<u16, ErrorType> {
+        let (class_index, name_and_type_index) = self.member(owner, name, descriptor)?;
+
+        self.add(Constant::Methodref {
+            tag: ConstantTag::Methodref,
+            class_index,
+            name_and_type_index
+        })
+    }
+
+    /// Adds an `InterfaceMethodref` constant
+    pub fn interface_method_ref(
+        &mut self,
+        owner: &str,
+        name: &str,
+        descriptor: &str
+    ) -> Result<u16, ErrorType> {
+        let (class_index, name_and_type_index) = self.member(owner, name, descriptor)?;
+
+        self.add(Constant::InterfaceMethodref {
+            tag: ConstantTag::InterfaceMethodref,
+            class_index,
+            name_and_type_index
+        })
+    }
+
+    fn member(
+        &mut self,
+        owner: &str,
+        name: &str,
+        descriptor: &str
+    ) -> Result<(u16, u16), ErrorType> {
+        Ok((self.class(owner)?, self.name_and_type(name, descriptor)?))
+    }
+
+    /// Adds a `MethodHandle` constant referring to the field or method reference at
+    /// `reference_index`
+    pub fn method_handle(
+        &mut self,
+        reference_kind: ReferenceKind,
+        reference_index: u16
+    ) -> Result<u16, ErrorType> {
+        self.add(Constant::MethodHandle {
+            tag: ConstantTag::MethodHandle,
+            reference_kind,
+            reference_index
+        })
+    }
+
+    /// Adds a `MethodType` constant
+    pub fn method_type(&mut self, descriptor: &str) -> Result<u16, ErrorType> {
+        let descriptor_index = self.utf8(descriptor)?;
+
+        self.add(Constant::MethodType {
+            tag: ConstantTag::MethodType,
+            descriptor_index
+        })
+    }
+
+    /// Adds a `Dynamic` constant, `bootstrap_method_attr_index` indexes into the
+    /// `BootstrapMethods` attribute
+    pub fn dynamic(
+        &mut self,
+        bootstrap_method_attr_index: u16,
+        name: &str,
+        descriptor: &str
+    ) -> Result<u16, ErrorType> {
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+
+        self.add(Constant::Dynamic {
+            tag: ConstantTag::Dynamic,
+            bootstrap_method_attr_index,
+            name_and_type_index
+        })
+    }
+
+    /// Adds an `InvokeDynamic` constant, `bootstrap_method_attr_index` indexes into the
+    /// `BootstrapMethods` attribute
+    pub fn invoke_dynamic(
+        &mut self,
+        bootstrap_method_attr_index: u16,
+        name: &str,
+        descriptor: &str
+    ) -> Result<u16, ErrorType> {
+        let name_index = self.name_and_type(name, descriptor)?;
+
+        self.add(Constant::InvokeDynamic {
+            tag: ConstantTag::InvokeDynamic,
+            bootstrap_method_attr_index,
+            name_index
+        })
+    }
+
+    /// Adds a `Module` constant
+    pub fn module(&mut self, name: &str) -> Result<u16, ErrorType> {
+        let name_index = self.utf8(name)?;
+
+        self.add(Constant::Module {
+            tag: ConstantTag::Module,
+            name_index
+        })
+    }
+
+    /// Adds a `Package` constant for the given internal name, e.g. `java/lang`
+    pub fn package(&mut self, name: &str) -> Result<u16, ErrorType> {
+        let name_index = self.utf8(name)?;
+
+        self.add(Constant::Package {
+            tag: ConstantTag::Package,
+            name_index
+        })
+    }
+}
+
+/// Decodes the bytes of a `Utf8` constant, borrowing from the class file if possible
+fn decode<'c>(bytes: &Cow<'c, [u8]>) -> Result<Cow<'c, str>, ErrorType> {
+    match bytes {
+        Cow::Borrowed(bytes) => mutf8::decode(bytes),
+        Cow::Owned(bytes) => mutf8::decode(bytes).map(|s| Cow::Owned(s.into_owned()))
+    }
+}
+
+fn unexpected(index: u16, expected: ConstantTag) -> ErrorType {
+    ErrorKind::UnexpectedConstant { index, expected }.into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Constant, ConstantPoolBuilder, MemberRef};
+    use crate::test_util::{expect_pat, read_class_file};
+    use crate::ClassParser;
+
+    #[test]
+    fn build_constant_pool() {
+        let buf = read_class_file("./tests/Hello.class").unwrap();
+        let mut class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let mut builder = ConstantPoolBuilder::from(std::mem::take(&mut class.constant_pool));
+        let len = builder.constant_pool().len();
+
+        // constants of the class are found instead of added again
+        assert_eq!(builder.class("java/lang/Object").unwrap(), class.super_class);
+        let hellow = builder.string("Hellow").unwrap();
+        let out = builder.field_ref("java/lang/System", "out", "Ljava/io/PrintStream;").unwrap();
+        let println = builder.method_ref("java/io/PrintStream", "println", "(Ljava/lang/String;)V").unwrap();
+        assert_eq!(builder.constant_pool().len(), len);
+        expect_pat!(Constant::String { string_index, .. }, builder.constant_pool().get(hellow).unwrap(), {
+            assert_eq!(builder.constant_pool().utf8(*string_index).unwrap(), "Hellow");
+        });
+        assert_eq!(builder.constant_pool().member_ref(out).unwrap().name, "out");
+        assert_eq!(builder.constant_pool().member_ref(println).unwrap().name, "println");
+
+        // a Fieldref is not a Methodref, but its Class and NameAndType are reused
+        let n = builder.method_ref("java/lang/System", "out", "Ljava/io/PrintStream;").unwrap();
+        assert_eq!(n as usize, len + 1);
+        assert_eq!(builder.constant_pool().len(), len + 1);
+
+        assert_eq!(builder.long(1 << 40).unwrap(), n + 1);
+        assert_eq!(builder.double(-0.5).unwrap(), n + 3);
+        assert_eq!(builder.float(f32::NAN).unwrap(), n + 5);
+        assert_eq!(builder.string("Bye \u{1f600}").unwrap(), n + 7);
+        assert_eq!(builder.long(1 << 40).unwrap(), n + 1);
+        assert_eq!(builder.float(f32::NAN).unwrap(), n + 5);
+        assert_eq!(builder.float(0.0).unwrap(), n + 8);
+        assert_eq!(builder.float(-0.0).unwrap(), n + 9);
+        assert_eq!(builder.interface_method_ref("java/util/List", "size", "()I").unwrap(), n + 15);
+        assert_eq!(builder.package("java/util").unwrap(), n + 17);
+        assert!(builder.add(Constant::Unusable).is_err());
+
+        class.constant_pool = builder.build();
+
+        let bytes = class.to_bytes().unwrap();
+        let class = ClassParser::from_bytes(&bytes)
+            .parse()
+            .unwrap();
+        let cp = &class.constant_pool;
+
+        assert_eq!(class.constant_pool_count, n + 18);
+        assert_eq!(cp.long(n + 1).unwrap(), 1 << 40);
+        expect_pat!(Err(_), cp.get(n + 2), {});
+        assert_eq!(cp.double(n + 3).unwrap(), -0.5);
+        assert!(cp.float(n + 5).unwrap().is_nan());
+        expect_pat!(Constant::String { string_index, .. }, cp.get(n + 7).unwrap(), {
+            assert_eq!(cp.utf8(*string_index).unwrap(), "Bye \u{1f600}");
+        });
+        assert_eq!(cp.member_ref(n + 15).unwrap(), MemberRef {
+            owner: "java/util/List".into(),
+            name: "size".into(),
+            descriptor: "()I".into()
+        });
+        assert_eq!(cp.package_name(n + 17).unwrap(), "java/util");
+
+        let mut builder = ConstantPoolBuilder::new();
+        assert_eq!(builder.class("A").unwrap(), 2);
+        assert_eq!(builder.utf8("A").unwrap(), 1);
+
+        for i in 0..32765 {
+            builder.long(i).unwrap();
+        }
+
+        assert_eq!(builder.constant_pool().len(), 65532);
+        assert_eq!(builder.integer(7).unwrap(), 65533);
+        expect_pat!(Err(_), builder.long(-1), {});
+        assert_eq!(builder.integer(-1).unwrap(), 65534);
+        expect_pat!(Err(_), builder.integer(-2), {});
     }
 }
@@ -0,0 +1,363 @@
+//! Parser for the field and method descriptors referenced by fields, methods and member
+//! references
+//!
+//! A field descriptor like `[Ljava/lang/String;` describes the type of a field, local variable or
+//! parameter, a method descriptor like `(IJ)V` the parameter types and the return type of a
+//! method.<br>
+//! See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.3> for more information
+
+use std::fmt;
+
+use crate::error::{ErrorKind, ErrorType};
+
+/// A primitive type
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BaseType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean
+}
+
+impl BaseType {
+    pub(crate) fn from_char(c: u8) -> Option<BaseType> {
+        Some(match c {
+            b'B' => BaseType::Byte,
+            b'C' => BaseType::Char,
+            b'D' => BaseType::Double,
+            b'F' => BaseType::Float,
+            b'I' => BaseType::Int,
+            b'J' => BaseType::Long,
+            b'S' => BaseType::Short,
+            b'Z' => BaseType::Boolean,
+            _ => return None
+        })
+    }
+
+    /// The character representing this type in a descriptor
+    pub fn descriptor(self) -> char {
+        match self {
+            BaseType::Byte => 'B',
+            BaseType::Char => 'C',
+            BaseType::Double => 'D',
+            BaseType::Float => 'F',
+            BaseType::Int => 'I',
+            BaseType::Long => 'J',
+            BaseType::Short => 'S',
+            BaseType::Boolean => 'Z'
+        }
+    }
+
+    /// The keyword of this type in Java source code, e.g. `int`
+    pub fn java_name(self) -> &'static str {
+        match self {
+            BaseType::Byte => "byte",
+            BaseType::Char => "char",
+            BaseType::Double => "double",
+            BaseType::Float => "float",
+            BaseType::Int => "int",
+            BaseType::Long => "long",
+            BaseType::Short => "short",
+            BaseType::Boolean => "boolean"
+        }
+    }
+}
+
+/// An instance of a class or interface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectType<'d> {
+    /// The binary name in internal form, e.g. `java/lang/String`
+    pub class_name: &'d str
+}
+
+/// An array, whose component type may be an array itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayType<'d> {
+    pub component_type: Box<FieldType<'d>>
+}
+
+impl<'d> ArrayType<'d> {
+    /// The number of dimensions, e.g. 2 for `[[I`
+    pub fn dimensions(&self) -> u8 {
+        match self.component_type.as_ref() {
+            FieldType::Array(array) => array.dimensions().saturating_add(1),
+            _ => 1
+        }
+    }
+
+    /// The innermost component type, which is not an array
+    pub fn element_type(&self) -> &FieldType<'d> {
+        match self.component_type.as_ref() {
+            FieldType::Array(array) => array.element_type(),
+            element_type => element_type
+        }
+    }
+}
+
+/// The type of a field, local variable or parameter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType<'d> {
+    Base(BaseType),
+    Object(ObjectType<'d>),
+    Array(ArrayType<'d>)
+}
+
+impl<'d> FieldType<'d> {
+    /// Parses a field descriptor
+    pub fn parse(descriptor: &'d str) -> Result<FieldType<'d>, ErrorType> {
+        let within = |e: ErrorType| e.within(format!("field descriptor {}", descriptor));
+        let mut parser = DescriptorParser { descriptor, pos: 0 };
+
+        let field_type = parser.field_type().map_err(within)?;
+        parser.expect_end().map_err(within)?;
+
+        Ok(field_type)
+    }
+
+    /// The number of local variable slots occupied by a value of this type, i.e. 2 for `long` and
+    /// `double` and 1 for every other type
+    pub fn slot_size(&self) -> u16 {
+        match self {
+            FieldType::Base(BaseType::Long) | FieldType::Base(BaseType::Double) => 2,
+            _ => 1
+        }
+    }
+
+    /// Formats the type as it would appear in Java source code, e.g. `java.lang.String[]`.<br>
+    /// Nested classes keep their binary name, e.g. `java.util.Map$Entry`
+    pub fn to_java_string(&self) -> String {
+        match self {
+            FieldType::Base(base_type) => base_type.java_name().to_owned(),
+            FieldType::Object(object_type) => object_type.class_name.replace('/', "."),
+            FieldType::Array(array_type) => {
+                let mut s = array_type.component_type.to_java_string();
+                s.push_str("[]");
+                s
+            }
+        }
+    }
+}
+
+impl fmt::Display for FieldType<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Base(base_type) => write!(f, "{}", base_type.descriptor()),
+            FieldType::Object(object_type) => write!(f, "L{};", object_type.class_name),
+            FieldType::Array(array_type) => write!(f, "[{}", array_type.component_type)
+        }
+    }
+}
+
+/// The parameter types and the return type of a method
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodDescriptor<'d> {
+    pub params: Vec<FieldType<'d>>,
+    /// The return type, `None` if the method returns `void`
+    pub ret: Option<FieldType<'d>>
+}
+
+impl<'d> MethodDescriptor<'d> {
+    /// Parses a method descriptor
+    pub fn parse(descriptor: &'d str) -> Result<MethodDescriptor<'d>, ErrorType> {
+        let within = |e: ErrorType| e.within(format!("method descriptor {}", descriptor));
+        let mut parser = DescriptorParser { descriptor, pos: 0 };
+
+        let method_descriptor = parser.method_descriptor().map_err(within)?;
+        parser.expect_end().map_err(within)?;
+
+        Ok(method_descriptor)
+    }
+
+    /// The number of local variable slots occupied by the parameters.<br>
+    /// Instance methods receive `this` in an additional slot in front of the parameters
+    pub fn param_slots(&self) -> u16 {
+        self.params.iter().map(FieldType::slot_size).sum()
+    }
+
+    /// Formats the descriptor as a Java method declaration with the given name,
+    /// e.g. `void main(java.lang.String[])`
+    pub fn to_java_string(&self, name: &str) -> String {
+        let ret = match &self.ret {
+            Some(ret) => ret.to_java_string(),
+            None => "void".to_owned()
+        };
+        let params: Vec<_> = self.params.iter().map(FieldType::to_java_string).collect();
+
+        format!("{} {}({})", ret, name, params.join(", "))
+    }
+}
+
+impl fmt::Display for MethodDescriptor<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+
+        for param in &self.params {
+            write!(f, "{}", param)?;
+        }
+
+        match &self.ret {
+            Some(ret) => write!(f, "){}", ret),
+            None => write!(f, ")V")
+        }
+    }
+}
+
+struct DescriptorParser<'d> {
+    descriptor: &'d str,
+    pos: usize
+}
+
+impl<'d> DescriptorParser<'d> {
+    fn peek(&self) -> Option<u8> {
+        self.descriptor.as_bytes().get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: u8, reason: &'static str) -> Result<(), ErrorType> {
+        if self.peek() != Some(c) {
+            return Err(ErrorKind::Malformed(reason).into());
+        }
+
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn expect_end(&self) -> Result<(), ErrorType> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(ErrorKind::Malformed("trailing characters after descriptor").into())
+        }
+    }
+
+    fn field_type(&mut self) -> Result<FieldType<'d>, ErrorType> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Err(ErrorKind::Malformed("incomplete descriptor").into())
+        };
+        self.pos += 1;
+
+        if let Some(base_type) = BaseType::from_char(c) {
+            return Ok(FieldType::Base(base_type));
+        }
+
+        match c {
+            b'L' => {
+                let rest = &self.descriptor[self.pos..];
+                let len = rest.find(';').ok_or(ErrorKind::Malformed(
+                    "unterminated class name in descriptor"
+                ))?;
+                let class_name = &rest[..len];
+
+                if class_name.is_empty() || class_name.contains(&['.', '['][..]) {
+                    return Err(ErrorKind::Malformed("invalid class name in descriptor").into());
+                }
+
+                self.pos += len + 1;
+                Ok(FieldType::Object(ObjectType { class_name }))
+            }
+            b'[' => {
+                let mut dimensions = 1;
+
+                while self.peek() == Some(b'[') {
+                    self.pos += 1;
+                    dimensions += 1;
+                }
+
+                if dimensions > 255 {
+                    return Err(
+                        ErrorKind::Malformed("array type with more than 255 dimensions").into()
+                    );
+                }
+
+                let mut field_type = self.field_type()?;
+
+                for _ in 0..dimensions {
+                    field_type = FieldType::Array(ArrayType {
+                        component_type: Box::new(field_type)
+                    });
+                }
+
+                Ok(field_type)
+            }
+            _ => Err(ErrorKind::Malformed("invalid type in descriptor").into())
+        }
+    }
+
+    fn method_descriptor(&mut self) -> Result<MethodDescriptor<'d>, ErrorType> {
+        self.expect(b'(', "method descriptor does not start with '('")?;
+
+        let mut params = Vec::new();
+
+        while self.peek() != Some(b')') {
+            params.push(self.field_type()?);
+        }
+
+        self.pos += 1;
+
+        let ret = match self.peek() {
+            Some(b'V') => {
+                self.pos += 1;
+                None
+            }
+            _ => Some(self.field_type()?)
+        };
+
+        Ok(MethodDescriptor { params, ret })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BaseType, FieldType, MethodDescriptor, ObjectType};
+    use crate::error::ErrorKind;
+    use crate::test_util::expect_pat;
+
+    #[test]
+    fn parse() {
+        let descriptor = "(Ljava/lang/String;I)Ljava/util/List;";
+        let method = MethodDescriptor::parse(descriptor).unwrap();
+
+        assert_eq!(method.params, vec![
+            FieldType::Object(ObjectType {
+                class_name: "java/lang/String"
+            }),
+            FieldType::Base(BaseType::Int)
+        ]);
+        assert_eq!(method.ret.as_ref().unwrap().to_string(), "Ljava/util/List;");
+        assert_eq!(method.to_string(), descriptor);
+        assert_eq!(method.to_java_string("repeat"), "java.util.List repeat(java.lang.String, int)");
+        assert_eq!(method.param_slots(), 2);
+
+        let method = MethodDescriptor::parse("(J[[DZ)V").unwrap();
+        assert!(method.ret.is_none());
+        assert_eq!(method.param_slots(), 4);
+        assert_eq!(method.to_string(), "(J[[DZ)V");
+        assert_eq!(method.to_java_string("run"), "void run(long, double[][], boolean)");
+
+        expect_pat!(FieldType::Array(array), &method.params[1], {
+            assert_eq!(array.dimensions(), 2);
+            assert_eq!(array.element_type(), &FieldType::Base(BaseType::Double));
+        });
+
+        let field = FieldType::parse("[Ljava/util/Map$Entry;").unwrap();
+        assert_eq!(field.to_java_string(), "java.util.Map$Entry[]");
+        assert_eq!(field.slot_size(), 1);
+
+        for invalid in &["", "V", "Q", "Ljava/lang/String", "L;", "Ljava.lang.String;", "II"] {
+            assert!(FieldType::parse(invalid).is_err(), "{}", invalid);
+        }
+
+        for invalid in &["", "I", "(I", "(V)V", "()", "()VV", "(I)Ljava/lang/Object"] {
+            assert!(MethodDescriptor::parse(invalid).is_err(), "{}", invalid);
+        }
+
+        let e = MethodDescriptor::parse("(X)V").unwrap_err();
+        expect_pat!(ErrorKind::Malformed("invalid type in descriptor"), e.kind(), {});
+        assert_eq!(e.context(), ["method descriptor (X)V"]);
+        assert!(FieldType::parse(&format!("{}I", "[".repeat(256))).is_err());
+        assert!(FieldType::parse(&format!("{}I", "[".repeat(255))).is_ok());
+    }
+}
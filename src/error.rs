@@ -1,15 +1,139 @@
+use std::error::Error;
+use std::fmt;
 use std::str::Utf8Error;
 
+use crate::constant_pool::ConstantTag;
+
+/// The cause of an [`ErrorType`]
+#[derive(Debug)]
+pub enum ErrorKind {
+    /// The input ended before the structure being parsed was complete
+    UnexpectedEof,
+    /// The input does not start with `0xCAFEBABE`
+    InvalidMagic(u32),
+    /// Unknown constant pool tag
+    InvalidConstantTag(u8),
+    /// Unknown `reference_kind` of a `MethodHandle` constant
+    InvalidReferenceKind(u8),
+    /// Unknown or reserved opcode
+    InvalidOpcode(u8),
+    /// A constant pool index is zero, out of range or points at the second slot of a `Long` or
+    /// `Double` constant
+    InvalidConstantPoolIndex(u16),
+    /// The constant at the given index does not have the expected tag
+    UnexpectedConstant {
+        index: u16,
+        expected: ConstantTag
+    },
+    /// A structure violates a constraint of the class file format
+    Malformed(&'static str),
+    Utf8Error(Utf8Error)
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::UnexpectedEof => write!(f, "unexpected end of input"),
+            ErrorKind::InvalidMagic(magic) => write!(f, "invalid magic number {:#010x}", magic),
+            ErrorKind::InvalidConstantTag(tag) => write!(f, "invalid constant tag {}", tag),
+            ErrorKind::InvalidReferenceKind(kind) => write!(f, "invalid reference kind {}", kind),
+            ErrorKind::InvalidOpcode(opcode) => write!(f, "invalid opcode {:#04x}", opcode),
+            ErrorKind::InvalidConstantPoolIndex(index) => {
+                write!(f, "invalid constant pool index {}", index)
+            }
+            ErrorKind::UnexpectedConstant { index, expected } => {
+                write!(f, "expected {:?} constant at index {}", expected, index)
+            }
+            ErrorKind::Malformed(reason) => write!(f, "{}", reason),
+            ErrorKind::Utf8Error(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+/// An error which occurred while parsing a class file.<br>
+/// Carries the cause, the byte offset at which it was detected (if known) and the
+/// structures which were being parsed, e.g. `method #3 attribute Code`
 #[derive(Debug)]
-pub enum ErrorType {
-    ParseError,
-    InvalidNameIndex,
-    Utf8Error(Utf8Error),
-    IntegerConversion
+pub struct ErrorType {
+    kind: ErrorKind,
+    offset: Option<usize>,
+    context: Vec<String>
+}
+
+impl ErrorType {
+    pub(crate) fn at(kind: ErrorKind, offset: usize) -> Self {
+        ErrorType {
+            kind,
+            offset: Some(offset),
+            context: Vec::new()
+        }
+    }
+
+    /// Sets the offset, unless one has been recorded already
+    pub(crate) fn or_offset(mut self, offset: usize) -> Self {
+        self.offset.get_or_insert(offset);
+        self
+    }
+
+    /// Records that the error occurred while parsing `structure`
+    pub(crate) fn within<S: Into<String>>(mut self, structure: S) -> Self {
+        self.context.insert(0, structure.into());
+        self
+    }
+
+    /// Returns the cause of the error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Returns the byte offset in the class file at which the error was detected
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// Returns the structures being parsed when the error occurred, outermost first
+    pub fn context(&self) -> &[String] {
+        &self.context
+    }
+}
+
+impl fmt::Display for ErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {:#x}", offset)?;
+        }
+
+        if !self.context.is_empty() {
+            write!(f, " while parsing {}", self.context.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ErrorType {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Utf8Error(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<ErrorKind> for ErrorType {
+    fn from(kind: ErrorKind) -> Self {
+        ErrorType {
+            kind,
+            offset: None,
+            context: Vec::new()
+        }
+    }
 }
 
 impl From<Utf8Error> for ErrorType {
     fn from(e: Utf8Error) -> Self {
-        Self::Utf8Error(e)
+        ErrorKind::Utf8Error(e).into()
     }
 }
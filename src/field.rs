@@ -4,10 +4,12 @@ use crate::attribute::Attribute;
 /// Describes a field <br>
 /// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.5> for more information
 #[derive(Debug)]
-pub struct Field {
+pub struct Field<'c> {
     pub access_flags: FieldAccessFlag,
+    /// See [`RawClass::undefined_access_flags`](crate::RawClass::undefined_access_flags)
+    pub undefined_access_flags: u16,
     pub name_index: u16,
     pub descriptor_index: u16,
     pub attributes_count: u16,
-    pub attributes: Vec<Attribute>
+    pub attributes: Vec<Attribute<'c>>
 }
@@ -0,0 +1,1018 @@
+//! Computation of `StackMapTable` frames by type inference over the bytecode
+//!
+//! The types of all local variables and operand stack entries are propagated along every path
+//! through the code. Where paths join, reference types are merged into their common superclass
+//! as resolved by a [`ClassHierarchy`]. A frame is emitted at every branch target, exception
+//! handler and instruction following an unconditional jump, which is where the verifier
+//! expects one
+
+use std::collections::BTreeSet;
+
+use crate::access_flags::MethodAccessFlag;
+use crate::analysis::{self, falls_through, index_of, jump_targets, local_access, stack_effect};
+use crate::attribute::{Attribute, ExceptionTableEntry};
+use crate::bytecode::Bytecode;
+use crate::constant_pool::{Constant, ConstantPool, ConstantPoolBuilder};
+use crate::descriptor::{BaseType, FieldType, MethodDescriptor};
+use crate::error::{ErrorKind, ErrorType};
+use crate::hierarchy::ClassHierarchy;
+use crate::opcode::{Instruction, Opcode};
+use crate::stack_map::{compress_frames, Frame, StackMapFrame, VerificationTypeInfo};
+use crate::Method;
+
+const OBJECT: &str = "java/lang/Object";
+
+/// Computes the entries of the `StackMapTable` of `method`, which is declared by the class
+/// `class_name`. Class constants for the types in the frames are added to `constant_pool`.<br>
+/// Returns no entries for methods without code or without any branches.<br>
+/// Fails for code containing `jsr` or `ret`, which can not be described by frames, and for
+/// unreachable code
+pub fn compute_frames<'c>(
+    method: &Method<'c>,
+    class_name: &str,
+    constant_pool: &mut ConstantPoolBuilder<'c>,
+    hierarchy: &dyn ClassHierarchy
+) -> Result<Vec<StackMapFrame>, ErrorType> {
+    let code = method
+        .attributes
+        .iter()
+        .find_map(|attribute| match attribute {
+            Attribute::Code {
+                code,
+                exception_table,
+                ..
+            } => Some((code, exception_table)),
+            _ => None
+        });
+    let (code, exception_table) = match code {
+        Some(code) => code,
+        None => return Ok(Vec::new())
+    };
+
+    let (initial, frames) = {
+        let cp = constant_pool.constant_pool();
+        let name = cp.utf8(method.name_index)?;
+        let descriptor = cp.utf8(method.descriptor_index)?;
+        let descriptor = MethodDescriptor::parse(&descriptor)?;
+        let is_static = method.access_flags.contains(MethodAccessFlag::STATIC);
+
+        let inference = Inference {
+            code,
+            exception_table,
+            constant_pool: cp,
+            class_name,
+            hierarchy
+        };
+        let initial = inference.initial_state(&name, &descriptor, is_static)?;
+        let frames = inference.run(initial.clone())?;
+
+        (initial, frames)
+    };
+
+    let initial_locals = encode(&initial.locals, true, constant_pool)?;
+    let frames = frames
+        .into_iter()
+        .map(|(pc, state)| {
+            Ok(Frame {
+                offset: pc as u16,
+                locals: encode(&state.locals, true, constant_pool)?,
+                stack: encode(&state.stack, false, constant_pool)?
+            })
+        })
+        .collect::<Result<Vec<_>, ErrorType>>()?;
+
+    compress_frames(&initial_locals, &frames)
+}
+
+/// The type of a local variable or operand stack slot. `long` and `double` values occupy two
+/// slots, the second of which is `Top`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Type {
+    Top,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    /// The result of the `new` instruction at the given pc
+    Uninitialized(u16),
+    /// A class by its internal name or an array by its descriptor, like in `Class` constants
+    Reference(String)
+}
+
+impl Type {
+    fn from_field_type(field_type: &FieldType<'_>) -> Type {
+        match field_type {
+            FieldType::Base(BaseType::Float) => Type::Float,
+            FieldType::Base(BaseType::Long) => Type::Long,
+            FieldType::Base(BaseType::Double) => Type::Double,
+            FieldType::Base(_) => Type::Integer,
+            FieldType::Object(object_type) => Type::Reference(object_type.class_name.to_owned()),
+            FieldType::Array(_) => Type::Reference(field_type.to_string())
+        }
+    }
+
+    fn is_wide(&self) -> bool {
+        matches!(self, Type::Long | Type::Double)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct State {
+    locals: Vec<Type>,
+    stack: Vec<Type>
+}
+
+impl State {
+    fn push(&mut self, value: Type) {
+        if value.is_wide() {
+            self.stack.push(value);
+            self.stack.push(Type::Top);
+        } else {
+            self.stack.push(value);
+        }
+    }
+
+    fn pop(&mut self, slots: u16) -> Result<(), ErrorType> {
+        let len = self
+            .stack
+            .len()
+            .checked_sub(slots as usize)
+            .ok_or(ErrorKind::Malformed("operand stack underflow"))?;
+
+        self.stack.truncate(len);
+        Ok(())
+    }
+
+    fn pop_value(&mut self) -> Result<Type, ErrorType> {
+        self.stack
+            .pop()
+            .ok_or_else(|| ErrorKind::Malformed("operand stack underflow").into())
+    }
+
+    /// Replaces the topmost `count` slots by the slots at the given positions among them,
+    /// counted from the bottom
+    fn shuffle(&mut self, count: usize, order: &[usize]) -> Result<(), ErrorType> {
+        let len = self
+            .stack
+            .len()
+            .checked_sub(count)
+            .ok_or(ErrorKind::Malformed("operand stack underflow"))?;
+        let values = self.stack.split_off(len);
+
+        self.stack.extend(order.iter().map(|&i| values[i].clone()));
+        Ok(())
+    }
+
+    fn local(&self, index: usize) -> Result<Type, ErrorType> {
+        self.locals
+            .get(index)
+            .cloned()
+            .ok_or_else(|| ErrorKind::Malformed("local variable index out of range").into())
+    }
+
+    fn set_local(&mut self, index: usize, value: Type) -> Result<(), ErrorType> {
+        let slots = if value.is_wide() { 2 } else { 1 };
+
+        if index + slots > self.locals.len() {
+            return Err(ErrorKind::Malformed("local variable index out of range").into());
+        }
+
+        // Overwriting the second slot of a long or double invalidates the first one
+        if index > 0 && self.locals[index - 1].is_wide() {
+            self.locals[index - 1] = Type::Top;
+        }
+
+        if slots == 2 {
+            self.locals[index + 1] = Type::Top;
+        }
+
+        self.locals[index] = value;
+        Ok(())
+    }
+
+    /// Replaces every occurrence of an uninitialized type once its constructor was invoked
+    fn initialize(&mut self, uninitialized: &Type, initialized: Type) {
+        for value in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+            if value == uninitialized {
+                *value = initialized.clone();
+            }
+        }
+    }
+}
+
+struct Inference<'a, 'c> {
+    code: &'a Bytecode,
+    exception_table: &'a [ExceptionTableEntry],
+    constant_pool: &'a ConstantPool<'c>,
+    class_name: &'a str,
+    hierarchy: &'a dyn ClassHierarchy
+}
+
+impl<'a, 'c> Inference<'a, 'c> {
+    fn initial_state(
+        &self,
+        name: &str,
+        descriptor: &MethodDescriptor<'_>,
+        is_static: bool
+    ) -> Result<State, ErrorType> {
+        let max_locals = analysis::max_locals(self.code, descriptor, is_static)?;
+        let mut state = State {
+            locals: vec![Type::Top; max_locals as usize],
+            stack: Vec::new()
+        };
+        let mut index = 0;
+
+        if !is_static {
+            let this = if name == "<init>" && self.class_name != OBJECT {
+                Type::UninitializedThis
+            } else {
+                Type::Reference(self.class_name.to_owned())
+            };
+
+            state.set_local(0, this)?;
+            index += 1;
+        }
+
+        for param in &descriptor.params {
+            state.set_local(index, Type::from_field_type(param))?;
+            index += param.slot_size() as usize;
+        }
+
+        Ok(state)
+    }
+
+    /// Propagates `initial` through the code, returning the states at the pcs which require a
+    /// frame
+    fn run(&self, initial: State) -> Result<Vec<(u32, State)>, ErrorType> {
+        let mut states: Vec<Option<State>> = vec![None; self.code.len()];
+        let mut pending = Vec::new();
+
+        if self.code.is_empty() {
+            return Err(ErrorKind::Malformed("code is empty").into());
+        }
+
+        states[0] = Some(initial);
+        pending.push(0);
+
+        while let Some(index) = pending.pop() {
+            let instruction = &self.code[index];
+            let pc = self.code.pc(index).unwrap();
+            let state = states[index].clone().unwrap();
+            let within = |e: ErrorType| e.within(format!("instruction at pc {}", pc));
+
+            for entry in self.exception_table {
+                if u32::from(entry.start_pc) <= pc && pc < u32::from(entry.end_pc) {
+                    let catch_type = match entry.catch_type {
+                        0 => "java/lang/Throwable".to_owned(),
+                        index => self.constant_pool.class_name(index)?.into_owned()
+                    };
+                    let handler = State {
+                        locals: state.locals.clone(),
+                        stack: vec![Type::Reference(catch_type)]
+                    };
+                    let target = index_of(self.code, u32::from(entry.handler_pc))?;
+
+                    self.merge_into(&mut states, &mut pending, target, handler)
+                        .map_err(within)?;
+                }
+            }
+
+            let mut next = state;
+            self.execute(&mut next, instruction, pc).map_err(within)?;
+
+            for target in jump_targets(self.code, instruction, pc).map_err(within)? {
+                self.merge_into(&mut states, &mut pending, target, next.clone())
+                    .map_err(within)?;
+            }
+
+            if falls_through(instruction) {
+                if index + 1 == self.code.len() {
+                    return Err(
+                        ErrorKind::Malformed("execution falls off the end of the code").into()
+                    );
+                }
+
+                self.merge_into(&mut states, &mut pending, index + 1, next)
+                    .map_err(within)?;
+            }
+        }
+
+        if let Some(index) = states.iter().position(Option::is_none) {
+            let pc = self.code.pc(index).unwrap();
+            return Err(ErrorType::from(ErrorKind::Malformed(
+                "unreachable code can not be described by stack map frames"
+            ))
+            .within(format!("instruction at pc {}", pc)));
+        }
+
+        let mut points = BTreeSet::new();
+
+        for (index, (pc, instruction)) in self.code.iter().enumerate() {
+            points.extend(jump_targets(self.code, instruction, pc)?);
+
+            if !falls_through(instruction) && index + 1 < self.code.len() {
+                points.insert(index + 1);
+            }
+        }
+
+        for entry in self.exception_table {
+            points.insert(index_of(self.code, u32::from(entry.handler_pc))?);
+        }
+
+        Ok(points
+            .into_iter()
+            .map(|index| (self.code.pc(index).unwrap(), states[index].take().unwrap()))
+            .collect())
+    }
+
+    fn merge_into(
+        &self,
+        states: &mut [Option<State>],
+        pending: &mut Vec<usize>,
+        index: usize,
+        state: State
+    ) -> Result<(), ErrorType> {
+        let merged = match &states[index] {
+            None => state,
+            Some(existing) => {
+                let merged = self.merge(existing, &state)?;
+
+                if &merged == existing {
+                    return Ok(());
+                }
+
+                merged
+            }
+        };
+
+        states[index] = Some(merged);
+        pending.push(index);
+        Ok(())
+    }
+
+    fn merge(&self, a: &State, b: &State) -> Result<State, ErrorType> {
+        if a.stack.len() != b.stack.len() {
+            return Err(ErrorKind::Malformed("inconsistent operand stack depth").into());
+        }
+
+        let locals = a
+            .locals
+            .iter()
+            .zip(b.locals.iter())
+            .map(|(a, b)| Ok(self.merge_types(a, b)?.unwrap_or(Type::Top)))
+            .collect::<Result<_, ErrorType>>()?;
+        let stack = a
+            .stack
+            .iter()
+            .zip(b.stack.iter())
+            .map(|(a, b)| {
+                self.merge_types(a, b)?.ok_or_else(|| {
+                    ErrorKind::Malformed("incompatible types on the operand stack").into()
+                })
+            })
+            .collect::<Result<_, ErrorType>>()?;
+
+        Ok(State { locals, stack })
+    }
+
+    /// The type assignable from both `a` and `b`, `None` if there is none besides `Top`
+    fn merge_types(&self, a: &Type, b: &Type) -> Result<Option<Type>, ErrorType> {
+        Ok(match (a, b) {
+            (a, b) if a == b => Some(a.clone()),
+            (Type::Null, Type::Reference(_)) => Some(b.clone()),
+            (Type::Reference(_), Type::Null) => Some(a.clone()),
+            (Type::Reference(a), Type::Reference(b)) => {
+                Some(Type::Reference(self.merge_references(a, b)?))
+            }
+            _ => None
+        })
+    }
+
+    fn merge_references(&self, a: &str, b: &str) -> Result<String, ErrorType> {
+        if a == b {
+            return Ok(a.to_owned());
+        }
+
+        match (a.strip_prefix('['), b.strip_prefix('[')) {
+            (Some(a), Some(b)) => match (component_class(a), component_class(b)) {
+                (Some(a), Some(b)) => {
+                    let merged = self.merge_references(a, b)?;
+
+                    Ok(if merged.starts_with('[') {
+                        format!("[{}", merged)
+                    } else {
+                        format!("[L{};", merged)
+                    })
+                }
+                _ => Ok(OBJECT.to_owned())
+            },
+            (None, None) => self.hierarchy.common_super_class(a, b),
+            _ => Ok(OBJECT.to_owned())
+        }
+    }
+
+    fn execute(
+        &self,
+        state: &mut State,
+        instruction: &Instruction,
+        pc: u32
+    ) -> Result<(), ErrorType> {
+        if let Some((index, _)) = local_access(instruction) {
+            return self.execute_local(state, instruction, index as usize);
+        }
+
+        match instruction {
+            Instruction::jsr { .. } | Instruction::jsr_w { .. } => {
+                return Err(
+                    ErrorKind::Malformed("jsr can not be described by stack map frames").into()
+                )
+            }
+            Instruction::aconst_null => state.push(Type::Null),
+            Instruction::dup => state.shuffle(1, &[0, 0])?,
+            Instruction::dup_x1 => state.shuffle(2, &[1, 0, 1])?,
+            Instruction::dup_x2 => state.shuffle(3, &[2, 0, 1, 2])?,
+            Instruction::dup2 => state.shuffle(2, &[0, 1, 0, 1])?,
+            Instruction::dup2_x1 => state.shuffle(3, &[1, 2, 0, 1, 2])?,
+            Instruction::dup2_x2 => state.shuffle(4, &[2, 3, 0, 1, 2, 3])?,
+            Instruction::swap => state.shuffle(2, &[1, 0])?,
+            Instruction::aaload => {
+                state.pop(1)?;
+                let component = match state.pop_value()? {
+                    Type::Null => Type::Null,
+                    Type::Reference(array) => array
+                        .strip_prefix('[')
+                        .and_then(component_class)
+                        .map(|class| Type::Reference(class.to_owned()))
+                        .ok_or(ErrorKind::Malformed("aaload from a non-reference array"))?,
+                    _ => return Err(ErrorKind::Malformed("aaload from a non-array").into())
+                };
+                state.push(component);
+            }
+            Instruction::getstatic { cp_index } | Instruction::getfield { cp_index } => {
+                if let Instruction::getfield { .. } = instruction {
+                    state.pop(1)?;
+                }
+
+                let descriptor = self.constant_pool.member_ref(*cp_index)?.descriptor;
+                state.push(Type::from_field_type(&FieldType::parse(&descriptor)?));
+            }
+            Instruction::invokestatic { cp_index }
+            | Instruction::invokevirtual { cp_index }
+            | Instruction::invokespecial { cp_index }
+            | Instruction::invokeinterface { cp_index, .. } => {
+                let member = self.constant_pool.member_ref(*cp_index)?;
+                let descriptor = MethodDescriptor::parse(&member.descriptor)?;
+                state.pop(descriptor.param_slots())?;
+
+                if !matches!(instruction, Instruction::invokestatic { .. }) {
+                    let receiver = state.pop_value()?;
+
+                    if let (Instruction::invokespecial { .. }, "<init>") =
+                        (instruction, member.name.as_ref())
+                    {
+                        let initialized = self.initialized_type(&receiver)?;
+                        state.initialize(&receiver, initialized);
+                    }
+                }
+
+                if let Some(ret) = &descriptor.ret {
+                    state.push(Type::from_field_type(ret));
+                }
+            }
+            Instruction::invokedynamic { cp_index } => {
+                let descriptor = match self.constant_pool.get(*cp_index)? {
+                    Constant::InvokeDynamic { name_index, .. } => {
+                        self.constant_pool.name_and_type(*name_index)?.1
+                    }
+                    _ => {
+                        return Err(
+                            ErrorKind::Malformed("invokedynamic without InvokeDynamic").into()
+                        )
+                    }
+                };
+                let descriptor = MethodDescriptor::parse(&descriptor)?;
+                state.pop(descriptor.param_slots())?;
+
+                if let Some(ret) = &descriptor.ret {
+                    state.push(Type::from_field_type(ret));
+                }
+            }
+            Instruction::new { .. } => state.push(Type::Uninitialized(pc as u16)),
+            Instruction::checkcast { cp_index } => {
+                state.pop(1)?;
+                state.push(Type::Reference(self.class_name(*cp_index)?));
+            }
+            Instruction::anewarray { cp_index } => {
+                state.pop(1)?;
+
+                let component = self.class_name(*cp_index)?;
+                state.push(Type::Reference(if component.starts_with('[') {
+                    format!("[{}", component)
+                } else {
+                    format!("[L{};", component)
+                }));
+            }
+            Instruction::multianewarray {
+                cp_index,
+                dimensions
+            } => {
+                state.pop(u16::from(*dimensions))?;
+                state.push(Type::Reference(self.class_name(*cp_index)?));
+            }
+            Instruction::newarray { atype } => {
+                let array = match atype {
+                    4 => "[Z",
+                    5 => "[C",
+                    6 => "[F",
+                    7 => "[D",
+                    8 => "[B",
+                    9 => "[S",
+                    10 => "[I",
+                    11 => "[J",
+                    _ => return Err(ErrorKind::Malformed("invalid newarray type").into())
+                };
+
+                state.pop(1)?;
+                state.push(Type::Reference(array.to_owned()));
+            }
+            Instruction::ldc { .. } | Instruction::ldc_w { .. } | Instruction::ldc2_w { .. } => {
+                let cp_index = instruction.cp_index().unwrap();
+                state.push(self.constant_type(cp_index)?);
+            }
+            _ => {
+                let (pop, _) = stack_effect(instruction, self.constant_pool)?;
+                state.pop(pop)?;
+
+                if let Some(result) = result_type(instruction) {
+                    state.push(result);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn execute_local(
+        &self,
+        state: &mut State,
+        instruction: &Instruction,
+        index: usize
+    ) -> Result<(), ErrorType> {
+        let opcode = match instruction {
+            Instruction::wide { opcode, .. } => *opcode,
+            _ => instruction.opcode()
+        };
+
+        match opcode {
+            Opcode::iload
+            | Opcode::iload_0
+            | Opcode::iload_1
+            | Opcode::iload_2
+            | Opcode::iload_3 => state.push(Type::Integer),
+            Opcode::fload
+            | Opcode::fload_0
+            | Opcode::fload_1
+            | Opcode::fload_2
+            | Opcode::fload_3 => state.push(Type::Float),
+            Opcode::lload
+            | Opcode::lload_0
+            | Opcode::lload_1
+            | Opcode::lload_2
+            | Opcode::lload_3 => state.push(Type::Long),
+            Opcode::dload
+            | Opcode::dload_0
+            | Opcode::dload_1
+            | Opcode::dload_2
+            | Opcode::dload_3 => state.push(Type::Double),
+            Opcode::aload
+            | Opcode::aload_0
+            | Opcode::aload_1
+            | Opcode::aload_2
+            | Opcode::aload_3 => {
+                let value = state.local(index)?;
+                state.push(value);
+            }
+            Opcode::istore
+            | Opcode::istore_0
+            | Opcode::istore_1
+            | Opcode::istore_2
+            | Opcode::istore_3 => {
+                state.pop(1)?;
+                state.set_local(index, Type::Integer)?;
+            }
+            Opcode::fstore
+            | Opcode::fstore_0
+            | Opcode::fstore_1
+            | Opcode::fstore_2
+            | Opcode::fstore_3 => {
+                state.pop(1)?;
+                state.set_local(index, Type::Float)?;
+            }
+            Opcode::lstore
+            | Opcode::lstore_0
+            | Opcode::lstore_1
+            | Opcode::lstore_2
+            | Opcode::lstore_3 => {
+                state.pop(2)?;
+                state.set_local(index, Type::Long)?;
+            }
+            Opcode::dstore
+            | Opcode::dstore_0
+            | Opcode::dstore_1
+            | Opcode::dstore_2
+            | Opcode::dstore_3 => {
+                state.pop(2)?;
+                state.set_local(index, Type::Double)?;
+            }
+            Opcode::astore
+            | Opcode::astore_0
+            | Opcode::astore_1
+            | Opcode::astore_2
+            | Opcode::astore_3 => {
+                let value = state.pop_value()?;
+                state.set_local(index, value)?;
+            }
+            Opcode::ret => {
+                return Err(
+                    ErrorKind::Malformed("ret can not be described by stack map frames").into()
+                )
+            }
+            // iinc leaves an int in the local variable
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// The type of an object once its constructor was invoked
+    fn initialized_type(&self, receiver: &Type) -> Result<Type, ErrorType> {
+        match receiver {
+            Type::UninitializedThis => Ok(Type::Reference(self.class_name.to_owned())),
+            Type::Uninitialized(offset) => match self.code.at(u32::from(*offset)) {
+                Some(Instruction::new { cp_index }) => {
+                    Ok(Type::Reference(self.class_name(*cp_index)?))
+                }
+                _ => Err(ErrorKind::Malformed("uninitialized type does not refer to new").into())
+            },
+            _ => Err(ErrorKind::Malformed("constructor invoked on an initialized object").into())
+        }
+    }
+
+    fn class_name(&self, cp_index: u16) -> Result<String, ErrorType> {
+        Ok(self.constant_pool.class_name(cp_index)?.into_owned())
+    }
+
+    /// The type of a constant loaded by `ldc`, `ldc_w` or `ldc2_w`
+    fn constant_type(&self, cp_index: u16) -> Result<Type, ErrorType> {
+        let class = |name: &str| Type::Reference(name.to_owned());
+
+        Ok(match self.constant_pool.get(cp_index)? {
+            Constant::Integer { .. } => Type::Integer,
+            Constant::Float { .. } => Type::Float,
+            Constant::Long { .. } => Type::Long,
+            Constant::Double { .. } => Type::Double,
+            Constant::String { .. } => class("java/lang/String"),
+            Constant::Class { .. } => class("java/lang/Class"),
+            Constant::MethodType { .. } => class("java/lang/invoke/MethodType"),
+            Constant::MethodHandle { .. } => class("java/lang/invoke/MethodHandle"),
+            Constant::Dynamic {
+                name_and_type_index,
+                ..
+            } => {
+                let (_, descriptor) = self.constant_pool.name_and_type(*name_and_type_index)?;
+                Type::from_field_type(&FieldType::parse(&descriptor)?)
+            }
+            _ => return Err(ErrorKind::Malformed("ldc of a constant which is not loadable").into())
+        })
+    }
+}
+
+/// The class of the components of an array with the given component descriptor, `None` for
+/// arrays of primitives
+fn component_class(component: &str) -> Option<&str> {
+    if component.starts_with('[') {
+        Some(component)
+    } else {
+        component.strip_prefix('L')?.strip_suffix(';')
+    }
+}
+
+/// The type pushed by instructions whose result only depends on the opcode
+fn result_type(instruction: &Instruction) -> Option<Type> {
+    match instruction {
+        Instruction::iconst_m1
+        | Instruction::iconst_0
+        | Instruction::iconst_1
+        | Instruction::iconst_2
+        | Instruction::iconst_3
+        | Instruction::iconst_4
+        | Instruction::iconst_5
+        | Instruction::bipush { .. }
+        | Instruction::sipush { .. }
+        | Instruction::iaload
+        | Instruction::baload
+        | Instruction::caload
+        | Instruction::saload
+        | Instruction::iadd
+        | Instruction::isub
+        | Instruction::imul
+        | Instruction::idiv
+        | Instruction::irem
+        | Instruction::ineg
+        | Instruction::ishl
+        | Instruction::ishr
+        | Instruction::iushr
+        | Instruction::iand
+        | Instruction::ior
+        | Instruction::ixor
+        | Instruction::l2i
+        | Instruction::f2i
+        | Instruction::d2i
+        | Instruction::i2b
+        | Instruction::i2c
+        | Instruction::i2s
+        | Instruction::lcmp
+        | Instruction::fcmpl
+        | Instruction::fcmpg
+        | Instruction::dcmpl
+        | Instruction::dcmpg
+        | Instruction::arraylength
+        | Instruction::instanceof { .. } => Some(Type::Integer),
+        Instruction::fconst_0
+        | Instruction::fconst_1
+        | Instruction::fconst_2
+        | Instruction::faload
+        | Instruction::fadd
+        | Instruction::fsub
+        | Instruction::fmul
+        | Instruction::fdiv
+        | Instruction::frem
+        | Instruction::fneg
+        | Instruction::i2f
+        | Instruction::l2f
+        | Instruction::d2f => Some(Type::Float),
+        Instruction::lconst_0
+        | Instruction::lconst_1
+        | Instruction::laload
+        | Instruction::ladd
+        | Instruction::lsub
+        | Instruction::lmul
+        | Instruction::ldiv
+        | Instruction::lrem
+        | Instruction::lneg
+        | Instruction::lshl
+        | Instruction::lshr
+        | Instruction::lushr
+        | Instruction::land
+        | Instruction::lor
+        | Instruction::lxor
+        | Instruction::i2l
+        | Instruction::f2l
+        | Instruction::d2l => Some(Type::Long),
+        Instruction::dconst_0
+        | Instruction::dconst_1
+        | Instruction::daload
+        | Instruction::dadd
+        | Instruction::dsub
+        | Instruction::dmul
+        | Instruction::ddiv
+        | Instruction::drem
+        | Instruction::dneg
+        | Instruction::i2d
+        | Instruction::l2d
+        | Instruction::f2d => Some(Type::Double),
+        _ => None
+    }
+}
+
+/// Encodes the slots of locals or the operand stack as verification types, `long` and `double`
+/// taking up a single entry. Trailing `Top` locals are omitted
+fn encode(
+    slots: &[Type],
+    is_locals: bool,
+    constant_pool: &mut ConstantPoolBuilder<'_>
+) -> Result<Vec<VerificationTypeInfo>, ErrorType> {
+    let mut types = Vec::with_capacity(slots.len());
+    let mut i = 0;
+
+    while i < slots.len() {
+        types.push(match &slots[i] {
+            Type::Top => VerificationTypeInfo::Top,
+            Type::Integer => VerificationTypeInfo::Integer,
+            Type::Float => VerificationTypeInfo::Float,
+            Type::Long => VerificationTypeInfo::Long,
+            Type::Double => VerificationTypeInfo::Double,
+            Type::Null => VerificationTypeInfo::Null,
+            Type::UninitializedThis => VerificationTypeInfo::UninitializedThis,
+            Type::Uninitialized(offset) => VerificationTypeInfo::Uninitialized { offset: *offset },
+            Type::Reference(class) => VerificationTypeInfo::Object {
+                cpool_index: constant_pool.class(class)?
+            }
+        });
+        i += if slots[i].is_wide() { 2 } else { 1 };
+    }
+
+    if is_locals {
+        while types.last() == Some(&VerificationTypeInfo::Top) {
+            types.pop();
+        }
+    }
+
+    Ok(types)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::access_flags::MethodAccessFlag;
+    use crate::constant_pool::ConstantPoolBuilder;
+    use crate::error::ErrorType;
+    use crate::hierarchy::{ClassHierarchy, ClassSet};
+    use crate::opcode::{Instruction, Opcode};
+    use crate::stack_map::{compress_frames, expand_frames, StackMapFrame, VerificationTypeInfo};
+    use crate::test_util::{expect_pat, read_class_file};
+    use crate::{Attribute, ClassParser, CodeBuilder, Method};
+    use std::fs;
+
+    #[test]
+    fn compute_frames() {
+        // treats every class as a direct subclass of java/lang/Object
+        struct Flat;
+
+        impl ClassHierarchy for Flat {
+            fn super_class(&self, class: &str) -> Result<Option<String>, ErrorType> {
+                Ok(Some("java/lang/Object".to_owned()).filter(|_| class != "java/lang/Object"))
+            }
+
+            fn is_interface(&self, _: &str) -> Result<bool, ErrorType> {
+                Ok(false)
+            }
+        }
+
+        fn stack_map_table<'a>(method: &'a Method<'_>) -> Option<&'a Vec<StackMapFrame>> {
+            method.attributes.iter().find_map(|attribute| match attribute {
+                Attribute::Code { attributes, .. } => attributes.iter().find_map(|attribute| match attribute {
+                    Attribute::StackMapTable { entries, .. } => Some(entries),
+                    _ => None
+                }),
+                _ => None
+            })
+        }
+
+        fn offsets(entries: &[StackMapFrame]) -> Vec<u16> {
+            expand_frames(&[], entries).unwrap().iter().map(|frame| frame.offset).collect()
+        }
+
+        let names = ["Frames", "Frames$Animal", "Frames$Dog", "Frames$Cat"];
+        let buffers: Vec<_> = names
+            .iter()
+            .map(|name| read_class_file(&format!("./tests/{}.class", name)).unwrap())
+            .collect();
+        let classes: Vec<_> = buffers
+            .iter()
+            .map(|buf| ClassParser::from_bytes(buf).parse().unwrap())
+            .collect();
+        let set = ClassSet::from_classes(&classes).unwrap();
+
+        assert_eq!(set.common_super_class("Frames$Dog", "Frames$Cat").unwrap(), "Frames$Animal");
+        assert_eq!(set.common_super_class("Frames$Dog", "Frames").unwrap(), "java/lang/Object");
+        assert_eq!(set.super_class("java/lang/Object").unwrap(), None);
+        assert!(set.super_class("java/lang/String").is_err());
+
+        let mut class = ClassParser::from_bytes(&buffers[0]).parse().unwrap();
+        class.compute_frames(&set).unwrap();
+
+        let bytes = class.to_bytes().unwrap();
+        let class = ClassParser::from_bytes(&bytes).parse().unwrap();
+        let view = class.view();
+
+        for (original, method) in classes[0].methods.iter().zip(class.methods.iter()) {
+            let name = view.constant_pool().utf8(method.name_index).unwrap();
+            let expected = stack_map_table(original);
+            let actual = stack_map_table(method);
+
+            match name.as_ref() {
+                // javac chops the loop variable once it goes out of scope
+                "count" => assert_eq!(offsets(actual.unwrap()), offsets(expected.unwrap())),
+                _ => assert_eq!(actual, expected, "{}", name)
+            }
+        }
+
+        // uninitialized objects on the stack and dog or cat merged into Animal
+        let pick = class
+            .methods
+            .iter()
+            .find(|method| view.constant_pool().utf8(method.name_index).unwrap() == "pick")
+            .unwrap();
+        let new_dog = pick
+            .attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::Code { code, .. } => code.iter().find(|(_, instruction)| matches!(instruction, Instruction::new { .. })),
+                _ => None
+            })
+            .map(|(pc, _)| pc)
+            .unwrap();
+        expect_pat!(Some(entries), stack_map_table(pick), {
+            let stacks: Vec<_> = entries
+                .iter()
+                .filter_map(|entry| match entry {
+                    StackMapFrame::Full { stack, .. } => Some(stack.as_slice()),
+                    StackMapFrame::SameLocals1StackItem { stack, .. } => Some(std::slice::from_ref(stack)),
+                    _ => None
+                })
+                .collect();
+            let uninitialized = VerificationTypeInfo::Uninitialized { offset: new_dog as u16 };
+            assert!(stacks.contains(&[uninitialized; 2].as_ref()));
+            assert!(stacks.iter().any(|stack| match stack {
+                [VerificationTypeInfo::Object { cpool_index }] => class.constant_pool.class_name(*cpool_index).unwrap() == "Frames$Animal",
+                _ => false
+            }));
+        });
+
+        // frames are needed at the same offsets as in the fixtures compiled by javac
+        let dirs = ["./tests", "./tests/module", "./tests/module/com/example/app"];
+
+        for dir in dirs.iter() {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+
+                if path.extension().and_then(|ext| ext.to_str()) != Some("class") {
+                    continue;
+                }
+
+                let buf = read_class_file(path.to_str().unwrap()).unwrap();
+                let original = ClassParser::from_bytes(&buf).parse().unwrap();
+                let mut class = ClassParser::from_bytes(&buf).parse().unwrap();
+                class.compute_frames(&Flat).unwrap();
+
+                for (original, method) in original.methods.iter().zip(class.methods.iter()) {
+                    let expected = stack_map_table(original).map(|entries| offsets(entries));
+                    let actual = stack_map_table(method).map(|entries| offsets(entries));
+                    assert_eq!(actual, expected, "{}", path.display());
+
+                    if let Some(entries) = stack_map_table(original) {
+                        let frames = expand_frames(&[], entries).unwrap();
+                        assert_eq!(&compress_frames(&[], &frames).unwrap(), entries);
+                    }
+                }
+            }
+        }
+
+        // a loop assembled by CodeBuilder
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let mut code = CodeBuilder::new();
+        let head = code.new_label();
+        let exit = code.new_label();
+
+        code.push(Instruction::iconst_0);
+        code.push(Instruction::istore_1);
+        code.place(head);
+        code.push(Instruction::iload_1);
+        code.push(Instruction::iload_0);
+        code.branch(Opcode::if_icmpge, exit);
+        code.push(Instruction::iinc { index: 1, r#const: 1 });
+        code.branch(Opcode::goto, head);
+        code.place(exit);
+        code.push(Instruction::iload_1);
+        code.push(Instruction::ireturn);
+
+        let mut method = Method {
+            access_flags: MethodAccessFlag::STATIC,
+            undefined_access_flags: 0,
+            name_index: constant_pool.utf8("loop").unwrap(),
+            descriptor_index: constant_pool.utf8("(I)I").unwrap(),
+            attributes_count: 1,
+            attributes: vec![code.build(&mut constant_pool, 2, 2).unwrap()]
+        };
+        method.compute_frames("Loop", &mut constant_pool, &Flat).unwrap();
+
+        expect_pat!(Some(entries), stack_map_table(&method), {
+            assert_eq!(entries, &vec![
+                StackMapFrame::Append { frame_type: 252, offset_delta: 2, locals: vec![VerificationTypeInfo::Integer] },
+                StackMapFrame::Same { frame_type: 10 }
+            ]);
+        });
+
+        // subroutines can not be described by frames
+        let mut code = CodeBuilder::new();
+        let subroutine = code.new_label();
+        code.branch(Opcode::jsr, subroutine);
+        code.push(Instruction::r#eturn);
+        code.place(subroutine);
+        code.push(Instruction::astore_0);
+        code.push(Instruction::ret { index: 0 });
+
+        method.attributes = vec![code.build(&mut constant_pool, 1, 1).unwrap()];
+        assert!(method.compute_frames("Loop", &mut constant_pool, &Flat).is_err());
+
+        // jumps before the start of the code
+        let mut code = CodeBuilder::new();
+        code.push(Instruction::nop);
+        code.push(Instruction::goto { offset: -5 });
+
+        method.attributes = vec![code.build(&mut constant_pool, 0, 1).unwrap()];
+        assert!(method.compute_frames("Loop", &mut constant_pool, &Flat).is_err());
+    }
+}
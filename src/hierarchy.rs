@@ -0,0 +1,117 @@
+//! Superclass lookups needed to merge reference types where control flow joins
+
+use std::collections::{HashMap, HashSet};
+
+use crate::access_flags::ClassAccessFlag;
+use crate::error::{ErrorKind, ErrorType};
+use crate::RawClass;
+
+/// Resolves the superclasses of classes by their internal name, e.g. `java/lang/String`
+pub trait ClassHierarchy {
+    /// The internal name of the direct superclass of `class`, `None` for `java/lang/Object`.
+    /// <br>
+    /// Fails if the class is unknown
+    fn super_class(&self, class: &str) -> Result<Option<String>, ErrorType>;
+
+    /// Whether `class` is an interface, fails if the class is unknown
+    fn is_interface(&self, class: &str) -> Result<bool, ErrorType>;
+
+    /// The most specific superclass shared by `a` and `b`.<br>
+    /// Like the verifier, interfaces are treated as `java/lang/Object`
+    fn common_super_class(&self, a: &str, b: &str) -> Result<String, ErrorType> {
+        if a == b {
+            return Ok(a.to_owned());
+        }
+
+        if self.is_interface(a)? || self.is_interface(b)? {
+            return Ok(OBJECT.to_owned());
+        }
+
+        let mut supers = HashSet::new();
+        let mut class = Some(a.to_owned());
+
+        while let Some(name) = class {
+            class = self.super_class(&name)?;
+
+            if !supers.insert(name) {
+                return Err(ErrorKind::Malformed("cyclic class hierarchy").into());
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut class = Some(b.to_owned());
+
+        while let Some(name) = class {
+            if supers.contains(&name) {
+                return Ok(name);
+            }
+
+            class = self.super_class(&name)?;
+
+            if !visited.insert(name) {
+                return Err(ErrorKind::Malformed("cyclic class hierarchy").into());
+            }
+        }
+
+        Ok(OBJECT.to_owned())
+    }
+}
+
+const OBJECT: &str = "java/lang/Object";
+
+/// A [`ClassHierarchy`] of the classes it was given, along with `java/lang/Object`.<br>
+/// Looking up any other class fails
+#[derive(Debug, Default)]
+pub struct ClassSet {
+    classes: HashMap<String, (Option<String>, bool)>
+}
+
+impl ClassSet {
+    /// Creates a set containing only `java/lang/Object`
+    pub fn new() -> ClassSet {
+        ClassSet::default()
+    }
+
+    /// Creates a set containing `classes`
+    pub fn from_classes(classes: &[RawClass<'_>]) -> Result<ClassSet, ErrorType> {
+        let mut set = ClassSet::new();
+
+        for class in classes {
+            set.insert(class)?;
+        }
+
+        Ok(set)
+    }
+
+    /// Adds `class`, replacing a class of the same name
+    pub fn insert(&mut self, class: &RawClass<'_>) -> Result<(), ErrorType> {
+        let name = class.constant_pool.class_name(class.this_class)?;
+        let super_class = match class.super_class {
+            0 => None,
+            index => Some(class.constant_pool.class_name(index)?.into_owned())
+        };
+        let is_interface = class.access_flags.contains(ClassAccessFlag::INTERFACE);
+
+        self.classes
+            .insert(name.into_owned(), (super_class, is_interface));
+        Ok(())
+    }
+
+    fn get(&self, class: &str) -> Result<(Option<String>, bool), ErrorType> {
+        match self.classes.get(class) {
+            Some(entry) => Ok(entry.clone()),
+            None if class == OBJECT => Ok((None, false)),
+            None => Err(ErrorType::from(ErrorKind::Malformed("unknown class")).within(class))
+        }
+    }
+}
+
+impl ClassHierarchy for ClassSet {
+    fn super_class(&self, class: &str) -> Result<Option<String>, ErrorType> {
+        self.get(class).map(|(super_class, _)| super_class)
+    }
+
+    fn is_interface(&self, class: &str) -> Result<bool, ErrorType> {
+        self.get(class).map(|(_, is_interface)| is_interface)
+    }
+}
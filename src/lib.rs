@@ -2,19 +2,43 @@
 extern crate bitflags;
 
 pub mod access_flags;
+pub mod analysis;
+pub mod annotation;
 pub mod attribute;
+pub mod bytecode;
+pub mod code_builder;
 pub mod constant_pool;
+pub mod descriptor;
 pub mod error;
 mod field;
+pub mod frames;
+pub mod hierarchy;
 mod method;
+pub mod mutf8;
 pub mod opcode;
 mod parser;
 mod raw_class;
+mod reader;
+pub mod signature;
+pub mod smap;
+pub mod stack_map;
+#[cfg(test)]
+mod test_util;
+pub mod view;
+mod writer;
 
 pub use attribute::Attribute;
-pub use constant_pool::{Constant, ConstantTag, ReferenceKind};
+pub use bytecode::Bytecode;
+pub use code_builder::CodeBuilder;
+pub use constant_pool::{
+    Constant, ConstantPool, ConstantPoolBuilder, ConstantTag, MemberRef, ReferenceKind
+};
+pub use descriptor::{FieldType, MethodDescriptor};
 pub use field::Field;
 pub use method::Method;
 pub use opcode::Opcode;
 pub use parser::ClassParser;
 pub use raw_class::RawClass;
+pub use stack_map::{StackMapFrame, VerificationTypeInfo};
+pub use view::ClassView;
+pub use writer::ClassWriter;
@@ -1,13 +1,99 @@
 use crate::access_flags::MethodAccessFlag;
+use crate::analysis;
 use crate::attribute::Attribute;
+use crate::constant_pool::{ConstantPool, ConstantPoolBuilder};
+use crate::descriptor::MethodDescriptor;
+use crate::error::ErrorType;
+use crate::frames;
+use crate::hierarchy::ClassHierarchy;
+use crate::stack_map::StackMapFrame;
 
 /// Describes a method <br>
 /// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.6> for more information
 #[derive(Debug)]
-pub struct Method {
+pub struct Method<'c> {
     pub access_flags: MethodAccessFlag,
+    /// See [`RawClass::undefined_access_flags`](crate::RawClass::undefined_access_flags)
+    pub undefined_access_flags: u16,
     pub name_index: u16,
     pub descriptor_index: u16,
     pub attributes_count: u16,
-    pub attributes: Vec<Attribute>
+    pub attributes: Vec<Attribute<'c>>
+}
+
+impl<'c> Method<'c> {
+    /// Recomputes `max_stack` and `max_locals` of the `Code` attribute, if the method has one,
+    /// with [`analysis::max_stack`] and [`analysis::max_locals`]
+    pub fn compute_maxs(&mut self, constant_pool: &ConstantPool<'c>) -> Result<(), ErrorType> {
+        let descriptor = constant_pool.utf8(self.descriptor_index)?;
+        let descriptor = MethodDescriptor::parse(&descriptor)?;
+        let is_static = self.access_flags.contains(MethodAccessFlag::STATIC);
+
+        for attribute in self.attributes.iter_mut() {
+            if let Attribute::Code {
+                max_stack,
+                max_locals,
+                code,
+                exception_table,
+                ..
+            } = attribute
+            {
+                *max_stack = analysis::max_stack(code, exception_table, constant_pool)?;
+                *max_locals = analysis::max_locals(code, &descriptor, is_static)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the `StackMapTable` of the `Code` attribute with frames computed by
+    /// [`frames::compute_frames`], removing it if no frames are needed.<br>
+    /// `class_name` is the internal name of the class declaring this method
+    pub fn compute_frames(
+        &mut self,
+        class_name: &str,
+        constant_pool: &mut ConstantPoolBuilder<'c>,
+        hierarchy: &dyn ClassHierarchy
+    ) -> Result<(), ErrorType> {
+        let entries = frames::compute_frames(self, class_name, constant_pool, hierarchy)?;
+        let stack_map_table = if entries.is_empty() {
+            None
+        } else {
+            Some(Attribute::StackMapTable {
+                attribute_name_index: constant_pool.utf8("StackMapTable")?,
+                attribute_length: 2 + entries.iter().map(StackMapFrame::encoded_len).sum::<u32>(),
+                number_of_entries: entries.len() as u16,
+                entries
+            })
+        };
+
+        for attribute in self.attributes.iter_mut() {
+            if let Attribute::Code {
+                attributes_count,
+                attributes,
+                ..
+            } = attribute
+            {
+                let position = attributes
+                    .iter()
+                    .position(|attribute| matches!(attribute, Attribute::StackMapTable { .. }));
+
+                match (position, stack_map_table) {
+                    (Some(position), Some(stack_map_table)) => {
+                        attributes[position] = stack_map_table
+                    }
+                    (None, Some(stack_map_table)) => attributes.push(stack_map_table),
+                    (Some(position), None) => {
+                        attributes.remove(position);
+                    }
+                    (None, None) => {}
+                }
+
+                *attributes_count = attributes.len() as u16;
+                break;
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,123 @@
+//! Conversion between Rust strings and the modified UTF-8 encoding used by class files
+//!
+//! Modified UTF-8 differs from standard UTF-8 in two ways: the null character is encoded with
+//! two bytes (`C0 80`) and characters outside the Basic Multilingual Plane are encoded as a
+//! surrogate pair of three bytes each, instead of a single four byte sequence.<br>
+//! See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.4.7> for more information
+
+use std::borrow::Cow;
+use std::char;
+use std::str;
+
+use crate::error::{ErrorKind, ErrorType};
+
+/// Decodes modified UTF-8 `bytes`.<br>
+/// Borrows from `bytes` unless they contain an encoded null character or supplementary
+/// character. Unpaired surrogates, which Java strings may contain but Rust strings can not,
+/// are replaced with `U+FFFD`
+pub fn decode(bytes: &[u8]) -> Result<Cow<'_, str>, ErrorType> {
+    // Any byte sequence that is valid in both encodings decodes to the same string
+    if !bytes.iter().any(|&b| b == 0 || b >= 0xf0) {
+        if let Ok(s) = str::from_utf8(bytes) {
+            return Ok(Cow::Borrowed(s));
+        }
+    }
+
+    let mut s = String::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let (unit, len) = decode_unit(&bytes[i..])?;
+        i += len;
+
+        let c = match unit {
+            0xd800..=0xdbff => match decode_unit(&bytes[i..]) {
+                Ok((low @ 0xdc00..=0xdfff, len)) => {
+                    i += len;
+                    let c = 0x10000 + (((unit as u32) - 0xd800) << 10) + (low as u32 - 0xdc00);
+                    char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER)
+                }
+                _ => char::REPLACEMENT_CHARACTER
+            },
+            _ => char::from_u32(unit as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+        };
+
+        s.push(c);
+    }
+
+    Ok(Cow::Owned(s))
+}
+
+/// Decodes a single UTF-16 code unit, returning it along with the number of bytes consumed
+fn decode_unit(bytes: &[u8]) -> Result<(u16, usize), ErrorType> {
+    let invalid = || ErrorType::from(ErrorKind::Malformed("invalid modified UTF-8"));
+    let continuation = |i: usize| match bytes.get(i) {
+        Some(&b) if b & 0xc0 == 0x80 => Ok((b & 0x3f) as u16),
+        _ => Err(invalid())
+    };
+
+    match bytes.first() {
+        Some(&b) if b != 0 && b < 0x80 => Ok((b as u16, 1)),
+        Some(&b) if b & 0xe0 == 0xc0 => Ok(((((b & 0x1f) as u16) << 6) | continuation(1)?, 2)),
+        Some(&b) if b & 0xf0 == 0xe0 => Ok((
+            (((b & 0x0f) as u16) << 12) | (continuation(1)? << 6) | continuation(2)?,
+            3
+        )),
+        _ => Err(invalid())
+    }
+}
+
+/// Encodes `s` as modified UTF-8.<br>
+/// Borrows from `s` unless it contains a null character or supplementary character
+pub fn encode(s: &str) -> Cow<'_, [u8]> {
+    if !s.chars().any(|c| c == '\0' || c > '\u{ffff}') {
+        return Cow::Borrowed(s.as_bytes());
+    }
+
+    let mut bytes = Vec::with_capacity(s.len() + 2);
+    let mut units = [0; 2];
+
+    for c in s.chars() {
+        match c {
+            '\0' => bytes.extend_from_slice(&[0xc0, 0x80]),
+            c if c > '\u{ffff}' => {
+                for unit in c.encode_utf16(&mut units).iter() {
+                    bytes.extend_from_slice(&[
+                        0xe0 | (unit >> 12) as u8,
+                        0x80 | ((unit >> 6) & 0x3f) as u8,
+                        0x80 | (unit & 0x3f) as u8
+                    ]);
+                }
+            }
+            c => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+
+    Cow::Owned(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode};
+    use std::borrow::Cow;
+
+    #[test]
+    fn decode_and_encode() {
+        let nul = [0x61, 0xc0, 0x80, 0x62];
+        let emoji = [0x73, 0x6d, 0x69, 0x6c, 0x65, 0x20, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80];
+
+        assert_eq!(decode(&nul).unwrap(), "a\0b");
+        assert_eq!(decode(&emoji).unwrap(), "smile \u{1f600}");
+        assert_eq!(decode(&[0xed, 0xa0, 0x80, 0x21]).unwrap(), "\u{fffd}!");
+        assert_eq!(encode("a\0b"), &nul[..]);
+        assert_eq!(encode("smile \u{1f600}"), &emoji[..]);
+
+        assert!(matches!(decode(b"plain"), Ok(Cow::Borrowed("plain"))));
+        assert!(matches!(encode("plain"), Cow::Borrowed(b"plain")));
+        assert!(decode(&[0x61, 0x00]).is_err());
+        assert!(decode(&[0xe0, 0x80]).is_err());
+    }
+}
@@ -1,13 +1,26 @@
-use crate::error::ErrorType;
+use crate::constant_pool::{ConstantPool, MemberRef};
+use crate::error::{ErrorKind, ErrorType};
+use crate::ConstantTag;
 use std::convert::TryFrom;
 
 #[derive(Debug)]
 #[allow(non_camel_case_types)]
 pub enum Instruction {
+    aaload,
+    aastore,
+    aconst_null,
+    aload {
+        index: u8
+    },
     aload_0,
     aload_1,
     aload_2,
     aload_3,
+    anewarray {
+        cp_index: u16
+    },
+    areturn,
+    arraylength,
     astore {
         index: u8
     },
@@ -15,41 +28,674 @@ pub enum Instruction {
     astore_1,
     astore_2,
     astore_3,
-    dup,
+    athrow,
+    baload,
+    bastore,
     bipush {
-        byte: u8
+        value: i8
+    },
+    caload,
+    castore,
+    checkcast {
+        cp_index: u16
+    },
+    d2f,
+    d2i,
+    d2l,
+    dadd,
+    daload,
+    dastore,
+    dcmpg,
+    dcmpl,
+    dconst_0,
+    dconst_1,
+    ddiv,
+    dload {
+        index: u8
+    },
+    dload_0,
+    dload_1,
+    dload_2,
+    dload_3,
+    dmul,
+    dneg,
+    drem,
+    dreturn,
+    dstore {
+        index: u8
+    },
+    dstore_0,
+    dstore_1,
+    dstore_2,
+    dstore_3,
+    dsub,
+    dup,
+    dup_x1,
+    dup_x2,
+    dup2,
+    dup2_x1,
+    dup2_x2,
+    f2d,
+    f2i,
+    f2l,
+    fadd,
+    faload,
+    fastore,
+    fcmpg,
+    fcmpl,
+    fconst_0,
+    fconst_1,
+    fconst_2,
+    fdiv,
+    fload {
+        index: u8
+    },
+    fload_0,
+    fload_1,
+    fload_2,
+    fload_3,
+    fmul,
+    fneg,
+    frem,
+    freturn,
+    fstore {
+        index: u8
+    },
+    fstore_0,
+    fstore_1,
+    fstore_2,
+    fstore_3,
+    fsub,
+    getfield {
+        cp_index: u16
+    },
+    getstatic {
+        cp_index: u16
+    },
+    goto {
+        offset: i16
+    },
+    goto_w {
+        offset: i32
+    },
+    i2b,
+    i2c,
+    i2d,
+    i2f,
+    i2l,
+    i2s,
+    iadd,
+    iaload,
+    iand,
+    iastore,
+    iconst_m1,
+    iconst_0,
+    iconst_1,
+    iconst_2,
+    iconst_3,
+    iconst_4,
+    iconst_5,
+    idiv,
+    if_acmpeq {
+        offset: i16
+    },
+    if_acmpne {
+        offset: i16
+    },
+    if_icmpeq {
+        offset: i16
+    },
+    if_icmpne {
+        offset: i16
+    },
+    if_icmplt {
+        offset: i16
+    },
+    if_icmpge {
+        offset: i16
+    },
+    if_icmpgt {
+        offset: i16
+    },
+    if_icmple {
+        offset: i16
+    },
+    ifeq {
+        offset: i16
+    },
+    ifne {
+        offset: i16
+    },
+    iflt {
+        offset: i16
+    },
+    ifge {
+        offset: i16
+    },
+    ifgt {
+        offset: i16
+    },
+    ifle {
+        offset: i16
+    },
+    ifnonnull {
+        offset: i16
+    },
+    ifnull {
+        offset: i16
+    },
+    iinc {
+        index: u8,
+        r#const: i8
+    },
+    iload {
+        index: u8
+    },
+    iload_0,
+    iload_1,
+    iload_2,
+    iload_3,
+    imul,
+    ineg,
+    instanceof {
+        cp_index: u16
     },
     invokedynamic {
-        indexbyte1: u8,
-        indexbyte2: u8,
-        byte3: u8,
-        byte4: u8
+        cp_index: u16
+    },
+    invokeinterface {
+        cp_index: u16,
+        count: u8
     },
     invokespecial {
-        indexbyte1: u8,
-        indexbyte2: u8
+        cp_index: u16
     },
     invokestatic {
-        indexbyte1: u8,
-        indexbyte2: u8
+        cp_index: u16
     },
     invokevirtual {
-        indexbyte1: u8,
-        indexbyte2: u8
+        cp_index: u16
     },
-    getstatic {
-        indexbyte1: u8,
-        indexbyte2: u8
+    ior,
+    irem,
+    ireturn,
+    ishl,
+    ishr,
+    istore {
+        index: u8
     },
-    new {
-        indexbyte1: u8,
-        indexbyte2: u8
+    istore_0,
+    istore_1,
+    istore_2,
+    istore_3,
+    isub,
+    iushr,
+    ixor,
+    jsr {
+        offset: i16
     },
-    r#eturn,
+    jsr_w {
+        offset: i32
+    },
+    l2d,
+    l2f,
+    l2i,
+    ladd,
+    laload,
+    land,
+    lastore,
+    lcmp,
+    lconst_0,
+    lconst_1,
     ldc {
+        cp_index: u8
+    },
+    ldc_w {
+        cp_index: u16
+    },
+    ldc2_w {
+        cp_index: u16
+    },
+    ldiv,
+    lload {
+        index: u8
+    },
+    lload_0,
+    lload_1,
+    lload_2,
+    lload_3,
+    lmul,
+    lneg,
+    lookupswitch {
+        default: i32,
+        npairs: i32,
+        match_offset_pairs: Vec<(i32, i32)>
+    },
+    lor,
+    lrem,
+    lreturn,
+    lshl,
+    lshr,
+    lstore {
+        index: u8
+    },
+    lstore_0,
+    lstore_1,
+    lstore_2,
+    lstore_3,
+    lsub,
+    lushr,
+    lxor,
+    monitorenter,
+    monitorexit,
+    multianewarray {
+        cp_index: u16,
+        dimensions: u8
+    },
+    new {
+        cp_index: u16
+    },
+    newarray {
+        atype: u8
+    },
+    nop,
+    pop,
+    pop2,
+    putfield {
+        cp_index: u16
+    },
+    putstatic {
+        cp_index: u16
+    },
+    ret {
         index: u8
     },
-    nop
+    r#eturn,
+    saload,
+    sastore,
+    sipush {
+        value: i16
+    },
+    swap,
+    tableswitch {
+        default: i32,
+        low: i32,
+        high: i32,
+        jump_offsets: Vec<i32>
+    },
+    /// Extends the local variable index of `iload`, `fload`, `aload`, `lload`, `dload`,
+    /// `istore`, `fstore`, `astore`, `lstore`, `dstore` and `ret` to two bytes
+    wide {
+        opcode: Opcode,
+        index: u16
+    },
+    /// A `wide` prefixed `iinc`, using a two byte local variable index and increment
+    wide_iinc {
+        index: u16,
+        r#const: i16
+    }
+}
+
+impl Instruction {
+    /// The opcode of this instruction, `wide` for the `wide` prefixed forms
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Instruction::aaload => Opcode::aaload,
+            Instruction::aastore => Opcode::aastore,
+            Instruction::aconst_null => Opcode::aconst_null,
+            Instruction::aload { .. } => Opcode::aload,
+            Instruction::aload_0 => Opcode::aload_0,
+            Instruction::aload_1 => Opcode::aload_1,
+            Instruction::aload_2 => Opcode::aload_2,
+            Instruction::aload_3 => Opcode::aload_3,
+            Instruction::anewarray { .. } => Opcode::anewarray,
+            Instruction::areturn => Opcode::areturn,
+            Instruction::arraylength => Opcode::arraylength,
+            Instruction::astore { .. } => Opcode::astore,
+            Instruction::astore_0 => Opcode::astore_0,
+            Instruction::astore_1 => Opcode::astore_1,
+            Instruction::astore_2 => Opcode::astore_2,
+            Instruction::astore_3 => Opcode::astore_3,
+            Instruction::athrow => Opcode::athrow,
+            Instruction::baload => Opcode::baload,
+            Instruction::bastore => Opcode::bastore,
+            Instruction::bipush { .. } => Opcode::bipush,
+            Instruction::caload => Opcode::caload,
+            Instruction::castore => Opcode::castore,
+            Instruction::checkcast { .. } => Opcode::checkcast,
+            Instruction::d2f => Opcode::d2f,
+            Instruction::d2i => Opcode::d2i,
+            Instruction::d2l => Opcode::d2l,
+            Instruction::dadd => Opcode::dadd,
+            Instruction::daload => Opcode::daload,
+            Instruction::dastore => Opcode::dastore,
+            Instruction::dcmpg => Opcode::dcmpg,
+            Instruction::dcmpl => Opcode::dcmpl,
+            Instruction::dconst_0 => Opcode::dconst_0,
+            Instruction::dconst_1 => Opcode::dconst_1,
+            Instruction::ddiv => Opcode::ddiv,
+            Instruction::dload { .. } => Opcode::dload,
+            Instruction::dload_0 => Opcode::dload_0,
+            Instruction::dload_1 => Opcode::dload_1,
+            Instruction::dload_2 => Opcode::dload_2,
+            Instruction::dload_3 => Opcode::dload_3,
+            Instruction::dmul => Opcode::dmul,
+            Instruction::dneg => Opcode::dneg,
+            Instruction::drem => Opcode::drem,
+            Instruction::dreturn => Opcode::dreturn,
+            Instruction::dstore { .. } => Opcode::dstore,
+            Instruction::dstore_0 => Opcode::dstore_0,
+            Instruction::dstore_1 => Opcode::dstore_1,
+            Instruction::dstore_2 => Opcode::dstore_2,
+            Instruction::dstore_3 => Opcode::dstore_3,
+            Instruction::dsub => Opcode::dsub,
+            Instruction::dup => Opcode::dup,
+            Instruction::dup_x1 => Opcode::dup_x1,
+            Instruction::dup_x2 => Opcode::dup_x2,
+            Instruction::dup2 => Opcode::dup2,
+            Instruction::dup2_x1 => Opcode::dup2_x1,
+            Instruction::dup2_x2 => Opcode::dup2_x2,
+            Instruction::f2d => Opcode::f2d,
+            Instruction::f2i => Opcode::f2i,
+            Instruction::f2l => Opcode::f2l,
+            Instruction::fadd => Opcode::fadd,
+            Instruction::faload => Opcode::faload,
+            Instruction::fastore => Opcode::fastore,
+            Instruction::fcmpg => Opcode::fcmpg,
+            Instruction::fcmpl => Opcode::fcmpl,
+            Instruction::fconst_0 => Opcode::fconst_0,
+            Instruction::fconst_1 => Opcode::fconst_1,
+            Instruction::fconst_2 => Opcode::fconst_2,
+            Instruction::fdiv => Opcode::fdiv,
+            Instruction::fload { .. } => Opcode::fload,
+            Instruction::fload_0 => Opcode::fload_0,
+            Instruction::fload_1 => Opcode::fload_1,
+            Instruction::fload_2 => Opcode::fload_2,
+            Instruction::fload_3 => Opcode::fload_3,
+            Instruction::fmul => Opcode::fmul,
+            Instruction::fneg => Opcode::fneg,
+            Instruction::frem => Opcode::frem,
+            Instruction::freturn => Opcode::freturn,
+            Instruction::fstore { .. } => Opcode::fstore,
+            Instruction::fstore_0 => Opcode::fstore_0,
+            Instruction::fstore_1 => Opcode::fstore_1,
+            Instruction::fstore_2 => Opcode::fstore_2,
+            Instruction::fstore_3 => Opcode::fstore_3,
+            Instruction::fsub => Opcode::fsub,
+            Instruction::getfield { .. } => Opcode::getfield,
+            Instruction::getstatic { .. } => Opcode::getstatic,
+            Instruction::goto { .. } => Opcode::goto,
+            Instruction::goto_w { .. } => Opcode::goto_w,
+            Instruction::i2b => Opcode::i2b,
+            Instruction::i2c => Opcode::i2c,
+            Instruction::i2d => Opcode::i2d,
+            Instruction::i2f => Opcode::i2f,
+            Instruction::i2l => Opcode::i2l,
+            Instruction::i2s => Opcode::i2s,
+            Instruction::iadd => Opcode::iadd,
+            Instruction::iaload => Opcode::iaload,
+            Instruction::iand => Opcode::iand,
+            Instruction::iastore => Opcode::iastore,
+            Instruction::iconst_m1 => Opcode::iconst_m1,
+            Instruction::iconst_0 => Opcode::iconst_0,
+            Instruction::iconst_1 => Opcode::iconst_1,
+            Instruction::iconst_2 => Opcode::iconst_2,
+            Instruction::iconst_3 => Opcode::iconst_3,
+            Instruction::iconst_4 => Opcode::iconst_4,
+            Instruction::iconst_5 => Opcode::iconst_5,
+            Instruction::idiv => Opcode::idiv,
+            Instruction::if_acmpeq { .. } => Opcode::if_acmpeq,
+            Instruction::if_acmpne { .. } => Opcode::if_acmpne,
+            Instruction::if_icmpeq { .. } => Opcode::if_icmpeq,
+            Instruction::if_icmpne { .. } => Opcode::if_icmpne,
+            Instruction::if_icmplt { .. } => Opcode::if_icmplt,
+            Instruction::if_icmpge { .. } => Opcode::if_icmpge,
+            Instruction::if_icmpgt { .. } => Opcode::if_icmpgt,
+            Instruction::if_icmple { .. } => Opcode::if_icmple,
+            Instruction::ifeq { .. } => Opcode::ifeq,
+            Instruction::ifne { .. } => Opcode::ifne,
+            Instruction::iflt { .. } => Opcode::iflt,
+            Instruction::ifge { .. } => Opcode::ifge,
+            Instruction::ifgt { .. } => Opcode::ifgt,
+            Instruction::ifle { .. } => Opcode::ifle,
+            Instruction::ifnonnull { .. } => Opcode::ifnonnull,
+            Instruction::ifnull { .. } => Opcode::ifnull,
+            Instruction::iinc { .. } => Opcode::iinc,
+            Instruction::iload { .. } => Opcode::iload,
+            Instruction::iload_0 => Opcode::iload_0,
+            Instruction::iload_1 => Opcode::iload_1,
+            Instruction::iload_2 => Opcode::iload_2,
+            Instruction::iload_3 => Opcode::iload_3,
+            Instruction::imul => Opcode::imul,
+            Instruction::ineg => Opcode::ineg,
+            Instruction::instanceof { .. } => Opcode::instanceof,
+            Instruction::invokedynamic { .. } => Opcode::invokedynamic,
+            Instruction::invokeinterface { .. } => Opcode::invokeinterface,
+            Instruction::invokespecial { .. } => Opcode::invokespecial,
+            Instruction::invokestatic { .. } => Opcode::invokestatic,
+            Instruction::invokevirtual { .. } => Opcode::invokevirtual,
+            Instruction::ior => Opcode::ior,
+            Instruction::irem => Opcode::irem,
+            Instruction::ireturn => Opcode::ireturn,
+            Instruction::ishl => Opcode::ishl,
+            Instruction::ishr => Opcode::ishr,
+            Instruction::istore { .. } => Opcode::istore,
+            Instruction::istore_0 => Opcode::istore_0,
+            Instruction::istore_1 => Opcode::istore_1,
+            Instruction::istore_2 => Opcode::istore_2,
+            Instruction::istore_3 => Opcode::istore_3,
+            Instruction::isub => Opcode::isub,
+            Instruction::iushr => Opcode::iushr,
+            Instruction::ixor => Opcode::ixor,
+            Instruction::jsr { .. } => Opcode::jsr,
+            Instruction::jsr_w { .. } => Opcode::jsr_w,
+            Instruction::l2d => Opcode::l2d,
+            Instruction::l2f => Opcode::l2f,
+            Instruction::l2i => Opcode::l2i,
+            Instruction::ladd => Opcode::ladd,
+            Instruction::laload => Opcode::laload,
+            Instruction::land => Opcode::land,
+            Instruction::lastore => Opcode::lastore,
+            Instruction::lcmp => Opcode::lcmp,
+            Instruction::lconst_0 => Opcode::lconst_0,
+            Instruction::lconst_1 => Opcode::lconst_1,
+            Instruction::ldc { .. } => Opcode::ldc,
+            Instruction::ldc_w { .. } => Opcode::ldc_w,
+            Instruction::ldc2_w { .. } => Opcode::ldc2_w,
+            Instruction::ldiv => Opcode::ldiv,
+            Instruction::lload { .. } => Opcode::lload,
+            Instruction::lload_0 => Opcode::lload_0,
+            Instruction::lload_1 => Opcode::lload_1,
+            Instruction::lload_2 => Opcode::lload_2,
+            Instruction::lload_3 => Opcode::lload_3,
+            Instruction::lmul => Opcode::lmul,
+            Instruction::lneg => Opcode::lneg,
+            Instruction::lookupswitch { .. } => Opcode::lookupswitch,
+            Instruction::lor => Opcode::lor,
+            Instruction::lrem => Opcode::lrem,
+            Instruction::lreturn => Opcode::lreturn,
+            Instruction::lshl => Opcode::lshl,
+            Instruction::lshr => Opcode::lshr,
+            Instruction::lstore { .. } => Opcode::lstore,
+            Instruction::lstore_0 => Opcode::lstore_0,
+            Instruction::lstore_1 => Opcode::lstore_1,
+            Instruction::lstore_2 => Opcode::lstore_2,
+            Instruction::lstore_3 => Opcode::lstore_3,
+            Instruction::lsub => Opcode::lsub,
+            Instruction::lushr => Opcode::lushr,
+            Instruction::lxor => Opcode::lxor,
+            Instruction::monitorenter => Opcode::monitorenter,
+            Instruction::monitorexit => Opcode::monitorexit,
+            Instruction::multianewarray { .. } => Opcode::multianewarray,
+            Instruction::new { .. } => Opcode::new,
+            Instruction::newarray { .. } => Opcode::newarray,
+            Instruction::nop => Opcode::nop,
+            Instruction::pop => Opcode::pop,
+            Instruction::pop2 => Opcode::pop2,
+            Instruction::putfield { .. } => Opcode::putfield,
+            Instruction::putstatic { .. } => Opcode::putstatic,
+            Instruction::ret { .. } => Opcode::ret,
+            Instruction::r#eturn => Opcode::r#eturn,
+            Instruction::saload => Opcode::saload,
+            Instruction::sastore => Opcode::sastore,
+            Instruction::sipush { .. } => Opcode::sipush,
+            Instruction::swap => Opcode::swap,
+            Instruction::tableswitch { .. } => Opcode::tableswitch,
+            Instruction::wide { .. } => Opcode::wide,
+            Instruction::wide_iinc { .. } => Opcode::wide
+        }
+    }
+
+    /// The constant pool index referenced by this instruction, if any
+    pub fn cp_index(&self) -> Option<u16> {
+        match self {
+            Instruction::ldc { cp_index } => Some(*cp_index as u16),
+            Instruction::anewarray { cp_index }
+            | Instruction::checkcast { cp_index }
+            | Instruction::getfield { cp_index }
+            | Instruction::getstatic { cp_index }
+            | Instruction::instanceof { cp_index }
+            | Instruction::invokedynamic { cp_index }
+            | Instruction::invokeinterface { cp_index, .. }
+            | Instruction::invokespecial { cp_index }
+            | Instruction::invokestatic { cp_index }
+            | Instruction::invokevirtual { cp_index }
+            | Instruction::ldc_w { cp_index }
+            | Instruction::ldc2_w { cp_index }
+            | Instruction::multianewarray { cp_index, .. }
+            | Instruction::new { cp_index }
+            | Instruction::putfield { cp_index }
+            | Instruction::putstatic { cp_index } => Some(*cp_index),
+            _ => None
+        }
+    }
+
+    /// Resolves the field accessed by `getfield`, `getstatic`, `putfield` and `putstatic` or
+    /// the method called by `invokevirtual`, `invokespecial`, `invokestatic` and
+    /// `invokeinterface`.<br>
+    /// Returns `Ok(None)` for all other instructions
+    pub fn member_ref<'c>(
+        &self,
+        constant_pool: &ConstantPool<'c>
+    ) -> Result<Option<MemberRef<'c>>, ErrorType> {
+        let (cp_index, expected) = match self {
+            Instruction::getfield { cp_index }
+            | Instruction::getstatic { cp_index }
+            | Instruction::putfield { cp_index }
+            | Instruction::putstatic { cp_index } => (*cp_index, ConstantTag::Fieldref),
+            Instruction::invokespecial { cp_index }
+            | Instruction::invokestatic { cp_index }
+            | Instruction::invokevirtual { cp_index } => (*cp_index, ConstantTag::Methodref),
+            Instruction::invokeinterface { cp_index, .. } => {
+                (*cp_index, ConstantTag::InterfaceMethodref)
+            }
+            _ => return Ok(None)
+        };
+
+        constant_pool.member_ref_of(cp_index, expected).map(Some)
+    }
+
+    /// The number of bytes this instruction occupies when located at `pc`, which determines the
+    /// padding of `tableswitch` and `lookupswitch`
+    pub fn size(&self, pc: u32) -> u32 {
+        let padding = (4 - (pc + 1) % 4) % 4;
+
+        match self {
+            Instruction::tableswitch { jump_offsets, .. } => {
+                1 + padding + 12 + 4 * jump_offsets.len() as u32
+            }
+            Instruction::lookupswitch {
+                match_offset_pairs, ..
+            } => 1 + padding + 8 + 8 * match_offset_pairs.len() as u32,
+            Instruction::wide_iinc { .. } => 6,
+            Instruction::goto_w { .. }
+            | Instruction::jsr_w { .. }
+            | Instruction::invokedynamic { .. }
+            | Instruction::invokeinterface { .. } => 5,
+            Instruction::multianewarray { .. } | Instruction::wide { .. } => 4,
+            Instruction::sipush { .. } | Instruction::iinc { .. } => 3,
+            Instruction::bipush { .. } | Instruction::newarray { .. } => 2,
+            Instruction::ldc { .. } => 2,
+            Instruction::aload { .. }
+            | Instruction::astore { .. }
+            | Instruction::dload { .. }
+            | Instruction::dstore { .. }
+            | Instruction::fload { .. }
+            | Instruction::fstore { .. }
+            | Instruction::iload { .. }
+            | Instruction::istore { .. }
+            | Instruction::lload { .. }
+            | Instruction::lstore { .. }
+            | Instruction::ret { .. } => 2,
+            instruction if instruction.cp_index().is_some() => 3,
+            instruction if instruction.branch_offset().is_some() => 3,
+            _ => 1
+        }
+    }
+
+    /// The signed offset of a branch instruction, relative to its own pc
+    pub fn branch_offset(&self) -> Option<i32> {
+        match self {
+            Instruction::goto { offset }
+            | Instruction::if_acmpeq { offset }
+            | Instruction::if_acmpne { offset }
+            | Instruction::if_icmpeq { offset }
+            | Instruction::if_icmpne { offset }
+            | Instruction::if_icmplt { offset }
+            | Instruction::if_icmpge { offset }
+            | Instruction::if_icmpgt { offset }
+            | Instruction::if_icmple { offset }
+            | Instruction::ifeq { offset }
+            | Instruction::ifne { offset }
+            | Instruction::iflt { offset }
+            | Instruction::ifge { offset }
+            | Instruction::ifgt { offset }
+            | Instruction::ifle { offset }
+            | Instruction::ifnonnull { offset }
+            | Instruction::ifnull { offset }
+            | Instruction::jsr { offset } => Some(*offset as i32),
+            Instruction::goto_w { offset } | Instruction::jsr_w { offset } => Some(*offset),
+            _ => None
+        }
+    }
+
+    /// The absolute target of a branch instruction located at `pc`
+    pub fn branch_target(&self, pc: u32) -> Option<u32> {
+        self.branch_offset().and_then(|offset| target(pc, offset))
+    }
+
+    /// The absolute targets of a `tableswitch` or `lookupswitch` instruction located at `pc`,
+    /// starting with the default target
+    pub fn switch_targets(&self, pc: u32) -> Option<Vec<u32>> {
+        let (default, offsets): (i32, Vec<i32>) = match self {
+            Instruction::tableswitch {
+                default,
+                jump_offsets,
+                ..
+            } => (*default, jump_offsets.clone()),
+            Instruction::lookupswitch {
+                default,
+                match_offset_pairs,
+                ..
+            } => (
+                *default,
+                match_offset_pairs
+                    .iter()
+                    .map(|(_, offset)| *offset)
+                    .collect()
+            ),
+            _ => return None
+        };
+
+        std::iter::once(default)
+            .chain(offsets)
+            .map(|offset| target(pc, offset))
+            .collect()
+    }
+}
+
+fn target(pc: u32, offset: i32) -> Option<u32> {
+    u32::try_from(i64::from(pc) + i64::from(offset)).ok()
 }
 
 #[repr(u8)]
@@ -468,7 +1114,7 @@ impl TryFrom<u8> for Opcode {
             x if x == Opcode::tableswitch as u8 => Ok(Opcode::tableswitch),
             x if x == Opcode::wide as u8 => Ok(Opcode::wide),
             x if x == Opcode::r#eturn as u8 => Ok(Opcode::r#eturn),
-            _ => Err(ErrorType::IntegerConversion)
+            _ => Err(ErrorKind::InvalidOpcode(v).into())
         }
     }
 }
@@ -1,47 +1,74 @@
+use std::borrow::Cow;
 use std::convert::TryFrom;
-use std::str;
 
 use crate::attribute::{
     BootstrapMethodAttribute, ExceptionTableEntry, InnerClassAttribute, LineNumberTableEntry,
-    ParameterAttribute
+    LocalVariableTableEntry, LocalVariableTypeTableEntry, ModuleAttribute, ModuleExports,
+    ModuleOpens, ModuleProvides, ModuleRequires, ParameterAttribute, RecordComponentInfo
 };
-use crate::error::ErrorType;
+use crate::constant_pool::ConstantPool;
+use crate::error::{ErrorKind, ErrorType};
 use crate::{Attribute, Constant, ConstantTag, Field, Method, Opcode, RawClass, ReferenceKind};
 
 use crate::access_flags::{
-    ClassAccessFlag, FieldAccessFlag, InnerClassAccessFlag, MethodAccessFlag, ParameterAccessFlag
+    ClassAccessFlag, ExportsFlag, FieldAccessFlag, InnerClassAccessFlag, MethodAccessFlag,
+    ModuleAccessFlag, OpensFlag, ParameterAccessFlag, RequiresFlag
 };
+use crate::annotation::{
+    Annotation, ElementValue, ElementValuePair, LocalvarTargetEntry, ParameterAnnotations,
+    TargetInfo, TypeAnnotation, TypePath, TypePathEntry
+};
+use crate::bytecode::Bytecode;
 use crate::opcode::Instruction;
+use crate::reader::Reader;
+use crate::stack_map::{StackMapFrame, VerificationTypeInfo};
+
+/// Limits how deeply attributes and annotation element values may be nested inside each other
+const MAX_DEPTH: usize = 64;
 
 /// The class parser. Used to construct instances of [`RawClass`]
 pub struct ClassParser<'c> {
-    bytes: &'c [u8],
-    offset: usize
+    reader: Reader<'c>,
+    depth: usize
 }
 
 impl<'c> ClassParser<'c> {
     /// Creates a new parser from given bytes
     pub fn from_bytes(bytes: &'c [u8]) -> ClassParser<'c> {
-        ClassParser { bytes, offset: 0 }
+        ClassParser {
+            reader: Reader::new(bytes),
+            depth: 0
+        }
     }
 
     /// Parses the provided bytes and tries to construct a new instance of [`RawClass`]
     pub fn parse(&mut self) -> Result<RawClass<'c>, ErrorType> {
-        let magic = self.read_u32_be();
-        let minor_version = self.read_u16_be();
-        let major_version = self.read_u16_be();
-        let constant_pool_count = self.read_u16_be();
+        let magic = self.reader.read_u32_be()?;
+
+        if magic != 0xCAFEBABE {
+            return Err(ErrorType::at(ErrorKind::InvalidMagic(magic), 0));
+        }
+
+        let minor_version = self.reader.read_u16_be()?;
+        let major_version = self.reader.read_u16_be()?;
+        let constant_pool_count = self.reader.read_u16_be()?;
         let constant_pool = self.read_constant_pool(constant_pool_count)?;
-        let access_flags = ClassAccessFlag::from_bits(self.read_u16_be()).unwrap();
-        let this_class = self.read_u16_be();
-        let super_class = self.read_u16_be();
-        let interface_count = self.read_u16_be();
-        let field_count = self.read_u16_be();
+        let (access_flags, undefined_access_flags) =
+            self.read_flags(ClassAccessFlag::from_bits_truncate, ClassAccessFlag::bits)?;
+        let this_class = self.reader.read_u16_be()?;
+        let super_class = self.reader.read_u16_be()?;
+        let interface_count = self.reader.read_u16_be()?;
+        let interfaces = self
+            .read_indices(interface_count)
+            .map_err(|e| e.within("interfaces"))?;
+        let field_count = self.reader.read_u16_be()?;
         let fields = self.read_fields(field_count, &constant_pool)?;
-        let methods_count = self.read_u16_be();
+        let methods_count = self.reader.read_u16_be()?;
         let methods = self.read_methods(methods_count, &constant_pool)?;
-        let attributes_count = self.read_u16_be();
-        let attributes = self.read_attributes(attributes_count, &constant_pool)?;
+        let attributes_count = self.reader.read_u16_be()?;
+        let attributes = self
+            .read_attributes(attributes_count, &constant_pool)
+            .map_err(|e| e.within("class"))?;
 
         Ok(RawClass {
             magic,
@@ -50,9 +77,11 @@ impl<'c> ClassParser<'c> {
             constant_pool_count,
             constant_pool,
             access_flags,
+            undefined_access_flags,
             this_class,
             super_class,
             interface_count,
+            interfaces,
             field_count,
             fields,
             methods_count,
@@ -62,153 +91,191 @@ impl<'c> ClassParser<'c> {
         })
     }
 
-    fn read_u32_be(&mut self) -> u32 {
-        let off = self.offset;
-        self.offset += 4;
-        u32::from_be_bytes([
-            self.bytes[off],
-            self.bytes[off + 1],
-            self.bytes[off + 2],
-            self.bytes[off + 3]
-        ])
-    }
+    fn read_constant_pool(
+        &mut self,
+        constant_pool_count: u16
+    ) -> Result<ConstantPool<'c>, ErrorType> {
+        let mut constant_pool = ConstantPool::default();
 
-    fn read_i32_be(&mut self) -> i32 {
-        let off = self.offset;
-        self.offset += 4;
-        i32::from_be_bytes([
-            self.bytes[off],
-            self.bytes[off + 1],
-            self.bytes[off + 2],
-            self.bytes[off + 3]
-        ])
-    }
+        while constant_pool.len() + 1 < constant_pool_count as usize {
+            let i = constant_pool.len() + 1;
+            let offset = self.reader.offset();
+            let constant = self
+                .read_constant()
+                .map_err(|e| e.within(format!("constant pool entry #{}", i)))?;
+
+            constant_pool.push(constant);
+
+            if constant_pool.len() >= constant_pool_count as usize {
+                return Err(ErrorType::at(
+                    ErrorKind::Malformed("Long or Double constant in the last constant pool slot"),
+                    offset
+                )
+                .within(format!("constant pool entry #{}", i)));
+            }
+        }
 
-    fn read_u16_be(&mut self) -> u16 {
-        let off = self.offset;
-        self.offset += 2;
-        u16::from_be_bytes([self.bytes[off], self.bytes[off + 1]])
+        Ok(constant_pool)
     }
 
-    fn read_constant_pool(
-        &mut self,
-        constant_pool_count: u16
-    ) -> Result<Vec<Constant<'c>>, ErrorType> {
-        let mut i = 1;
-        let mut constant_pool = Vec::with_capacity(constant_pool_count as usize);
-
-        while i < constant_pool_count {
-            let tag = ConstantTag::try_from(self.bytes[self.offset])?;
-            self.offset += 1;
-
-            let constant = match tag {
-                ConstantTag::Methodref => {
-                    let class_index = self.read_u16_be();
-                    let name_and_type_index = self.read_u16_be();
-
-                    Constant::Methodref {
-                        tag,
-                        class_index,
-                        name_and_type_index
-                    }
-                }
-                ConstantTag::Fieldref => {
-                    let class_index = self.read_u16_be();
-                    let name_and_type_index = self.read_u16_be();
+    fn read_constant(&mut self) -> Result<Constant<'c>, ErrorType> {
+        let offset = self.reader.offset();
+        let tag = ConstantTag::try_from(self.reader.read_u8()?).map_err(|e| e.or_offset(offset))?;
 
-                    Constant::Fieldref {
-                        tag,
-                        class_index,
-                        name_and_type_index
-                    }
-                }
-                ConstantTag::String => {
-                    let string_index = self.read_u16_be();
+        let constant = match tag {
+            ConstantTag::Methodref => {
+                let class_index = self.reader.read_u16_be()?;
+                let name_and_type_index = self.reader.read_u16_be()?;
 
-                    Constant::String { tag, string_index }
+                Constant::Methodref {
+                    tag,
+                    class_index,
+                    name_and_type_index
                 }
-                ConstantTag::Class => {
-                    let name_index = self.read_u16_be();
-
-                    Constant::Class { tag, name_index }
+            }
+            ConstantTag::Fieldref => {
+                let class_index = self.reader.read_u16_be()?;
+                let name_and_type_index = self.reader.read_u16_be()?;
+
+                Constant::Fieldref {
+                    tag,
+                    class_index,
+                    name_and_type_index
                 }
-                ConstantTag::Utf8 => {
-                    let length = self.read_u16_be();
-                    let end = self.offset + length as usize;
-                    let bytes = &self.bytes[self.offset..end];
-                    self.offset += length as usize;
-
-                    Constant::Utf8 { tag, length, bytes }
+            }
+            ConstantTag::InterfaceMethodref => {
+                let class_index = self.reader.read_u16_be()?;
+                let name_and_type_index = self.reader.read_u16_be()?;
+
+                Constant::InterfaceMethodref {
+                    tag,
+                    class_index,
+                    name_and_type_index
                 }
-                ConstantTag::NameAndType => {
-                    let name_index = self.read_u16_be();
-                    let descriptor_index = self.read_u16_be();
+            }
+            ConstantTag::String => {
+                let string_index = self.reader.read_u16_be()?;
 
-                    Constant::NameAndType {
-                        tag,
-                        name_index,
-                        descriptor_index
-                    }
-                }
-                ConstantTag::Integer => {
-                    let value = self.read_i32_be();
+                Constant::String { tag, string_index }
+            }
+            ConstantTag::Class => {
+                let name_index = self.reader.read_u16_be()?;
+
+                Constant::Class { tag, name_index }
+            }
+            ConstantTag::Utf8 => {
+                let length = self.reader.read_u16_be()?;
+                let bytes = Cow::Borrowed(self.reader.read_bytes(length as usize)?);
 
-                    Constant::Integer { tag, value }
+                Constant::Utf8 { tag, length, bytes }
+            }
+            ConstantTag::NameAndType => {
+                let name_index = self.reader.read_u16_be()?;
+                let descriptor_index = self.reader.read_u16_be()?;
+
+                Constant::NameAndType {
+                    tag,
+                    name_index,
+                    descriptor_index
                 }
-                ConstantTag::MethodHandle => {
-                    let reference_kind = ReferenceKind::try_from(self.bytes[self.offset])?;
-                    self.offset += 1;
-                    let reference_index = self.read_u16_be();
+            }
+            ConstantTag::Integer => {
+                let value = self.reader.read_i32_be()?;
 
-                    Constant::MethodHandle {
-                        tag,
-                        reference_kind,
-                        reference_index
-                    }
+                Constant::Integer { tag, value }
+            }
+            ConstantTag::Float => {
+                let bytes = self.reader.read_u32_be()?.to_be_bytes();
+
+                Constant::Float { tag, bytes }
+            }
+            ConstantTag::Long => {
+                let high_bytes = self.reader.read_u32_be()?;
+                let low_bytes = self.reader.read_u32_be()?;
+
+                Constant::Long {
+                    tag,
+                    high_bytes,
+                    low_bytes
+                }
+            }
+            ConstantTag::Double => {
+                let high_bytes = self.reader.read_u32_be()?;
+                let low_bytes = self.reader.read_u32_be()?;
+
+                Constant::Double {
+                    tag,
+                    high_bytes,
+                    low_bytes
+                }
+            }
+            ConstantTag::MethodHandle => {
+                let offset = self.reader.offset();
+                let reference_kind = ReferenceKind::try_from(self.reader.read_u8()?)
+                    .map_err(|e| e.or_offset(offset))?;
+                let reference_index = self.reader.read_u16_be()?;
+
+                Constant::MethodHandle {
+                    tag,
+                    reference_kind,
+                    reference_index
                 }
-                ConstantTag::InvokeDynamic => {
-                    let bootstrap_method_attr_index = self.read_u16_be();
-                    let name_index = self.read_u16_be();
+            }
+            ConstantTag::MethodType => {
+                let descriptor_index = self.reader.read_u16_be()?;
 
-                    Constant::InvokeDynamic {
-                        tag,
-                        bootstrap_method_attr_index,
-                        name_index
-                    }
+                Constant::MethodType {
+                    tag,
+                    descriptor_index
                 }
-                _ => unimplemented!("Unsupported constant tag {:?}", tag)
-            };
+            }
+            ConstantTag::Dynamic => {
+                let bootstrap_method_attr_index = self.reader.read_u16_be()?;
+                let name_and_type_index = self.reader.read_u16_be()?;
+
+                Constant::Dynamic {
+                    tag,
+                    bootstrap_method_attr_index,
+                    name_and_type_index
+                }
+            }
+            ConstantTag::InvokeDynamic => {
+                let bootstrap_method_attr_index = self.reader.read_u16_be()?;
+                let name_index = self.reader.read_u16_be()?;
+
+                Constant::InvokeDynamic {
+                    tag,
+                    bootstrap_method_attr_index,
+                    name_index
+                }
+            }
+            ConstantTag::Module => {
+                let name_index = self.reader.read_u16_be()?;
 
-            constant_pool.push(constant);
-            i += 1;
-        }
+                Constant::Module { tag, name_index }
+            }
+            ConstantTag::Package => {
+                let name_index = self.reader.read_u16_be()?;
 
-        Ok(constant_pool)
+                Constant::Package { tag, name_index }
+            }
+        };
+
+        Ok(constant)
     }
 
     fn read_fields(
         &mut self,
         field_count: u16,
-        constant_pool: &[Constant]
-    ) -> Result<Vec<Field>, ErrorType> {
+        constant_pool: &ConstantPool
+    ) -> Result<Vec<Field<'c>>, ErrorType> {
         let mut i = 0;
         let mut fields = Vec::with_capacity(field_count as usize);
 
         while i < field_count {
-            let access_flags = FieldAccessFlag::from_bits(self.read_u16_be()).unwrap();
-            let name_index = self.read_u16_be();
-            let descriptor_index = self.read_u16_be();
-            let attributes_count = self.read_u16_be();
-
-            let attributes = self.read_attributes(attributes_count, constant_pool)?;
-
-            let field = Field {
-                access_flags,
-                name_index,
-                descriptor_index,
-                attributes_count,
-                attributes
-            };
+            let field = self
+                .read_field(constant_pool)
+                .map_err(|e| e.within(format!("field #{}", i)))?;
 
             fields.push(field);
 
@@ -221,26 +288,15 @@ impl<'c> ClassParser<'c> {
     fn read_methods(
         &mut self,
         method_count: u16,
-        constant_pool: &[Constant]
-    ) -> Result<Vec<Method>, ErrorType> {
+        constant_pool: &ConstantPool
+    ) -> Result<Vec<Method<'c>>, ErrorType> {
         let mut i = 0;
         let mut methods = Vec::with_capacity(method_count as usize);
 
         while i < method_count {
-            let access_flags = MethodAccessFlag::from_bits(self.read_u16_be()).unwrap();
-            let name_index = self.read_u16_be();
-            let descriptor_index = self.read_u16_be();
-            let attributes_count = self.read_u16_be();
-
-            let attributes = self.read_attributes(attributes_count, constant_pool)?;
-
-            let method = Method {
-                access_flags,
-                name_index,
-                descriptor_index,
-                attributes_count,
-                attributes
-            };
+            let method = self
+                .read_method(constant_pool)
+                .map_err(|e| e.within(format!("method #{}", i)))?;
 
             methods.push(method);
 
@@ -250,139 +306,449 @@ impl<'c> ClassParser<'c> {
         Ok(methods)
     }
 
+    fn read_field(&mut self, constant_pool: &ConstantPool) -> Result<Field<'c>, ErrorType> {
+        let (access_flags, undefined_access_flags) =
+            self.read_flags(FieldAccessFlag::from_bits_truncate, FieldAccessFlag::bits)?;
+        let name_index = self.reader.read_u16_be()?;
+        let descriptor_index = self.reader.read_u16_be()?;
+        let attributes_count = self.reader.read_u16_be()?;
+        let attributes = self.read_attributes(attributes_count, constant_pool)?;
+
+        Ok(Field {
+            access_flags,
+            undefined_access_flags,
+            name_index,
+            descriptor_index,
+            attributes_count,
+            attributes
+        })
+    }
+
+    fn read_method(&mut self, constant_pool: &ConstantPool) -> Result<Method<'c>, ErrorType> {
+        let (access_flags, undefined_access_flags) =
+            self.read_flags(MethodAccessFlag::from_bits_truncate, MethodAccessFlag::bits)?;
+        let name_index = self.reader.read_u16_be()?;
+        let descriptor_index = self.reader.read_u16_be()?;
+        let attributes_count = self.reader.read_u16_be()?;
+        let attributes = self.read_attributes(attributes_count, constant_pool)?;
+
+        Ok(Method {
+            access_flags,
+            undefined_access_flags,
+            name_index,
+            descriptor_index,
+            attributes_count,
+            attributes
+        })
+    }
+
     fn read_attributes(
         &mut self,
         attribute_count: u16,
-        constant_pool: &[Constant]
-    ) -> Result<Vec<Attribute>, ErrorType> {
+        constant_pool: &ConstantPool
+    ) -> Result<Vec<Attribute<'c>>, ErrorType> {
         let mut i = 0;
         let mut attributes = Vec::with_capacity(attribute_count as usize);
 
+        self.descend()?;
+
         while i < attribute_count {
-            let attribute_name_index = self.read_u16_be();
-            let attribute_length = self.read_u32_be();
-
-            if let Constant::Utf8 {
-                tag: _,
-                length: _,
-                bytes
-            } = &constant_pool[(attribute_name_index - 1) as usize]
-            {
-                let s = str::from_utf8(bytes)?;
-
-                let attribute = match s {
-                    "ConstantValue" => {
-                        let constantvalue_index = self.read_u16_be();
-
-                        Attribute::ConstantValue {
-                            attribute_name_index,
-                            attribute_length,
-                            constantvalue_index
-                        }
-                    }
-                    "Code" => self.read_code_attribute(
-                        attribute_name_index,
-                        attribute_length,
-                        constant_pool
-                    )?,
-                    "InnerClasses" => {
-                        let number_of_classes = self.read_u16_be();
-                        let classes = self.read_inner_class_attributes(number_of_classes);
-
-                        Attribute::InnerClasses {
-                            attribute_name_index,
-                            attribute_length,
-                            number_of_classes,
-                            classes
-                        }
-                    }
-                    "LineNumberTable" => self
-                        .read_line_number_table_attribute(attribute_name_index, attribute_length)?,
-                    "SourceFile" => {
-                        let sourcefile_index = self.read_u16_be();
-
-                        Attribute::SourceFile {
-                            attribute_name_index,
-                            attribute_length,
-                            sourcefile_index
-                        }
-                    }
-                    "BootstrapMethods" => {
-                        let num_bootstrap_methods = self.read_u16_be();
-                        let bootstrap_methods =
-                            self.read_bootstrap_method_attributes(num_bootstrap_methods);
-
-                        Attribute::BootstrapMethods {
-                            attribute_name_index,
-                            attribute_length,
-                            num_bootstrap_methods,
-                            bootstrap_methods
-                        }
-                    }
-                    "MethodParameters" => {
-                        let parameters_count = self.bytes[self.offset];
-                        self.offset += 1;
-                        let parameters = self.read_method_parameter_attributes(parameters_count);
-
-                        Attribute::MethodParameters {
-                            attribute_name_index,
-                            attribute_length,
-                            parameters_count,
-                            parameters
-                        }
-                    }
-                    "NestMembers" => {
-                        let number_of_classes = self.read_u16_be();
-                        let mut classes = Vec::with_capacity(number_of_classes as usize);
-
-                        let mut i = 0;
-                        while i < number_of_classes {
-                            classes.push(self.read_u16_be());
-                            i += 1;
-                        }
-
-                        Attribute::NestMembers {
-                            attribute_name_index,
-                            attribute_length,
-                            number_of_classes,
-                            classes
-                        }
-                    }
-                    _ => panic!("unknown tag: `{}`", s)
-                };
+            let offset = self.reader.offset();
+            let attribute_name_index = self.reader.read_u16_be()?;
+            let name = constant_pool
+                .utf8(attribute_name_index)
+                .map_err(|e| e.or_offset(offset).within("attribute"))?;
+            let attribute = self
+                .read_attribute(attribute_name_index, &name, constant_pool)
+                .map_err(|e| e.within(format!("attribute {}", name)))?;
+
+            attributes.push(attribute);
+            i += 1;
+        }
+
+        self.depth -= 1;
+        Ok(attributes)
+    }
+
+    fn read_attribute(
+        &mut self,
+        attribute_name_index: u16,
+        name: &str,
+        constant_pool: &ConstantPool
+    ) -> Result<Attribute<'c>, ErrorType> {
+        let attribute_length = self.reader.read_u32_be()?;
+        self.reader.ensure(attribute_length as usize)?;
+        let end = self.reader.offset() + attribute_length as usize;
+
+        let attribute = match name {
+            "ConstantValue" => {
+                let constantvalue_index = self.reader.read_u16_be()?;
+
+                Attribute::ConstantValue {
+                    attribute_name_index,
+                    attribute_length,
+                    constantvalue_index
+                }
+            }
+            "Code" => {
+                self.read_code_attribute(attribute_name_index, attribute_length, constant_pool)?
+            }
+            "InnerClasses" => {
+                let number_of_classes = self.reader.read_u16_be()?;
+                let classes = self.read_inner_class_attributes(number_of_classes)?;
+
+                Attribute::InnerClasses {
+                    attribute_name_index,
+                    attribute_length,
+                    number_of_classes,
+                    classes
+                }
+            }
+            "LineNumberTable" => {
+                self.read_line_number_table_attribute(attribute_name_index, attribute_length)?
+            }
+            "SourceDebugExtension" => {
+                let debug_extension = self.reader.read_bytes(attribute_length as usize)?;
 
-                attributes.push(attribute);
-            } else {
-                return Err(ErrorType::InvalidNameIndex);
+                Attribute::SourceDebugExtension {
+                    attribute_name_index,
+                    attribute_length,
+                    debug_extension
+                }
             }
+            "LocalVariableTable" => {
+                let local_variable_table_length = self.reader.read_u16_be()?;
+                let mut local_variable_table =
+                    Vec::with_capacity(local_variable_table_length as usize);
+                let mut i = 0;
+
+                while i < local_variable_table_length {
+                    let start_pc = self.reader.read_u16_be()?;
+                    let length = self.reader.read_u16_be()?;
+                    let name_index = self.reader.read_u16_be()?;
+                    let descriptor_index = self.reader.read_u16_be()?;
+                    let index = self.reader.read_u16_be()?;
+
+                    local_variable_table.push(LocalVariableTableEntry {
+                        start_pc,
+                        length,
+                        name_index,
+                        descriptor_index,
+                        index
+                    });
+                    i += 1;
+                }
 
-            i += 1;
+                Attribute::LocalVariableTable {
+                    attribute_name_index,
+                    attribute_length,
+                    local_variable_table_length,
+                    local_variable_table
+                }
+            }
+            "LocalVariableTypeTable" => {
+                let local_variable_type_table_length = self.reader.read_u16_be()?;
+                let mut local_variable_type_table =
+                    Vec::with_capacity(local_variable_type_table_length as usize);
+                let mut i = 0;
+
+                while i < local_variable_type_table_length {
+                    let start_pc = self.reader.read_u16_be()?;
+                    let length = self.reader.read_u16_be()?;
+                    let name_index = self.reader.read_u16_be()?;
+                    let signature_index = self.reader.read_u16_be()?;
+                    let index = self.reader.read_u16_be()?;
+
+                    local_variable_type_table.push(LocalVariableTypeTableEntry {
+                        start_pc,
+                        length,
+                        name_index,
+                        signature_index,
+                        index
+                    });
+                    i += 1;
+                }
+
+                Attribute::LocalVariableTypeTable {
+                    attribute_name_index,
+                    attribute_length,
+                    local_variable_type_table_length,
+                    local_variable_type_table
+                }
+            }
+            "SourceFile" => {
+                let sourcefile_index = self.reader.read_u16_be()?;
+
+                Attribute::SourceFile {
+                    attribute_name_index,
+                    attribute_length,
+                    sourcefile_index
+                }
+            }
+            "Signature" => {
+                let signature_index = self.reader.read_u16_be()?;
+
+                Attribute::Signature {
+                    attribute_name_index,
+                    attribute_length,
+                    signature_index
+                }
+            }
+            "Exceptions" => {
+                let number_of_exceptions = self.reader.read_u16_be()?;
+                let exception_index_table = self.read_indices(number_of_exceptions)?;
+
+                Attribute::Exceptions {
+                    attribute_name_index,
+                    attribute_length,
+                    number_of_exceptions,
+                    exception_index_table
+                }
+            }
+            "EnclosingMethod" => {
+                let class_index = self.reader.read_u16_be()?;
+                let method_index = self.reader.read_u16_be()?;
+
+                Attribute::EnclosingMethod {
+                    attribute_name_index,
+                    attribute_length,
+                    class_index,
+                    method_index
+                }
+            }
+            "Synthetic" => Attribute::Synthetic {
+                attribute_name_index,
+                attribute_length
+            },
+            "Deprecated" => Attribute::Deprecated {
+                attribute_name_index,
+                attribute_length
+            },
+            "BootstrapMethods" => {
+                let num_bootstrap_methods = self.reader.read_u16_be()?;
+                let bootstrap_methods =
+                    self.read_bootstrap_method_attributes(num_bootstrap_methods)?;
+
+                Attribute::BootstrapMethods {
+                    attribute_name_index,
+                    attribute_length,
+                    num_bootstrap_methods,
+                    bootstrap_methods
+                }
+            }
+            "MethodParameters" => {
+                let parameters_count = self.reader.read_u8()?;
+                let parameters = self.read_method_parameter_attributes(parameters_count)?;
+
+                Attribute::MethodParameters {
+                    attribute_name_index,
+                    attribute_length,
+                    parameters_count,
+                    parameters
+                }
+            }
+            "NestMembers" => {
+                let number_of_classes = self.reader.read_u16_be()?;
+                let mut classes = Vec::with_capacity(number_of_classes as usize);
+
+                let mut i = 0;
+                while i < number_of_classes {
+                    classes.push(self.reader.read_u16_be()?);
+                    i += 1;
+                }
+
+                Attribute::NestMembers {
+                    attribute_name_index,
+                    attribute_length,
+                    number_of_classes,
+                    classes
+                }
+            }
+            "NestHost" => {
+                let host_class_index = self.reader.read_u16_be()?;
+
+                Attribute::NestHost {
+                    attribute_name_index,
+                    attribute_length,
+                    host_class_index
+                }
+            }
+            "Record" => {
+                let components_count = self.reader.read_u16_be()?;
+                let components = self.read_record_components(components_count, constant_pool)?;
+
+                Attribute::Record {
+                    attribute_name_index,
+                    attribute_length,
+                    components_count,
+                    components
+                }
+            }
+            "PermittedSubclasses" => {
+                let number_of_classes = self.reader.read_u16_be()?;
+                let classes = self.read_indices(number_of_classes)?;
+
+                Attribute::PermittedSubclasses {
+                    attribute_name_index,
+                    attribute_length,
+                    number_of_classes,
+                    classes
+                }
+            }
+            "StackMapTable" => {
+                let number_of_entries = self.reader.read_u16_be()?;
+                let entries = self.read_stack_map_frames(number_of_entries)?;
+
+                Attribute::StackMapTable {
+                    attribute_name_index,
+                    attribute_length,
+                    number_of_entries,
+                    entries
+                }
+            }
+            "RuntimeVisibleAnnotations" => {
+                let num_annotations = self.reader.read_u16_be()?;
+                let annotations = self.read_annotations(num_annotations)?;
+
+                Attribute::RuntimeVisibleAnnotations {
+                    attribute_name_index,
+                    attribute_length,
+                    num_annotations,
+                    annotations
+                }
+            }
+            "RuntimeInvisibleAnnotations" => {
+                let num_annotations = self.reader.read_u16_be()?;
+                let annotations = self.read_annotations(num_annotations)?;
+
+                Attribute::RuntimeInvisibleAnnotations {
+                    attribute_name_index,
+                    attribute_length,
+                    num_annotations,
+                    annotations
+                }
+            }
+            "RuntimeVisibleParameterAnnotations" => {
+                let num_parameters = self.reader.read_u8()?;
+                let parameter_annotations = self.read_parameter_annotations(num_parameters)?;
+
+                Attribute::RuntimeVisibleParameterAnnotations {
+                    attribute_name_index,
+                    attribute_length,
+                    num_parameters,
+                    parameter_annotations
+                }
+            }
+            "RuntimeInvisibleParameterAnnotations" => {
+                let num_parameters = self.reader.read_u8()?;
+                let parameter_annotations = self.read_parameter_annotations(num_parameters)?;
+
+                Attribute::RuntimeInvisibleParameterAnnotations {
+                    attribute_name_index,
+                    attribute_length,
+                    num_parameters,
+                    parameter_annotations
+                }
+            }
+            "RuntimeVisibleTypeAnnotations" => {
+                let num_annotations = self.reader.read_u16_be()?;
+                let annotations = self.read_type_annotations(num_annotations)?;
+
+                Attribute::RuntimeVisibleTypeAnnotations {
+                    attribute_name_index,
+                    attribute_length,
+                    num_annotations,
+                    annotations
+                }
+            }
+            "RuntimeInvisibleTypeAnnotations" => {
+                let num_annotations = self.reader.read_u16_be()?;
+                let annotations = self.read_type_annotations(num_annotations)?;
+
+                Attribute::RuntimeInvisibleTypeAnnotations {
+                    attribute_name_index,
+                    attribute_length,
+                    num_annotations,
+                    annotations
+                }
+            }
+            "AnnotationDefault" => {
+                let default_value = self.read_element_value()?;
+
+                Attribute::AnnotationDefault {
+                    attribute_name_index,
+                    attribute_length,
+                    default_value
+                }
+            }
+            "Module" => {
+                let module = self.read_module_attribute()?;
+
+                Attribute::Module {
+                    attribute_name_index,
+                    attribute_length,
+                    module
+                }
+            }
+            "ModulePackages" => {
+                let package_count = self.reader.read_u16_be()?;
+                let package_index = self.read_indices(package_count)?;
+
+                Attribute::ModulePackages {
+                    attribute_name_index,
+                    attribute_length,
+                    package_count,
+                    package_index
+                }
+            }
+            "ModuleMainClass" => {
+                let main_class_index = self.reader.read_u16_be()?;
+
+                Attribute::ModuleMainClass {
+                    attribute_name_index,
+                    attribute_length,
+                    main_class_index
+                }
+            }
+            _ => {
+                let info = self.reader.read_bytes(attribute_length as usize)?;
+
+                Attribute::Unknown {
+                    attribute_name_index,
+                    info
+                }
+            }
+        };
+
+        if self.reader.offset() != end {
+            return Err(self.reader.error(ErrorKind::Malformed(
+                "attribute contents do not match attribute_length"
+            )));
         }
 
-        Ok(attributes)
+        Ok(attribute)
     }
 
     fn read_code_attribute(
         &mut self,
         attribute_name_index: u16,
         attribute_length: u32,
-        constant_pool: &[Constant]
-    ) -> Result<Attribute, ErrorType> {
-        let max_stack = self.read_u16_be();
-        let max_locals = self.read_u16_be();
-        let code_length = self.read_u32_be();
-        let (offset, code) = self.read_instructions(code_length)?;
-        self.offset = offset;
-
-        let exception_table_length = self.read_u16_be();
+        constant_pool: &ConstantPool
+    ) -> Result<Attribute<'c>, ErrorType> {
+        let max_stack = self.reader.read_u16_be()?;
+        let max_locals = self.reader.read_u16_be()?;
+        let code_length = self.reader.read_u32_be()?;
+        let code = self.read_instructions(code_length)?;
+
+        let exception_table_length = self.reader.read_u16_be()?;
         let mut exception_table = Vec::with_capacity(exception_table_length as usize);
         let mut i = 0;
 
         while i < exception_table_length {
-            let start_pc = self.read_u16_be();
-            let end_pc = self.read_u16_be();
-            let handler_pc = self.read_u16_be();
-            let catch_type = self.read_u16_be();
+            let start_pc = self.reader.read_u16_be()?;
+            let end_pc = self.reader.read_u16_be()?;
+            let handler_pc = self.reader.read_u16_be()?;
+            let catch_type = self.reader.read_u16_be()?;
 
             let entry = ExceptionTableEntry {
                 start_pc,
@@ -395,7 +761,7 @@ impl<'c> ClassParser<'c> {
             i += 1;
         }
 
-        let attributes_count = self.read_u16_be();
+        let attributes_count = self.reader.read_u16_be()?;
         let attributes = self.read_attributes(attributes_count, constant_pool)?;
 
         Ok(Attribute::Code {
@@ -412,65 +778,75 @@ impl<'c> ClassParser<'c> {
         })
     }
 
-    fn read_inner_class_attributes(&mut self, number_of_classes: u16) -> Vec<InnerClassAttribute> {
+    fn read_inner_class_attributes(
+        &mut self,
+        number_of_classes: u16
+    ) -> Result<Vec<InnerClassAttribute>, ErrorType> {
         let mut classes = Vec::with_capacity(number_of_classes as usize);
         let mut i = 0;
 
         while i < number_of_classes {
-            let inner_class_info_index = self.read_u16_be();
-            let outer_class_info_index = self.read_u16_be();
-            let inner_name_index = self.read_u16_be();
-            let inner_class_access_flags =
-                InnerClassAccessFlag::from_bits(self.read_u16_be()).unwrap();
+            let inner_class_info_index = self.reader.read_u16_be()?;
+            let outer_class_info_index = self.reader.read_u16_be()?;
+            let inner_name_index = self.reader.read_u16_be()?;
+            let (inner_class_access_flags, undefined_inner_class_access_flags) = self.read_flags(
+                InnerClassAccessFlag::from_bits_truncate,
+                InnerClassAccessFlag::bits
+            )?;
 
             classes.push(InnerClassAttribute {
                 inner_class_info_index,
                 outer_class_info_index,
                 inner_name_index,
-                inner_class_access_flags
+                inner_class_access_flags,
+                undefined_inner_class_access_flags
             });
             i += 1;
         }
 
-        classes
+        Ok(classes)
     }
 
     fn read_method_parameter_attributes(
         &mut self,
         parameters_count: u8
-    ) -> Vec<ParameterAttribute> {
+    ) -> Result<Vec<ParameterAttribute>, ErrorType> {
         let mut parameters = Vec::with_capacity(parameters_count as usize);
         let mut i = 0;
 
         while i < parameters_count {
-            let name_index = self.read_u16_be();
-            let access_flags = ParameterAccessFlag::from_bits(self.read_u16_be()).unwrap();
+            let name_index = self.reader.read_u16_be()?;
+            let (access_flags, undefined_access_flags) = self.read_flags(
+                ParameterAccessFlag::from_bits_truncate,
+                ParameterAccessFlag::bits
+            )?;
 
             parameters.push(ParameterAttribute {
                 name_index,
-                access_flags
+                access_flags,
+                undefined_access_flags
             });
             i += 1;
         }
 
-        parameters
+        Ok(parameters)
     }
 
     fn read_bootstrap_method_attributes(
         &mut self,
         num_bootstrap_methods: u16
-    ) -> Vec<BootstrapMethodAttribute> {
+    ) -> Result<Vec<BootstrapMethodAttribute>, ErrorType> {
         let mut methods = Vec::with_capacity(num_bootstrap_methods as usize);
         let mut i = 0;
 
         while i < num_bootstrap_methods {
-            let bootstrap_method_ref = self.read_u16_be();
-            let num_bootstrap_arguments = self.read_u16_be();
+            let bootstrap_method_ref = self.reader.read_u16_be()?;
+            let num_bootstrap_arguments = self.reader.read_u16_be()?;
             let mut bootstrap_arguments = Vec::with_capacity(num_bootstrap_arguments as usize);
             let mut j = 0;
 
             while j < num_bootstrap_arguments {
-                bootstrap_arguments.push(self.read_u16_be());
+                bootstrap_arguments.push(self.reader.read_u16_be()?);
                 j += 1;
             }
 
@@ -483,21 +859,21 @@ impl<'c> ClassParser<'c> {
             i += 1;
         }
 
-        methods
+        Ok(methods)
     }
 
     fn read_line_number_table_attribute(
         &mut self,
         attribute_name_index: u16,
         attribute_length: u32
-    ) -> Result<Attribute, ErrorType> {
-        let line_number_table_length = self.read_u16_be();
+    ) -> Result<Attribute<'c>, ErrorType> {
+        let line_number_table_length = self.reader.read_u16_be()?;
         let mut i = 0;
         let mut line_number_table = Vec::with_capacity(line_number_table_length as usize);
 
         while i < line_number_table_length {
-            let start_pc = self.read_u16_be();
-            let line_number = self.read_u16_be();
+            let start_pc = self.reader.read_u16_be()?;
+            let line_number = self.reader.read_u16_be()?;
 
             let entry = LineNumberTableEntry {
                 start_pc,
@@ -516,170 +892,1136 @@ impl<'c> ClassParser<'c> {
         })
     }
 
-    fn read_instructions(
+    fn read_record_components(
         &mut self,
-        code_length: u32
-    ) -> Result<(usize, Vec<Instruction>), ErrorType> {
-        let mut offset = self.offset;
-        let mut instructions = Vec::with_capacity(code_length as usize);
-
-        while offset < self.offset + code_length as usize {
-            let opcode = Opcode::try_from(self.bytes[offset])?;
-            offset += 1;
-
-            let ins = match opcode {
-                Opcode::aload_0 => Instruction::aload_0,
-                Opcode::aload_1 => Instruction::aload_1,
-                Opcode::aload_2 => Instruction::aload_2,
-                Opcode::aload_3 => Instruction::aload_3,
-                Opcode::astore => {
-                    let index = self.bytes[offset];
-                    offset += 1;
-
-                    Instruction::astore { index }
-                }
-                Opcode::astore_0 => Instruction::astore_0,
-                Opcode::astore_1 => Instruction::astore_1,
-                Opcode::astore_2 => Instruction::astore_2,
-                Opcode::astore_3 => Instruction::astore_3,
-                Opcode::dup => Instruction::dup,
-                Opcode::bipush => {
-                    let byte = self.bytes[offset];
-                    offset += 1;
-
-                    Instruction::bipush { byte }
-                }
-                Opcode::new => {
-                    let indexbyte1 = self.bytes[offset];
-                    let indexbyte2 = self.bytes[offset + 1];
-                    offset += 2;
-
-                    Instruction::new {
-                        indexbyte1,
-                        indexbyte2
-                    }
-                }
-                Opcode::r#eturn => Instruction::r#eturn,
-                Opcode::invokedynamic => {
-                    let indexbyte1 = self.bytes[offset];
-                    let indexbyte2 = self.bytes[offset + 1];
-                    let byte3 = self.bytes[offset + 2];
-                    let byte4 = self.bytes[offset + 3];
-                    offset += 4;
+        components_count: u16,
+        constant_pool: &ConstantPool
+    ) -> Result<Vec<RecordComponentInfo<'c>>, ErrorType> {
+        let mut components = Vec::with_capacity(components_count as usize);
+        let mut i = 0;
 
-                    if byte3 != 0 || byte4 != 0 {
-                        return Err(ErrorType::ParseError);
-                    }
+        while i < components_count {
+            let name_index = self.reader.read_u16_be()?;
+            let descriptor_index = self.reader.read_u16_be()?;
+            let attributes_count = self.reader.read_u16_be()?;
+            let attributes = self
+                .read_attributes(attributes_count, constant_pool)
+                .map_err(|e| e.within(format!("record component #{}", i)))?;
 
-                    Instruction::invokedynamic {
-                        indexbyte1,
-                        indexbyte2,
-                        byte3,
-                        byte4
-                    }
-                }
-                Opcode::invokestatic => {
-                    let indexbyte1 = self.bytes[offset];
-                    let indexbyte2 = self.bytes[offset + 1];
-                    offset += 2;
+            components.push(RecordComponentInfo {
+                name_index,
+                descriptor_index,
+                attributes_count,
+                attributes
+            });
+            i += 1;
+        }
 
-                    Instruction::invokestatic {
-                        indexbyte1,
-                        indexbyte2
-                    }
-                }
-                Opcode::invokespecial => {
-                    let indexbyte1 = self.bytes[offset];
-                    let indexbyte2 = self.bytes[offset + 1];
-                    offset += 2;
+        Ok(components)
+    }
 
-                    Instruction::invokespecial {
-                        indexbyte1,
-                        indexbyte2
-                    }
-                }
-                Opcode::invokevirtual => {
-                    let indexbyte1 = self.bytes[offset];
-                    let indexbyte2 = self.bytes[offset + 1];
-                    offset += 2;
+    fn read_module_attribute(&mut self) -> Result<ModuleAttribute, ErrorType> {
+        let module_name_index = self.reader.read_u16_be()?;
+        let (module_flags, undefined_module_flags) =
+            self.read_flags(ModuleAccessFlag::from_bits_truncate, ModuleAccessFlag::bits)?;
+        let module_version_index = self.reader.read_u16_be()?;
 
-                    Instruction::invokevirtual {
-                        indexbyte1,
-                        indexbyte2
-                    }
-                }
-                Opcode::getstatic => {
-                    let indexbyte1 = self.bytes[offset];
-                    let indexbyte2 = self.bytes[offset + 1];
-                    offset += 2;
+        let requires_count = self.reader.read_u16_be()?;
+        let mut requires = Vec::with_capacity(requires_count as usize);
+        let mut i = 0;
 
-                    Instruction::getstatic {
-                        indexbyte1,
-                        indexbyte2
-                    }
-                }
-                Opcode::ldc => {
-                    let index = self.bytes[offset];
-                    offset += 1;
+        while i < requires_count {
+            let requires_index = self.reader.read_u16_be()?;
+            let (requires_flags, undefined_requires_flags) =
+                self.read_flags(RequiresFlag::from_bits_truncate, RequiresFlag::bits)?;
+            let requires_version_index = self.reader.read_u16_be()?;
+
+            requires.push(ModuleRequires {
+                requires_index,
+                requires_flags,
+                undefined_requires_flags,
+                requires_version_index
+            });
+            i += 1;
+        }
 
-                    Instruction::ldc { index }
-                }
-                _ => unimplemented!("Unsupported opcode {:?}", opcode)
-            };
+        let exports_count = self.reader.read_u16_be()?;
+        let mut exports = Vec::with_capacity(exports_count as usize);
+        let mut i = 0;
+
+        while i < exports_count {
+            let exports_index = self.reader.read_u16_be()?;
+            let (exports_flags, undefined_exports_flags) =
+                self.read_flags(ExportsFlag::from_bits_truncate, ExportsFlag::bits)?;
+            let exports_to_count = self.reader.read_u16_be()?;
+            let exports_to_index = self.read_indices(exports_to_count)?;
+
+            exports.push(ModuleExports {
+                exports_index,
+                exports_flags,
+                undefined_exports_flags,
+                exports_to_count,
+                exports_to_index
+            });
+            i += 1;
+        }
+
+        let opens_count = self.reader.read_u16_be()?;
+        let mut opens = Vec::with_capacity(opens_count as usize);
+        let mut i = 0;
+
+        while i < opens_count {
+            let opens_index = self.reader.read_u16_be()?;
+            let (opens_flags, undefined_opens_flags) =
+                self.read_flags(OpensFlag::from_bits_truncate, OpensFlag::bits)?;
+            let opens_to_count = self.reader.read_u16_be()?;
+            let opens_to_index = self.read_indices(opens_to_count)?;
+
+            opens.push(ModuleOpens {
+                opens_index,
+                opens_flags,
+                undefined_opens_flags,
+                opens_to_count,
+                opens_to_index
+            });
+            i += 1;
+        }
+
+        let uses_count = self.reader.read_u16_be()?;
+        let uses_index = self.read_indices(uses_count)?;
 
-            instructions.push(ins);
+        let provides_count = self.reader.read_u16_be()?;
+        let mut provides = Vec::with_capacity(provides_count as usize);
+        let mut i = 0;
+
+        while i < provides_count {
+            let provides_index = self.reader.read_u16_be()?;
+            let provides_with_count = self.reader.read_u16_be()?;
+            let provides_with_index = self.read_indices(provides_with_count)?;
+
+            provides.push(ModuleProvides {
+                provides_index,
+                provides_with_count,
+                provides_with_index
+            });
+            i += 1;
         }
 
-        Ok((offset, instructions))
+        Ok(ModuleAttribute {
+            module_name_index,
+            module_flags,
+            undefined_module_flags,
+            module_version_index,
+            requires_count,
+            requires,
+            exports_count,
+            exports,
+            opens_count,
+            opens,
+            uses_count,
+            uses_index,
+            provides_count,
+            provides
+        })
     }
-}
 
-#[cfg(test)]
-mod test {
-    macro_rules! expect_pat {
-        ($expected: pat, $expr: expr, $then: block) => {
-            if let $expected = $expr $then
-            else {
-                panic!("Expected {:?}, found {:?}", stringify!($expected), $expr);
-            }
-        };
+    /// Reads access or module flags, along with the bits which are not defined by the flags type
+    fn read_flags<F>(
+        &mut self,
+        from_bits_truncate: fn(u16) -> F,
+        bits: fn(&F) -> u16
+    ) -> Result<(F, u16), ErrorType> {
+        let raw = self.reader.read_u16_be()?;
+        let flags = from_bits_truncate(raw);
+        let undefined = raw & !bits(&flags);
+
+        Ok((flags, undefined))
     }
 
-    use super::ClassParser;
-    use crate::attribute::Attribute;
-    use crate::constant_pool::Constant;
-    use crate::error::ErrorType;
-    use std::{fs::File, io::Read, path::Path, str};
+    /// Reads a table of `count` constant pool indices
+    fn read_indices(&mut self, count: u16) -> Result<Vec<u16>, ErrorType> {
+        let mut indices = Vec::with_capacity(count as usize);
+        let mut i = 0;
 
-    fn read_class_file(p: &str) -> Result<Vec<u8>, ErrorType> {
-        let mut f = File::open(Path::new(p)).unwrap();
-        let mut buf = Vec::with_capacity(64);
+        while i < count {
+            indices.push(self.reader.read_u16_be()?);
+            i += 1;
+        }
 
-        f.read_to_end(&mut buf).unwrap();
-        Ok(buf)
+        Ok(indices)
     }
 
-    #[test]
-    fn parse_simple() {
-        let buf = read_class_file("./tests/Hello.class").unwrap();
-        let class = ClassParser::from_bytes(&buf)
-            .parse()
-            .unwrap();
+    fn read_annotations(&mut self, num_annotations: u16) -> Result<Vec<Annotation>, ErrorType> {
+        let mut annotations = Vec::with_capacity(num_annotations as usize);
+        let mut i = 0;
 
-        assert_eq!(class.magic, 0xCAFEBABE);
-        assert_eq!(class.methods_count, 2);
-        assert_eq!(class.field_count, 0);
+        while i < num_annotations {
+            annotations.push(self.read_annotation()?);
+            i += 1;
+        }
 
-        let constant_pool = class.constant_pool;
+        Ok(annotations)
+    }
 
-        expect_pat!(Constant::Class {tag: _, name_index}, &constant_pool[(class.this_class - 1) as usize], {
-            expect_pat!(Constant::Utf8 {tag: _, length: _, bytes}, &constant_pool[(name_index - 1) as usize], {
-                assert_eq!(str::from_utf8(bytes).unwrap(), "Hello");
+    fn read_parameter_annotations(
+        &mut self,
+        num_parameters: u8
+    ) -> Result<Vec<ParameterAnnotations>, ErrorType> {
+        let mut parameter_annotations = Vec::with_capacity(num_parameters as usize);
+        let mut i = 0;
+
+        while i < num_parameters {
+            let num_annotations = self.reader.read_u16_be()?;
+            let annotations = self.read_annotations(num_annotations)?;
+
+            parameter_annotations.push(ParameterAnnotations {
+                num_annotations,
+                annotations
+            });
+            i += 1;
+        }
+
+        Ok(parameter_annotations)
+    }
+
+    fn read_annotation(&mut self) -> Result<Annotation, ErrorType> {
+        let type_index = self.reader.read_u16_be()?;
+        let num_element_value_pairs = self.reader.read_u16_be()?;
+        let element_value_pairs = self.read_element_value_pairs(num_element_value_pairs)?;
+
+        Ok(Annotation {
+            type_index,
+            num_element_value_pairs,
+            element_value_pairs
+        })
+    }
+
+    fn read_element_value_pairs(
+        &mut self,
+        num_element_value_pairs: u16
+    ) -> Result<Vec<ElementValuePair>, ErrorType> {
+        let mut element_value_pairs = Vec::with_capacity(num_element_value_pairs as usize);
+        let mut i = 0;
+
+        while i < num_element_value_pairs {
+            let element_name_index = self.reader.read_u16_be()?;
+            let value = self.read_element_value()?;
+
+            element_value_pairs.push(ElementValuePair {
+                element_name_index,
+                value
+            });
+            i += 1;
+        }
+
+        Ok(element_value_pairs)
+    }
+
+    fn read_type_annotations(
+        &mut self,
+        num_annotations: u16
+    ) -> Result<Vec<TypeAnnotation>, ErrorType> {
+        let mut annotations = Vec::with_capacity(num_annotations as usize);
+        let mut i = 0;
+
+        while i < num_annotations {
+            annotations.push(self.read_type_annotation()?);
+            i += 1;
+        }
+
+        Ok(annotations)
+    }
+
+    fn read_type_annotation(&mut self) -> Result<TypeAnnotation, ErrorType> {
+        let offset = self.reader.offset();
+        let target_type = self.reader.read_u8()?;
+
+        let target_info = match target_type {
+            0x00 | 0x01 => TargetInfo::TypeParameter {
+                type_parameter_index: self.reader.read_u8()?
+            },
+            0x10 => TargetInfo::Supertype {
+                supertype_index: self.reader.read_u16_be()?
+            },
+            0x11 | 0x12 => {
+                let type_parameter_index = self.reader.read_u8()?;
+                let bound_index = self.reader.read_u8()?;
+
+                TargetInfo::TypeParameterBound {
+                    type_parameter_index,
+                    bound_index
+                }
+            }
+            0x13..=0x15 => TargetInfo::Empty,
+            0x16 => TargetInfo::FormalParameter {
+                formal_parameter_index: self.reader.read_u8()?
+            },
+            0x17 => TargetInfo::Throws {
+                throws_type_index: self.reader.read_u16_be()?
+            },
+            0x40 | 0x41 => {
+                let table_length = self.reader.read_u16_be()?;
+                let mut table = Vec::with_capacity(table_length as usize);
+                let mut i = 0;
+
+                while i < table_length {
+                    let start_pc = self.reader.read_u16_be()?;
+                    let length = self.reader.read_u16_be()?;
+                    let index = self.reader.read_u16_be()?;
+
+                    table.push(LocalvarTargetEntry {
+                        start_pc,
+                        length,
+                        index
+                    });
+                    i += 1;
+                }
+
+                TargetInfo::Localvar {
+                    table_length,
+                    table
+                }
+            }
+            0x42 => TargetInfo::Catch {
+                exception_table_index: self.reader.read_u16_be()?
+            },
+            0x43..=0x46 => TargetInfo::Offset {
+                offset: self.reader.read_u16_be()?
+            },
+            0x47..=0x4b => {
+                let offset = self.reader.read_u16_be()?;
+                let type_argument_index = self.reader.read_u8()?;
+
+                TargetInfo::TypeArgument {
+                    offset,
+                    type_argument_index
+                }
+            }
+            _ => {
+                return Err(ErrorType::at(
+                    ErrorKind::Malformed("invalid type annotation target type"),
+                    offset
+                ))
+            }
+        };
+
+        let path_length = self.reader.read_u8()?;
+        let mut path = Vec::with_capacity(path_length as usize);
+        let mut i = 0;
+
+        while i < path_length {
+            let type_path_kind = self.reader.read_u8()?;
+            let type_argument_index = self.reader.read_u8()?;
+
+            path.push(TypePathEntry {
+                type_path_kind,
+                type_argument_index
+            });
+            i += 1;
+        }
+
+        let type_index = self.reader.read_u16_be()?;
+        let num_element_value_pairs = self.reader.read_u16_be()?;
+        let element_value_pairs = self.read_element_value_pairs(num_element_value_pairs)?;
+
+        Ok(TypeAnnotation {
+            target_type,
+            target_info,
+            target_path: TypePath { path_length, path },
+            type_index,
+            num_element_value_pairs,
+            element_value_pairs
+        })
+    }
+
+    fn read_element_value(&mut self) -> Result<ElementValue, ErrorType> {
+        let offset = self.reader.offset();
+        let tag = self.reader.read_u8()?;
+        self.descend()?;
+
+        let value = match tag {
+            b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b's' => ElementValue::Const {
+                tag,
+                const_value_index: self.reader.read_u16_be()?
+            },
+            b'e' => {
+                let type_name_index = self.reader.read_u16_be()?;
+                let const_name_index = self.reader.read_u16_be()?;
+
+                ElementValue::Enum {
+                    type_name_index,
+                    const_name_index
+                }
+            }
+            b'c' => ElementValue::Class {
+                class_info_index: self.reader.read_u16_be()?
+            },
+            b'@' => ElementValue::Annotation {
+                annotation_value: self.read_annotation()?
+            },
+            b'[' => {
+                let num_values = self.reader.read_u16_be()?;
+                let mut values = Vec::with_capacity(num_values as usize);
+                let mut i = 0;
+
+                while i < num_values {
+                    values.push(self.read_element_value()?);
+                    i += 1;
+                }
+
+                ElementValue::Array { num_values, values }
+            }
+            _ => {
+                return Err(ErrorType::at(
+                    ErrorKind::Malformed("invalid element value tag"),
+                    offset
+                ))
+            }
+        };
+
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    /// Tracks entering a nested structure, failing if the nesting gets too deep
+    fn descend(&mut self) -> Result<(), ErrorType> {
+        if self.depth == MAX_DEPTH {
+            return Err(self
+                .reader
+                .error(ErrorKind::Malformed("structures are nested too deeply")));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn read_stack_map_frames(
+        &mut self,
+        number_of_entries: u16
+    ) -> Result<Vec<StackMapFrame>, ErrorType> {
+        let mut entries = Vec::with_capacity(number_of_entries as usize);
+        let mut i = 0;
+
+        while i < number_of_entries {
+            let offset = self.reader.offset();
+            let frame_type = self.reader.read_u8()?;
+
+            let frame = match frame_type {
+                0..=63 => StackMapFrame::Same { frame_type },
+                64..=127 => {
+                    let stack = self.read_verification_type_info()?;
+
+                    StackMapFrame::SameLocals1StackItem { frame_type, stack }
+                }
+                247 => {
+                    let offset_delta = self.reader.read_u16_be()?;
+                    let stack = self.read_verification_type_info()?;
+
+                    StackMapFrame::SameLocals1StackItemExtended {
+                        frame_type,
+                        offset_delta,
+                        stack
+                    }
+                }
+                248..=250 => {
+                    let offset_delta = self.reader.read_u16_be()?;
+
+                    StackMapFrame::Chop {
+                        frame_type,
+                        offset_delta
+                    }
+                }
+                251 => {
+                    let offset_delta = self.reader.read_u16_be()?;
+
+                    StackMapFrame::SameExtended {
+                        frame_type,
+                        offset_delta
+                    }
+                }
+                252..=254 => {
+                    let offset_delta = self.reader.read_u16_be()?;
+                    let locals = self.read_verification_type_infos((frame_type - 251) as u16)?;
+
+                    StackMapFrame::Append {
+                        frame_type,
+                        offset_delta,
+                        locals
+                    }
+                }
+                255 => {
+                    let offset_delta = self.reader.read_u16_be()?;
+                    let number_of_locals = self.reader.read_u16_be()?;
+                    let locals = self.read_verification_type_infos(number_of_locals)?;
+                    let number_of_stack_items = self.reader.read_u16_be()?;
+                    let stack = self.read_verification_type_infos(number_of_stack_items)?;
+
+                    StackMapFrame::Full {
+                        frame_type,
+                        offset_delta,
+                        number_of_locals,
+                        locals,
+                        number_of_stack_items,
+                        stack
+                    }
+                }
+                _ => {
+                    return Err(ErrorType::at(
+                        ErrorKind::Malformed("reserved stack map frame type"),
+                        offset
+                    ))
+                }
+            };
+
+            entries.push(frame);
+            i += 1;
+        }
+
+        Ok(entries)
+    }
+
+    fn read_verification_type_infos(
+        &mut self,
+        count: u16
+    ) -> Result<Vec<VerificationTypeInfo>, ErrorType> {
+        let mut types = Vec::with_capacity(count as usize);
+        let mut i = 0;
+
+        while i < count {
+            types.push(self.read_verification_type_info()?);
+            i += 1;
+        }
+
+        Ok(types)
+    }
+
+    fn read_verification_type_info(&mut self) -> Result<VerificationTypeInfo, ErrorType> {
+        let offset = self.reader.offset();
+
+        let info = match self.reader.read_u8()? {
+            0 => VerificationTypeInfo::Top,
+            1 => VerificationTypeInfo::Integer,
+            2 => VerificationTypeInfo::Float,
+            3 => VerificationTypeInfo::Double,
+            4 => VerificationTypeInfo::Long,
+            5 => VerificationTypeInfo::Null,
+            6 => VerificationTypeInfo::UninitializedThis,
+            7 => VerificationTypeInfo::Object {
+                cpool_index: self.reader.read_u16_be()?
+            },
+            8 => VerificationTypeInfo::Uninitialized {
+                offset: self.reader.read_u16_be()?
+            },
+            _ => {
+                return Err(ErrorType::at(
+                    ErrorKind::Malformed("invalid verification type tag"),
+                    offset
+                ))
+            }
+        };
+
+        Ok(info)
+    }
+
+    /// Returns the number of padding bytes following a `tableswitch` or `lookupswitch` opcode,
+    /// given the offset of the first byte after the opcode relative to the start of the method
+    fn switch_padding(pc: usize) -> usize {
+        (4 - pc % 4) % 4
+    }
+
+    fn read_instructions(&mut self, code_length: u32) -> Result<Bytecode, ErrorType> {
+        self.reader.ensure(code_length as usize)?;
+
+        let start = self.reader.offset();
+        let end = start + code_length as usize;
+        let mut instructions = Bytecode::default();
+
+        while self.reader.offset() < end {
+            let pc = self.reader.offset() - start;
+            let ins = self
+                .read_instruction(start)
+                .map_err(|e| e.within(format!("instruction at pc {}", pc)))?;
+
+            instructions.push(pc as u32, ins);
+        }
+
+        if self.reader.offset() != end {
+            return Err(self
+                .reader
+                .error(ErrorKind::Malformed("instruction exceeds code_length")));
+        }
+
+        Ok(instructions)
+    }
+
+    /// Reads a single instruction. `start` is the offset of the first instruction of the method,
+    /// which `tableswitch` and `lookupswitch` padding is relative to
+    fn read_instruction(&mut self, start: usize) -> Result<Instruction, ErrorType> {
+        let opcode = self.read_opcode()?;
+
+        let ins = match opcode {
+            Opcode::aaload => Instruction::aaload,
+            Opcode::aastore => Instruction::aastore,
+            Opcode::aconst_null => Instruction::aconst_null,
+            Opcode::aload => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::aload { index }
+            }
+            Opcode::aload_0 => Instruction::aload_0,
+            Opcode::aload_1 => Instruction::aload_1,
+            Opcode::aload_2 => Instruction::aload_2,
+            Opcode::aload_3 => Instruction::aload_3,
+            Opcode::anewarray => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::anewarray { cp_index }
+            }
+            Opcode::areturn => Instruction::areturn,
+            Opcode::arraylength => Instruction::arraylength,
+            Opcode::astore => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::astore { index }
+            }
+            Opcode::astore_0 => Instruction::astore_0,
+            Opcode::astore_1 => Instruction::astore_1,
+            Opcode::astore_2 => Instruction::astore_2,
+            Opcode::astore_3 => Instruction::astore_3,
+            Opcode::athrow => Instruction::athrow,
+            Opcode::baload => Instruction::baload,
+            Opcode::bastore => Instruction::bastore,
+            Opcode::bipush => {
+                let value = self.reader.read_u8()? as i8;
+
+                Instruction::bipush { value }
+            }
+            Opcode::caload => Instruction::caload,
+            Opcode::castore => Instruction::castore,
+            Opcode::checkcast => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::checkcast { cp_index }
+            }
+            Opcode::d2f => Instruction::d2f,
+            Opcode::d2i => Instruction::d2i,
+            Opcode::d2l => Instruction::d2l,
+            Opcode::dadd => Instruction::dadd,
+            Opcode::daload => Instruction::daload,
+            Opcode::dastore => Instruction::dastore,
+            Opcode::dcmpg => Instruction::dcmpg,
+            Opcode::dcmpl => Instruction::dcmpl,
+            Opcode::dconst_0 => Instruction::dconst_0,
+            Opcode::dconst_1 => Instruction::dconst_1,
+            Opcode::ddiv => Instruction::ddiv,
+            Opcode::dload => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::dload { index }
+            }
+            Opcode::dload_0 => Instruction::dload_0,
+            Opcode::dload_1 => Instruction::dload_1,
+            Opcode::dload_2 => Instruction::dload_2,
+            Opcode::dload_3 => Instruction::dload_3,
+            Opcode::dmul => Instruction::dmul,
+            Opcode::dneg => Instruction::dneg,
+            Opcode::drem => Instruction::drem,
+            Opcode::dreturn => Instruction::dreturn,
+            Opcode::dstore => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::dstore { index }
+            }
+            Opcode::dstore_0 => Instruction::dstore_0,
+            Opcode::dstore_1 => Instruction::dstore_1,
+            Opcode::dstore_2 => Instruction::dstore_2,
+            Opcode::dstore_3 => Instruction::dstore_3,
+            Opcode::dsub => Instruction::dsub,
+            Opcode::dup => Instruction::dup,
+            Opcode::dup_x1 => Instruction::dup_x1,
+            Opcode::dup_x2 => Instruction::dup_x2,
+            Opcode::dup2 => Instruction::dup2,
+            Opcode::dup2_x1 => Instruction::dup2_x1,
+            Opcode::dup2_x2 => Instruction::dup2_x2,
+            Opcode::f2d => Instruction::f2d,
+            Opcode::f2i => Instruction::f2i,
+            Opcode::f2l => Instruction::f2l,
+            Opcode::fadd => Instruction::fadd,
+            Opcode::faload => Instruction::faload,
+            Opcode::fastore => Instruction::fastore,
+            Opcode::fcmpg => Instruction::fcmpg,
+            Opcode::fcmpl => Instruction::fcmpl,
+            Opcode::fconst_0 => Instruction::fconst_0,
+            Opcode::fconst_1 => Instruction::fconst_1,
+            Opcode::fconst_2 => Instruction::fconst_2,
+            Opcode::fdiv => Instruction::fdiv,
+            Opcode::fload => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::fload { index }
+            }
+            Opcode::fload_0 => Instruction::fload_0,
+            Opcode::fload_1 => Instruction::fload_1,
+            Opcode::fload_2 => Instruction::fload_2,
+            Opcode::fload_3 => Instruction::fload_3,
+            Opcode::fmul => Instruction::fmul,
+            Opcode::fneg => Instruction::fneg,
+            Opcode::frem => Instruction::frem,
+            Opcode::freturn => Instruction::freturn,
+            Opcode::fstore => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::fstore { index }
+            }
+            Opcode::fstore_0 => Instruction::fstore_0,
+            Opcode::fstore_1 => Instruction::fstore_1,
+            Opcode::fstore_2 => Instruction::fstore_2,
+            Opcode::fstore_3 => Instruction::fstore_3,
+            Opcode::fsub => Instruction::fsub,
+            Opcode::getfield => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::getfield { cp_index }
+            }
+            Opcode::getstatic => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::getstatic { cp_index }
+            }
+            Opcode::goto => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::goto { offset }
+            }
+            Opcode::goto_w => {
+                let offset = self.reader.read_i32_be()?;
+
+                Instruction::goto_w { offset }
+            }
+            Opcode::i2b => Instruction::i2b,
+            Opcode::i2c => Instruction::i2c,
+            Opcode::i2d => Instruction::i2d,
+            Opcode::i2f => Instruction::i2f,
+            Opcode::i2l => Instruction::i2l,
+            Opcode::i2s => Instruction::i2s,
+            Opcode::iadd => Instruction::iadd,
+            Opcode::iaload => Instruction::iaload,
+            Opcode::iand => Instruction::iand,
+            Opcode::iastore => Instruction::iastore,
+            Opcode::iconst_m1 => Instruction::iconst_m1,
+            Opcode::iconst_0 => Instruction::iconst_0,
+            Opcode::iconst_1 => Instruction::iconst_1,
+            Opcode::iconst_2 => Instruction::iconst_2,
+            Opcode::iconst_3 => Instruction::iconst_3,
+            Opcode::iconst_4 => Instruction::iconst_4,
+            Opcode::iconst_5 => Instruction::iconst_5,
+            Opcode::idiv => Instruction::idiv,
+            Opcode::if_acmpeq => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::if_acmpeq { offset }
+            }
+            Opcode::if_acmpne => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::if_acmpne { offset }
+            }
+            Opcode::if_icmpeq => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::if_icmpeq { offset }
+            }
+            Opcode::if_icmpne => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::if_icmpne { offset }
+            }
+            Opcode::if_icmplt => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::if_icmplt { offset }
+            }
+            Opcode::if_icmpge => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::if_icmpge { offset }
+            }
+            Opcode::if_icmpgt => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::if_icmpgt { offset }
+            }
+            Opcode::if_icmple => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::if_icmple { offset }
+            }
+            Opcode::ifeq => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::ifeq { offset }
+            }
+            Opcode::ifne => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::ifne { offset }
+            }
+            Opcode::iflt => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::iflt { offset }
+            }
+            Opcode::ifge => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::ifge { offset }
+            }
+            Opcode::ifgt => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::ifgt { offset }
+            }
+            Opcode::ifle => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::ifle { offset }
+            }
+            Opcode::ifnonnull => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::ifnonnull { offset }
+            }
+            Opcode::ifnull => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::ifnull { offset }
+            }
+            Opcode::iinc => {
+                let index = self.reader.read_u8()?;
+                let r#const = self.reader.read_u8()? as i8;
+
+                Instruction::iinc { index, r#const }
+            }
+            Opcode::iload => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::iload { index }
+            }
+            Opcode::iload_0 => Instruction::iload_0,
+            Opcode::iload_1 => Instruction::iload_1,
+            Opcode::iload_2 => Instruction::iload_2,
+            Opcode::iload_3 => Instruction::iload_3,
+            Opcode::imul => Instruction::imul,
+            Opcode::ineg => Instruction::ineg,
+            Opcode::instanceof => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::instanceof { cp_index }
+            }
+            Opcode::invokedynamic => {
+                let cp_index = self.reader.read_u16_be()?;
+                let byte3 = self.reader.read_u8()?;
+                let byte4 = self.reader.read_u8()?;
+
+                if byte3 != 0 || byte4 != 0 {
+                    return Err(self.reader.error(ErrorKind::Malformed(
+                        "invokedynamic operands 3 and 4 must be zero"
+                    )));
+                }
+
+                Instruction::invokedynamic { cp_index }
+            }
+            Opcode::invokeinterface => {
+                let cp_index = self.reader.read_u16_be()?;
+                let count = self.reader.read_u8()?;
+                let byte4 = self.reader.read_u8()?;
+
+                if byte4 != 0 {
+                    return Err(self.reader.error(ErrorKind::Malformed(
+                        "invokeinterface operand 4 must be zero"
+                    )));
+                }
+
+                Instruction::invokeinterface { cp_index, count }
+            }
+            Opcode::invokespecial => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::invokespecial { cp_index }
+            }
+            Opcode::invokestatic => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::invokestatic { cp_index }
+            }
+            Opcode::invokevirtual => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::invokevirtual { cp_index }
+            }
+            Opcode::ior => Instruction::ior,
+            Opcode::irem => Instruction::irem,
+            Opcode::ireturn => Instruction::ireturn,
+            Opcode::ishl => Instruction::ishl,
+            Opcode::ishr => Instruction::ishr,
+            Opcode::istore => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::istore { index }
+            }
+            Opcode::istore_0 => Instruction::istore_0,
+            Opcode::istore_1 => Instruction::istore_1,
+            Opcode::istore_2 => Instruction::istore_2,
+            Opcode::istore_3 => Instruction::istore_3,
+            Opcode::isub => Instruction::isub,
+            Opcode::iushr => Instruction::iushr,
+            Opcode::ixor => Instruction::ixor,
+            Opcode::jsr => {
+                let offset = self.reader.read_i16_be()?;
+
+                Instruction::jsr { offset }
+            }
+            Opcode::jsr_w => {
+                let offset = self.reader.read_i32_be()?;
+
+                Instruction::jsr_w { offset }
+            }
+            Opcode::l2d => Instruction::l2d,
+            Opcode::l2f => Instruction::l2f,
+            Opcode::l2i => Instruction::l2i,
+            Opcode::ladd => Instruction::ladd,
+            Opcode::laload => Instruction::laload,
+            Opcode::land => Instruction::land,
+            Opcode::lastore => Instruction::lastore,
+            Opcode::lcmp => Instruction::lcmp,
+            Opcode::lconst_0 => Instruction::lconst_0,
+            Opcode::lconst_1 => Instruction::lconst_1,
+            Opcode::ldc => {
+                let cp_index = self.reader.read_u8()?;
+
+                Instruction::ldc { cp_index }
+            }
+            Opcode::ldc_w => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::ldc_w { cp_index }
+            }
+            Opcode::ldc2_w => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::ldc2_w { cp_index }
+            }
+            Opcode::ldiv => Instruction::ldiv,
+            Opcode::lload => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::lload { index }
+            }
+            Opcode::lload_0 => Instruction::lload_0,
+            Opcode::lload_1 => Instruction::lload_1,
+            Opcode::lload_2 => Instruction::lload_2,
+            Opcode::lload_3 => Instruction::lload_3,
+            Opcode::lmul => Instruction::lmul,
+            Opcode::lneg => Instruction::lneg,
+            Opcode::lor => Instruction::lor,
+            Opcode::lrem => Instruction::lrem,
+            Opcode::lreturn => Instruction::lreturn,
+            Opcode::lshl => Instruction::lshl,
+            Opcode::lshr => Instruction::lshr,
+            Opcode::lstore => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::lstore { index }
+            }
+            Opcode::lstore_0 => Instruction::lstore_0,
+            Opcode::lstore_1 => Instruction::lstore_1,
+            Opcode::lstore_2 => Instruction::lstore_2,
+            Opcode::lstore_3 => Instruction::lstore_3,
+            Opcode::lsub => Instruction::lsub,
+            Opcode::lushr => Instruction::lushr,
+            Opcode::lxor => Instruction::lxor,
+            Opcode::monitorenter => Instruction::monitorenter,
+            Opcode::monitorexit => Instruction::monitorexit,
+            Opcode::multianewarray => {
+                let cp_index = self.reader.read_u16_be()?;
+                let dimensions = self.reader.read_u8()?;
+
+                Instruction::multianewarray {
+                    cp_index,
+                    dimensions
+                }
+            }
+            Opcode::new => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::new { cp_index }
+            }
+            Opcode::newarray => {
+                let atype = self.reader.read_u8()?;
+
+                Instruction::newarray { atype }
+            }
+            Opcode::nop => Instruction::nop,
+            Opcode::pop => Instruction::pop,
+            Opcode::pop2 => Instruction::pop2,
+            Opcode::putfield => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::putfield { cp_index }
+            }
+            Opcode::putstatic => {
+                let cp_index = self.reader.read_u16_be()?;
+
+                Instruction::putstatic { cp_index }
+            }
+            Opcode::ret => {
+                let index = self.reader.read_u8()?;
+
+                Instruction::ret { index }
+            }
+            Opcode::r#eturn => Instruction::r#eturn,
+            Opcode::saload => Instruction::saload,
+            Opcode::sastore => Instruction::sastore,
+            Opcode::sipush => {
+                let value = self.reader.read_i16_be()?;
+
+                Instruction::sipush { value }
+            }
+            Opcode::swap => Instruction::swap,
+            Opcode::tableswitch => {
+                self.reader
+                    .skip(Self::switch_padding(self.reader.offset() - start))?;
+                let default = self.reader.read_i32_be()?;
+                let low = self.reader.read_i32_be()?;
+                let high = self.reader.read_i32_be()?;
+
+                if low > high {
+                    return Err(self
+                        .reader
+                        .error(ErrorKind::Malformed("tableswitch low exceeds high")));
+                }
+
+                let count = (i64::from(high) - i64::from(low) + 1) as usize;
+                self.reader.ensure(count.saturating_mul(4))?;
+                let mut jump_offsets = Vec::with_capacity(count);
+
+                for _ in 0..count {
+                    jump_offsets.push(self.reader.read_i32_be()?);
+                }
+
+                Instruction::tableswitch {
+                    default,
+                    low,
+                    high,
+                    jump_offsets
+                }
+            }
+            Opcode::lookupswitch => {
+                self.reader
+                    .skip(Self::switch_padding(self.reader.offset() - start))?;
+                let default = self.reader.read_i32_be()?;
+                let npairs = self.reader.read_i32_be()?;
+
+                if npairs < 0 {
+                    return Err(self
+                        .reader
+                        .error(ErrorKind::Malformed("lookupswitch npairs is negative")));
+                }
+
+                self.reader.ensure((npairs as usize).saturating_mul(8))?;
+                let mut match_offset_pairs = Vec::with_capacity(npairs as usize);
+
+                for _ in 0..npairs {
+                    let r#match = self.reader.read_i32_be()?;
+                    let jump_offset = self.reader.read_i32_be()?;
+
+                    match_offset_pairs.push((r#match, jump_offset));
+                }
+
+                Instruction::lookupswitch {
+                    default,
+                    npairs,
+                    match_offset_pairs
+                }
+            }
+            Opcode::wide => {
+                let opcode = self.read_opcode()?;
+                let index = self.reader.read_u16_be()?;
+
+                match opcode {
+                    Opcode::iinc => {
+                        let r#const = self.reader.read_i16_be()?;
+
+                        Instruction::wide_iinc { index, r#const }
+                    }
+                    Opcode::iload
+                    | Opcode::fload
+                    | Opcode::aload
+                    | Opcode::lload
+                    | Opcode::dload
+                    | Opcode::istore
+                    | Opcode::fstore
+                    | Opcode::astore
+                    | Opcode::lstore
+                    | Opcode::dstore
+                    | Opcode::ret => Instruction::wide { opcode, index },
+                    _ => {
+                        return Err(self
+                            .reader
+                            .error(ErrorKind::Malformed("invalid opcode following wide")))
+                    }
+                }
+            }
+        };
+
+        Ok(ins)
+    }
+
+    fn read_opcode(&mut self) -> Result<Opcode, ErrorType> {
+        let offset = self.reader.offset();
+        let opcode = self.reader.read_u8()?;
+
+        Opcode::try_from(opcode).map_err(|e| e.or_offset(offset))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ClassParser;
+    use crate::access_flags::{ClassAccessFlag, FieldAccessFlag, RequiresFlag};
+    use crate::annotation::{ResolvedElementValue, TargetInfo};
+    use crate::attribute::Attribute;
+    use crate::constant_pool::{Constant, ConstantPool, ConstantPoolBuilder, ConstantTag};
+    use crate::error::ErrorKind;
+    use crate::opcode::{Instruction, Opcode};
+    use crate::smap::Smap;
+    use crate::stack_map::{expand_frames, StackMapFrame, VerificationTypeInfo};
+    use crate::test_util::{expect_pat, read_class_file};
+    use crate::view::{self, AttributeKind};
+    use crate::{CodeBuilder, Field};
+    use std::str;
+
+    #[test]
+    fn parse_simple() {
+        let buf = read_class_file("./tests/Hello.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        assert_eq!(class.magic, 0xCAFEBABE);
+        assert_eq!(class.methods_count, 2);
+        assert_eq!(class.field_count, 0);
+
+        let constant_pool = class.constant_pool;
+
+        expect_pat!(Constant::Class {tag: _, name_index}, constant_pool.get(class.this_class).unwrap(), {
+            expect_pat!(Constant::Utf8 {tag: _, length: _, bytes}, constant_pool.get(*name_index).unwrap(), {
+                assert_eq!(str::from_utf8(bytes).unwrap(), "Hello");
             });
         });
 
-        expect_pat!(Constant::Class {tag: _, name_index}, &constant_pool[(class.super_class - 1) as usize], {
-            expect_pat!(Constant::Utf8 {tag: _, length: _, bytes}, &constant_pool[(name_index - 1) as usize], {
+        expect_pat!(Constant::Class {tag: _, name_index}, constant_pool.get(class.super_class).unwrap(), {
+            expect_pat!(Constant::Utf8 {tag: _, length: _, bytes}, constant_pool.get(*name_index).unwrap(), {
                 assert_eq!(str::from_utf8(bytes).unwrap(), "java/lang/Object");
             });
         });
@@ -697,7 +2039,7 @@ mod test {
         let fields = class.fields;
         let f0 = &fields[0];
 
-        let constant = &constant_pool[(f0.name_index - 1) as usize];
+        let constant = constant_pool.get(f0.name_index).unwrap();
 
         expect_pat!(Constant::Utf8{tag: _, length: _, bytes}, constant, {
             assert_eq!(str::from_utf8(bytes).unwrap(), "test");
@@ -705,10 +2047,726 @@ mod test {
             let attribute = &f0.attributes[0];
 
             expect_pat!(Attribute::ConstantValue{attribute_name_index: _, attribute_length: _, constantvalue_index}, attribute, {
-                expect_pat!(Constant::Integer{tag: _, value}, &constant_pool[(constantvalue_index - 1) as usize], {
+                expect_pat!(Constant::Integer{tag: _, value}, constant_pool.get(*constantvalue_index).unwrap(), {
                     assert_eq!(*value, 2147483647);
                 });
             });
         });
     }
+
+    #[test]
+    fn parse_wide_constants() {
+        let buf = read_class_file("./tests/Constants.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        assert_eq!(class.field_count, 4);
+        assert_eq!(class.constant_pool.len(), (class.constant_pool_count - 1) as usize);
+        let constant_pool = class.constant_pool;
+        let fields = class.fields;
+
+        expect_pat!(Attribute::ConstantValue{attribute_name_index: _, attribute_length: _, constantvalue_index}, &fields[0].attributes[0], {
+            expect_pat!(Constant::Long{tag: _, high_bytes, low_bytes}, constant_pool.get(*constantvalue_index).unwrap(), {
+                assert_eq!(((*high_bytes as u64) << 32) | *low_bytes as u64, 0x1234567890ABCDEF);
+            });
+            let e = constant_pool.get(*constantvalue_index + 1).unwrap_err();
+            expect_pat!(ErrorKind::InvalidConstantPoolIndex(_), e.kind(), {});
+        });
+
+        expect_pat!(Attribute::ConstantValue{attribute_name_index: _, attribute_length: _, constantvalue_index}, &fields[1].attributes[0], {
+            expect_pat!(Constant::Double{tag: _, high_bytes, low_bytes}, constant_pool.get(*constantvalue_index).unwrap(), {
+                assert_eq!(f64::from_bits(((*high_bytes as u64) << 32) | *low_bytes as u64), 2.5);
+            });
+        });
+
+        expect_pat!(Attribute::ConstantValue{attribute_name_index: _, attribute_length: _, constantvalue_index}, &fields[2].attributes[0], {
+            expect_pat!(Constant::Float{tag: _, bytes}, constant_pool.get(*constantvalue_index).unwrap(), {
+                assert_eq!(f32::from_be_bytes(*bytes), 1.5);
+            });
+        });
+
+        expect_pat!(Attribute::ConstantValue{attribute_name_index: _, attribute_length: _, constantvalue_index}, &fields[3].attributes[0], {
+            expect_pat!(Constant::String{tag: _, string_index}, constant_pool.get(*constantvalue_index).unwrap(), {
+                expect_pat!(Constant::Utf8{tag: _, length: _, bytes}, constant_pool.get(*string_index).unwrap(), {
+                    assert_eq!(str::from_utf8(bytes).unwrap(), "after wide constants");
+                });
+            });
+        });
+
+        // A Long in the last slot would push the pool past constant_pool_count - 1
+        let buf = [0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 61, 0, 2, 5, 0, 0, 0, 0, 0, 0, 0, 1];
+        let err = ClassParser::from_bytes(&buf).parse().unwrap_err();
+        expect_pat!(ErrorKind::Malformed(_), err.kind(), {});
+        assert_eq!(err.offset(), Some(10));
+        assert_eq!(err.context(), ["constant pool entry #1"]);
+    }
+
+    #[test]
+    fn parse_instructions() {
+        let code = [
+            0x1b, 0xaa, 0x00, 0x00, 0x00, 0x00, 0x00, 0x24, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+            0x00, 0x03, 0x00, 0x00, 0x00, 0x1b, 0x00, 0x00, 0x00, 0x1e, 0x00, 0x00, 0x00, 0x21,
+            0x10, 0x0a, 0xab, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x01, 0xff, 0xff,
+            0xfc, 0x18, 0x00, 0x00, 0x00, 0x03, 0xc4, 0x84, 0x00, 0x05, 0x03, 0xe8, 0xc4, 0x15,
+            0x01, 0x00, 0xac
+        ];
+        let mut parser = ClassParser::from_bytes(&code);
+        let instructions = parser.read_instructions(code.len() as u32).unwrap();
+
+        assert_eq!(parser.reader.offset(), code.len());
+        assert_eq!(instructions.len(), 7);
+
+        expect_pat!(Instruction::tableswitch{default, low, high, jump_offsets}, &instructions[1], {
+            assert_eq!((*default, *low, *high), (36, 1, 3));
+            assert_eq!(jump_offsets, &vec![27, 30, 33]);
+        });
+        assert_eq!(instructions[1].switch_targets(1), Some(vec![37, 28, 31, 34]));
+        assert_eq!(instructions[3].switch_targets(30), Some(vec![35, 33]));
+        assert_eq!(instructions[2].switch_targets(28), None);
+
+        let pcs: Vec<u32> = instructions.iter().map(|(pc, _)| pc).collect();
+        assert_eq!(pcs, vec![0, 1, 28, 30, 48, 54, 58]);
+        assert_eq!(instructions.index_of(48), Some(4));
+        assert_eq!(instructions.index_of(49), None);
+        assert_eq!(instructions.pc(6), Some(58));
+        expect_pat!(Instruction::ireturn, instructions.at(58).unwrap(), {});
+        expect_pat!(Instruction::bipush{value}, &instructions[2], {
+            assert_eq!(*value, 10);
+        });
+        expect_pat!(Instruction::lookupswitch{default, npairs, match_offset_pairs}, &instructions[3], {
+            assert_eq!((*default, *npairs), (5, 1));
+            assert_eq!(match_offset_pairs, &vec![(-1000, 3)]);
+        });
+        expect_pat!(Instruction::wide_iinc{index, r#const}, &instructions[4], {
+            assert_eq!(*index, 5);
+            assert_eq!(*r#const, 1000);
+        });
+        expect_pat!(Instruction::wide{opcode: Opcode::iload, index}, &instructions[5], {
+            assert_eq!(*index, 256);
+        });
+        expect_pat!(Instruction::ireturn, &instructions[6], {});
+    }
+
+    #[test]
+    fn parse_interfaces() {
+        let buf = read_class_file("./tests/Interfaces.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        assert_eq!(class.interface_count, 2);
+        assert_eq!(class.interfaces.len(), 2);
+        assert_eq!(class.interface_names().unwrap(), vec!["java/lang/Runnable", "java/lang/Comparable"]);
+        assert_eq!(class.field_count, 1);
+        assert_eq!(class.methods_count, 3);
+    }
+
+    #[test]
+    fn parse_truncated() {
+        let buf = read_class_file("./tests/Hello.class").unwrap();
+
+        for len in 0..buf.len() {
+            let err = ClassParser::from_bytes(&buf[..len]).parse().unwrap_err();
+
+            match err.kind() {
+                ErrorKind::UnexpectedEof => assert!(err.offset().unwrap() <= len),
+                kind => panic!("Expected UnexpectedEof, found {:?}", kind)
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let mut buf = read_class_file("./tests/Hello.class").unwrap();
+        let err = ClassParser::from_bytes(&buf[4..]).parse().unwrap_err();
+        expect_pat!(ErrorKind::InvalidMagic(_), err.kind(), {});
+
+        // Replace the opcode of the first instruction of `main` with a reserved one
+        let main = buf.windows(4).rposition(|w| w == [0xb2, 0x00, 0x07, 0x12]).unwrap();
+        buf[main] = 0xfe;
+
+        let err = ClassParser::from_bytes(&buf).parse().unwrap_err();
+        expect_pat!(ErrorKind::InvalidOpcode(0xfe), err.kind(), {});
+        assert_eq!(err.offset(), Some(main));
+        assert_eq!(err.context(), ["method #1", "attribute Code", "instruction at pc 0"]);
+        assert_eq!(err.to_string(), format!("invalid opcode 0xfe at offset {:#x} while parsing method #1 attribute Code instruction at pc 0", main));
+    }
+
+    #[test]
+    fn parse_unknown_attribute() {
+        let mut buf = read_class_file("./tests/Hello.class").unwrap();
+        let name = buf.windows(10).position(|w| w == b"SourceFile").unwrap();
+        buf[name..name + 10].copy_from_slice(b"VendorAttr");
+
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        assert_eq!(class.attributes_count, 1);
+
+        expect_pat!(Attribute::Unknown{attribute_name_index, info}, &class.attributes[0], {
+            expect_pat!(Constant::Utf8{tag: _, length: _, bytes}, class.constant_pool.get(*attribute_name_index).unwrap(), {
+                assert_eq!(bytes.as_ref(), b"VendorAttr");
+            });
+            assert_eq!(info.len(), 2);
+        });
+    }
+
+    #[test]
+    fn parse_stack_map_table() {
+        let buf = read_class_file("./tests/StackMap.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let methods = class.methods;
+
+        expect_pat!(Attribute::Code{attributes, ..}, &methods[1].attributes[0], {
+            expect_pat!(Attribute::StackMapTable{attribute_name_index: _, attribute_length: _, number_of_entries, entries}, &attributes[1], {
+                assert_eq!(*number_of_entries, 3);
+                expect_pat!(StackMapFrame::Append{frame_type: 253, offset_delta: 5, locals}, &entries[0], {
+                    assert_eq!(locals, &vec![VerificationTypeInfo::Long, VerificationTypeInfo::Integer]);
+                });
+                expect_pat!(StackMapFrame::Chop{frame_type: 250, offset_delta: 17}, &entries[1], {});
+                expect_pat!(StackMapFrame::Same{frame_type: 5}, &entries[2], {});
+
+                let string = VerificationTypeInfo::Object { cpool_index: 7 };
+                let frames = expand_frames(&[VerificationTypeInfo::Integer, string], entries).unwrap();
+                let offsets: Vec<_> = frames.iter().map(|f| f.offset).collect();
+
+                assert_eq!(offsets, vec![5, 23, 29]);
+                assert_eq!(frames[0].locals, vec![VerificationTypeInfo::Integer, string, VerificationTypeInfo::Long, VerificationTypeInfo::Integer]);
+                assert_eq!(frames[1].locals, vec![VerificationTypeInfo::Integer, string, VerificationTypeInfo::Long]);
+                assert_eq!(frames[2].locals, frames[1].locals);
+                assert!(frames.iter().all(|f| f.stack.is_empty()));
+            });
+        });
+
+        expect_pat!(Attribute::Code{attributes, ..}, &methods[2].attributes[0], {
+            expect_pat!(Attribute::StackMapTable{entries, ..}, &attributes[1], {
+                expect_pat!(StackMapFrame::SameLocals1StackItem{frame_type: 67, stack: VerificationTypeInfo::Object{cpool_index}}, &entries[1], {
+                    expect_pat!(Constant::Class{tag: _, name_index}, class.constant_pool.get(*cpool_index).unwrap(), {
+                        expect_pat!(Constant::Utf8{tag: _, length: _, bytes}, class.constant_pool.get(*name_index).unwrap(), {
+                            assert_eq!(str::from_utf8(bytes).unwrap(), "java/lang/Object");
+                        });
+                    });
+                });
+            });
+        });
+    }
+
+    #[test]
+    fn parse_annotations() {
+        let buf = read_class_file("./tests/Annotations.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let constant_pool = &class.constant_pool;
+        let annotations = class.attributes.iter().find_map(|a| match a {
+            Attribute::RuntimeVisibleAnnotations { annotations, .. } => Some(annotations),
+            _ => None
+        }).unwrap();
+        let path = annotations[0].resolve(constant_pool).unwrap();
+
+        assert_eq!(path.type_name, "LAnnotations$Path;");
+        assert_eq!(path.elements, vec![
+            ("value".into(), ResolvedElementValue::String("/items".into())),
+            ("priority".into(), ResolvedElementValue::Int(2)),
+            ("targets".into(), ResolvedElementValue::Array(vec![
+                ResolvedElementValue::Enum { type_name: "Ljava/lang/annotation/ElementType;".into(), const_name: "TYPE".into() },
+                ResolvedElementValue::Enum { type_name: "Ljava/lang/annotation/ElementType;".into(), const_name: "METHOD".into() }
+            ])),
+            ("type".into(), ResolvedElementValue::Class("Ljava/lang/String;".into()))
+        ]);
+
+        expect_pat!(Attribute::RuntimeInvisibleAnnotations{attribute_name_index: _, attribute_length: _, num_annotations: 1, annotations}, class.attributes.iter().find(|a| matches!(a, Attribute::RuntimeInvisibleAnnotations { .. })).unwrap(), {
+            assert_eq!(annotations[0].resolve(constant_pool).unwrap().type_name, "LAnnotations$Marker;");
+        });
+
+        expect_pat!(Attribute::RuntimeVisibleAnnotations{annotations, ..}, &class.fields[0].attributes[0], {
+            assert_eq!(annotations[0].resolve(constant_pool).unwrap().type_name, "LAnnotations$Inject;");
+        });
+
+        let method = &class.methods[1];
+        expect_pat!(Attribute::RuntimeVisibleParameterAnnotations{attribute_name_index: _, attribute_length: _, num_parameters: 2, parameter_annotations}, &method.attributes[2], {
+            assert_eq!(parameter_annotations[0].num_annotations, 0);
+            assert_eq!(parameter_annotations[1].annotations[0].resolve(constant_pool).unwrap().type_name, "LAnnotations$Inject;");
+        });
+        expect_pat!(Attribute::RuntimeInvisibleParameterAnnotations{attribute_name_index: _, attribute_length: _, num_parameters: 2, parameter_annotations}, &method.attributes[3], {
+            assert_eq!(parameter_annotations[0].annotations[0].resolve(constant_pool).unwrap().type_name, "LAnnotations$Marker;");
+            assert_eq!(parameter_annotations[1].num_annotations, 0);
+        });
+    }
+
+    #[test]
+    fn parse_annotation_default() {
+        let buf = read_class_file("./tests/Annotations$Path.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let defaults: Vec<_> = class.methods.iter().map(|m| {
+            m.attributes.iter().find_map(|a| match a {
+                Attribute::AnnotationDefault { default_value, .. } => Some(default_value.resolve(&class.constant_pool).unwrap()),
+                _ => None
+            }).unwrap()
+        }).collect();
+
+        assert_eq!(defaults, vec![
+            ResolvedElementValue::String("/".into()),
+            ResolvedElementValue::Int(5),
+            ResolvedElementValue::Array(vec![]),
+            ResolvedElementValue::Class("Ljava/lang/Object;".into())
+        ]);
+    }
+
+    #[test]
+    fn parse_type_annotations() {
+        let buf = read_class_file("./tests/TypeAnnotations.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let constant_pool = &class.constant_pool;
+
+        expect_pat!(Attribute::RuntimeVisibleTypeAnnotations{attribute_name_index: _, attribute_length: _, num_annotations: 1, annotations}, &class.fields[0].attributes[0], {
+            expect_pat!(TargetInfo::Empty, &annotations[0].target_info, {});
+            assert_eq!(annotations[0].target_type, 0x13);
+            assert_eq!(annotations[0].resolve(constant_pool).unwrap().type_name, "LTypeAnnotations$Nullable;");
+        });
+
+        let method = &class.methods[1];
+        let annotations = method.attributes.iter().find_map(|a| match a {
+            Attribute::RuntimeVisibleTypeAnnotations { annotations, .. } => Some(annotations),
+            _ => None
+        }).unwrap();
+
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].target_type, 0x14);
+        expect_pat!(TargetInfo::FormalParameter{formal_parameter_index: 0}, &annotations[1].target_info, {
+            assert_eq!(annotations[1].target_path.path_length, 1);
+            assert_eq!(annotations[1].target_path.path[0].type_path_kind, 3);
+            assert_eq!(annotations[1].target_path.path[0].type_argument_index, 0);
+        });
+
+        expect_pat!(Attribute::Code{attributes, ..}, &method.attributes[0], {
+            let annotations = attributes.iter().find_map(|a| match a {
+                Attribute::RuntimeVisibleTypeAnnotations { annotations, .. } => Some(annotations),
+                _ => None
+            }).unwrap();
+
+            expect_pat!(TargetInfo::Localvar{table_length: 1, table}, &annotations[0].target_info, {
+                assert_eq!(table[0].index, 2);
+            });
+        });
+    }
+
+    #[test]
+    fn parse_module() {
+        let buf = read_class_file("./tests/module/module-info.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let constant_pool = &class.constant_pool;
+        assert!(class.access_flags.contains(ClassAccessFlag::MODULE));
+
+        let module = class.attributes.iter().find_map(|a| match a {
+            Attribute::Module { module, .. } => Some(module),
+            _ => None
+        }).unwrap();
+
+        assert_eq!(module.name(constant_pool).unwrap(), "com.example.app");
+        assert_eq!(module.required_modules(constant_pool).unwrap(), vec!["java.base", "java.logging", "java.sql"]);
+        assert_eq!(module.requires[0].requires_flags, RequiresFlag::MANDATED);
+        assert_eq!(module.requires[1].requires_flags, RequiresFlag::TRANSITIVE);
+        assert_eq!(module.requires[2].requires_flags, RequiresFlag::STATIC_PHASE);
+        assert_eq!(module.exported_packages(constant_pool).unwrap(), vec!["com/example/app"]);
+
+        assert_eq!(module.opens_count, 1);
+        assert_eq!(module.opens[0].opens_to_index, vec![module.requires[1].requires_index]);
+        assert_eq!(module.uses_count, 1);
+        assert_eq!(module.provides[0].provides_with_count, 1);
+    }
+
+    #[test]
+    fn parse_module_packages() {
+        let bytes = [
+            0x00, 0x00, 0x00, 0x06, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, 0x00, 0x00, 0x00, 0x02,
+            0x00, 0x05
+        ];
+        let mut parser = ClassParser::from_bytes(&bytes);
+
+        let attribute = parser.read_attribute(1, "ModulePackages", &ConstantPool::default()).unwrap();
+        expect_pat!(Attribute::ModulePackages{attribute_name_index: 1, attribute_length: 6, package_count: 2, package_index}, &attribute, {
+            assert_eq!(package_index, &vec![3, 4]);
+        });
+        assert_eq!(view::ModulePackages::view(&attribute), Some(&[3, 4][..]));
+
+        let attribute = parser.read_attribute(2, "ModuleMainClass", &ConstantPool::default()).unwrap();
+        expect_pat!(Attribute::ModuleMainClass{attribute_name_index: 2, attribute_length: 2, main_class_index: 5}, &attribute, {});
+        assert_eq!(view::ModuleMainClass::view(&attribute), Some(5));
+        assert_eq!(parser.reader.remaining(), 0);
+    }
+
+    #[test]
+    fn parse_records() {
+        let buf = read_class_file("./tests/Shapes.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let constant_pool = &class.constant_pool;
+        let permitted = class.attributes.iter().find_map(|a| match a {
+            Attribute::PermittedSubclasses { classes, .. } => Some(classes),
+            _ => None
+        }).unwrap();
+
+        let names: Vec<_> = permitted.iter().map(|i| constant_pool.class_name(*i).unwrap()).collect();
+        assert_eq!(names, vec!["Shapes$Circle", "Shapes$Polygon"]);
+
+        let buf = read_class_file("./tests/Shapes$Polygon.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let constant_pool = &class.constant_pool;
+        let host = class.attributes.iter().find_map(|a| match a {
+            Attribute::NestHost { host_class_index, .. } => Some(*host_class_index),
+            _ => None
+        }).unwrap();
+        assert_eq!(constant_pool.class_name(host).unwrap(), "Shapes");
+
+        let components = class.attributes.iter().find_map(|a| match a {
+            Attribute::Record { components, .. } => Some(components),
+            _ => None
+        }).unwrap();
+
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].name(constant_pool).unwrap(), "vertices");
+        assert_eq!(components[0].descriptor(constant_pool).unwrap(), "Ljava/util/List;");
+        assert_eq!(components[0].attributes_count, 1);
+        assert_eq!(components[1].name(constant_pool).unwrap(), "sides");
+        assert_eq!(components[1].descriptor(constant_pool).unwrap(), "I");
+
+        expect_pat!(Attribute::RuntimeVisibleAnnotations{attribute_name_index: _, attribute_length: _, num_annotations: 1, annotations}, &components[1].attributes[0], {
+            assert_eq!(annotations[0].resolve(constant_pool).unwrap().type_name, "LShapes$Positive;");
+        });
+    }
+
+    #[test]
+    fn parse_signatures_and_exceptions() {
+        let buf = read_class_file("./tests/Outline.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let constant_pool = &class.constant_pool;
+        let signature = class.attributes.iter().find_map(|a| match a {
+            Attribute::Signature { signature_index, .. } => Some(*signature_index),
+            _ => None
+        }).unwrap();
+        assert_eq!(constant_pool.utf8(signature).unwrap(), "<T::Ljava/lang/Comparable<TT;>;>Ljava/lang/Object;");
+
+        let field = &class.fields[0];
+        expect_pat!(Attribute::Deprecated{attribute_name_index: _, attribute_length: 0}, &field.attributes[0], {});
+        expect_pat!(Attribute::Signature{attribute_name_index: _, attribute_length: 2, signature_index}, &field.attributes[1], {
+            assert_eq!(constant_pool.utf8(*signature_index).unwrap(), "Ljava/util/List<TT;>;");
+        });
+
+        let exceptions = class.methods[1].attributes.iter().find_map(|a| match a {
+            Attribute::Exceptions { exception_index_table, .. } => Some(exception_index_table),
+            _ => None
+        }).unwrap();
+
+        let names: Vec<_> = exceptions.iter().map(|i| constant_pool.class_name(*i).unwrap()).collect();
+        assert_eq!(names, vec!["java/io/IOException", "java/lang/InterruptedException"]);
+
+        let buf = read_class_file("./tests/Outline$1.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let enclosing = class.attributes.iter().find_map(|a| match a {
+            Attribute::EnclosingMethod { class_index, method_index, .. } => Some((*class_index, *method_index)),
+            _ => None
+        }).unwrap();
+        assert_eq!(class.constant_pool.class_name(enclosing.0).unwrap(), "Outline");
+        assert_ne!(enclosing.1, 0);
+    }
+
+    #[test]
+    fn parse_synthetic() {
+        let bytes = [0x00, 0x00, 0x00, 0x00];
+        let mut parser = ClassParser::from_bytes(&bytes);
+
+        let attribute = parser.read_attribute(1, "Synthetic", &ConstantPool::default()).unwrap();
+        expect_pat!(Attribute::Synthetic{attribute_name_index: 1, attribute_length: 0}, &attribute, {});
+        assert_eq!(parser.reader.remaining(), 0);
+    }
+
+    #[test]
+    fn parse_local_variables() {
+        let buf = read_class_file("./tests/Locals.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let constant_pool = &class.constant_pool;
+        let code = &class.methods[1].attributes[0];
+
+        let name = |index, pc| code.local_variable_at(index, pc, constant_pool).map(|v| v.name(constant_pool).unwrap());
+        assert_eq!(name(0, 0), Some("word".into()));
+        assert_eq!(name(1, 48), Some("times".into()));
+        assert_eq!(name(2, 7), None);
+        assert_eq!(name(2, 8), Some("words".into()));
+        assert_eq!(name(3, 10), Some("i".into()));
+        assert_eq!(name(3, 29), None);
+        assert_eq!(name(3, 37), Some("total".into()));
+        assert_eq!(name(3, 49), None);
+        // `total` is a long, so it occupies slot 4 as well
+        assert_eq!(name(4, 37), Some("total".into()));
+        assert_eq!(name(4, 49), None);
+        assert_eq!(name(5, 37), None);
+
+        let total = code.local_variable_at(3, 40, constant_pool).unwrap();
+        assert_eq!(total.descriptor(constant_pool).unwrap(), "J");
+
+        let words = code.local_variable_type_at(2, 20, constant_pool).unwrap();
+        assert_eq!(words.name(constant_pool).unwrap(), "words");
+        assert_eq!(words.signature(constant_pool).unwrap(), "Ljava/util/List<Ljava/lang/String;>;");
+        assert!(code.local_variable_type_at(1, 20, constant_pool).is_none());
+        assert!(class.methods[1].attributes[1].local_variable_at(0, 0, constant_pool).is_none());
+    }
+
+    #[test]
+    fn parse_source_debug_extension() {
+        // As emitted by kotlinc for a call of an inline function declared in Util.kt
+        let smap = "SMAP\nMain.kt\nKotlin\n*S Kotlin\n*F\n+ 1 Main.kt\ncom/example/MainKt\n+ 2 Util.kt\ncom/example/UtilKt\n*L\n1#1,10:1\n5#2,3:11\n*E\n*S KotlinDebug\n*F\n+ 1 Main.kt\ncom/example/MainKt\n*L\n4#1:11,3\n*E\n";
+        let mut bytes = (smap.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(smap.as_bytes());
+
+        let mut parser = ClassParser::from_bytes(&bytes);
+        let attribute = parser.read_attribute(1, "SourceDebugExtension", &ConstantPool::default()).unwrap();
+
+        expect_pat!(Attribute::SourceDebugExtension{attribute_name_index: 1, attribute_length: _, debug_extension}, &attribute, {
+            assert_eq!(*debug_extension, smap.as_bytes());
+            assert_eq!(Smap::from_bytes(debug_extension).unwrap().strata.len(), 2);
+        });
+        assert_eq!(parser.reader.remaining(), 0);
+    }
+
+    #[test]
+    fn parse_modified_utf8() {
+        let buf = read_class_file("./tests/Strings.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let constant_pool = &class.constant_pool;
+        let string = |field: &Field| match field.attributes[0] {
+            Attribute::ConstantValue { constantvalue_index, .. } => constant_pool.get(constantvalue_index).unwrap(),
+            _ => panic!("expected a ConstantValue attribute")
+        };
+        let value = |field: &Field| match string(field) {
+            Constant::String { string_index, .. } => constant_pool.get(*string_index).unwrap(),
+            _ => panic!("expected a String constant")
+        };
+
+        assert_eq!(value(&class.fields[0]).as_str().unwrap(), "a\0b");
+        assert_eq!(value(&class.fields[1]).as_str().unwrap(), "smile \u{1f600}");
+        assert_eq!(value(&class.fields[2]).as_str().unwrap(), "\u{fffd}!");
+        assert!(string(&class.fields[0]).as_str().is_err());
+    }
+
+    #[test]
+    fn resolve_operands() {
+        let mut constant_pool = ConstantPoolBuilder::new();
+        let out = constant_pool.field_ref("java/lang/System", "out", "Ljava/io/PrintStream;").unwrap();
+        let hello = constant_pool.string("Hello").unwrap();
+        let println = constant_pool.method_ref("java/io/PrintStream", "println", "(Ljava/lang/String;)V").unwrap();
+        let value_of = constant_pool.method_ref("java/lang/Integer", "valueOf", "(I)Ljava/lang/Integer;").unwrap();
+
+        let mut code = CodeBuilder::new();
+        let head = code.new_label();
+        let exit = code.new_label();
+        code.push(Instruction::getstatic { cp_index: out });
+        code.push(Instruction::ldc { cp_index: hello as u8 });
+        code.push(Instruction::invokevirtual { cp_index: println });
+        code.push(Instruction::iconst_0);
+        code.place(head);
+        code.push(Instruction::iload_0);
+        code.branch(Opcode::ifle, exit);
+        code.push(Instruction::iinc { index: 0, r#const: -1 });
+        code.branch(Opcode::goto, head);
+        code.place(exit);
+        code.push(Instruction::invokestatic { cp_index: value_of });
+        code.push(Instruction::areturn);
+
+        let attribute = code.build(&mut constant_pool, 2, 1).unwrap();
+        let constant_pool = constant_pool.build();
+
+        expect_pat!(Attribute::Code{code, ..}, &attribute, {
+            let field = code[0].member_ref(&constant_pool).unwrap().unwrap();
+            assert_eq!((field.owner, field.name, field.descriptor), ("java/lang/System".into(), "out".into(), "Ljava/io/PrintStream;".into()));
+
+            let method = code[2].member_ref(&constant_pool).unwrap().unwrap();
+            assert_eq!((method.owner, method.name, method.descriptor), ("java/io/PrintStream".into(), "println".into(), "(Ljava/lang/String;)V".into()));
+
+            expect_pat!(Instruction::ldc{cp_index}, &code[1], {
+                assert_eq!(code[1].cp_index(), Some(*cp_index as u16));
+            });
+            assert!(code[1].member_ref(&constant_pool).unwrap().is_none());
+            assert!(code[3].member_ref(&constant_pool).unwrap().is_none());
+
+            // the forward branch out of the loop and the backward jump to its head
+            let (head, exit) = (code.pc(4).unwrap(), code.pc(8).unwrap());
+            expect_pat!(Instruction::ifle{offset}, &code[5], {
+                assert_eq!(*offset as u32, exit - code.pc(5).unwrap());
+            });
+            assert_eq!(code[5].branch_target(code.pc(5).unwrap()), Some(exit));
+            assert_eq!(code.index_of(exit), Some(8));
+            assert_eq!(code[7].branch_offset(), Some(head as i32 - code.pc(7).unwrap() as i32));
+            assert_eq!(code[7].branch_target(code.pc(7).unwrap()), Some(head));
+            assert_eq!(code[7].branch_target(0), None);
+            assert_eq!(code[6].branch_target(code.pc(6).unwrap()), None);
+
+            let method = code[8].member_ref(&constant_pool).unwrap().unwrap();
+            assert_eq!(method.name, "valueOf");
+
+            let e = Instruction::getstatic { cp_index: code[8].cp_index().unwrap() }.member_ref(&constant_pool).unwrap_err();
+            expect_pat!(ErrorKind::UnexpectedConstant{index: _, expected: ConstantTag::Fieldref}, e.kind(), {});
+        });
+    }
+
+    #[test]
+    fn resolve_constants() {
+        let buf = read_class_file("./tests/Hello.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let constant_pool = &class.constant_pool;
+        let object = class.super_class;
+        assert_eq!(constant_pool.class_name(object).unwrap(), "java/lang/Object");
+
+        let (init, name_and_type) = constant_pool
+            .iter()
+            .find_map(|(index, constant)| match constant {
+                Constant::Methodref { name_and_type_index, .. } if constant_pool.member_ref(index).unwrap().name == "<init>" => {
+                    Some((index, *name_and_type_index))
+                }
+                _ => None
+            })
+            .unwrap();
+        assert_eq!(constant_pool.name_and_type(name_and_type).unwrap(), ("<init>".into(), "()V".into()));
+        let method = constant_pool.member_ref(init).unwrap();
+        assert_eq!((method.owner, method.name, method.descriptor), ("java/lang/Object".into(), "<init>".into(), "()V".into()));
+
+        let out = constant_pool
+            .iter()
+            .find(|(index, constant)| matches!(constant, Constant::Fieldref { .. }) && constant_pool.member_ref(*index).unwrap().name == "out")
+            .map(|(index, _)| index)
+            .unwrap();
+        let field = constant_pool.member_ref(out).unwrap();
+        assert_eq!((field.owner, field.name), ("java/lang/System".into(), "out".into()));
+
+        let e = constant_pool.utf8(object).unwrap_err();
+        expect_pat!(ErrorKind::UnexpectedConstant{index, expected: ConstantTag::Utf8}, e.kind(), {
+            assert_eq!(*index, object);
+        });
+        let e = constant_pool.member_ref(name_and_type).unwrap_err();
+        expect_pat!(ErrorKind::UnexpectedConstant{index, expected: ConstantTag::Methodref}, e.kind(), {
+            assert_eq!(*index, name_and_type);
+        });
+        for index in [0, class.constant_pool_count] {
+            let e = constant_pool.get(index).unwrap_err();
+            expect_pat!(ErrorKind::InvalidConstantPoolIndex(i), e.kind(), {
+                assert_eq!(*i, index);
+            });
+        }
+
+        let indices: Vec<u16> = constant_pool.iter().map(|(index, _)| index).collect();
+        assert_eq!(indices, (1..class.constant_pool_count).collect::<Vec<u16>>());
+
+        let buf = read_class_file("./tests/Constants.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let wide = class.constant_pool.iter().filter(|(_, c)| matches!(c, Constant::Long { .. } | Constant::Double { .. })).count();
+        assert_eq!(class.constant_pool.iter().count() + wide, class.constant_pool.len());
+    }
+
+    #[test]
+    fn class_view() {
+        let buf = read_class_file("./tests/Outline.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let view = class.view();
+        assert_eq!(view.name().unwrap(), "Outline");
+        assert_eq!(view.super_name().unwrap().unwrap(), "java/lang/Object");
+        assert!(view.interfaces().unwrap().is_empty());
+        assert_eq!(view.constant_pool().utf8(view.attribute::<view::SourceFile>().unwrap()).unwrap(), "Outline.java");
+
+        let field = view.fields().next().unwrap();
+        assert_eq!((field.name().unwrap(), field.descriptor().unwrap()), ("items".into(), "Ljava/util/List;".into()));
+        assert!(field.access().contains(FieldAccessFlag::PUBLIC));
+        assert!(field.attribute::<view::Deprecated>().is_some());
+        assert!(field.attribute::<view::Synthetic>().is_none());
+
+        let methods: Vec<_> = view.methods().collect();
+        let names: Vec<_> = methods.iter().map(|m| m.name().unwrap()).collect();
+        assert_eq!(names, vec!["<init>", "load", "task"]);
+
+        let load = methods[1];
+        assert_eq!(load.descriptor().unwrap(), "()V");
+        assert_eq!(load.attribute::<view::Exceptions>().unwrap().len(), 2);
+
+        let code = load.code().unwrap();
+        assert_eq!((code.max_stack, code.max_locals), (0, 1));
+        expect_pat!(Instruction::r#eturn, &code.bytecode[0], {});
+        assert_eq!(code.attribute::<view::LineNumberTable>().unwrap().len(), 1);
+
+        let buf = read_class_file("./tests/module/module-info.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let view = class.view();
+        assert!(view.super_name().unwrap().is_none());
+        assert_eq!(view.attribute::<view::Module>().unwrap().name(view.constant_pool()).unwrap(), "com.example.app");
+
+        let buf = read_class_file("./tests/Annotations.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let method = class.view().methods().nth(1).unwrap();
+        assert_eq!(method.attribute::<view::RuntimeVisibleParameterAnnotations>().unwrap().len(), 2);
+        assert_eq!(method.attribute::<view::RuntimeInvisibleParameterAnnotations>().unwrap().len(), 2);
+
+        let buf = read_class_file("./tests/TypeAnnotations.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let field = class.view().fields().next().unwrap();
+        assert_eq!(field.attribute::<view::RuntimeVisibleTypeAnnotations>().unwrap().len(), 1);
+        assert!(field.attribute::<view::RuntimeInvisibleTypeAnnotations>().is_none());
+
+        let buf = read_class_file("./tests/Locals.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        let code = class.view().methods().nth(1).unwrap().code().unwrap();
+        let words = code.attribute::<view::LocalVariableTypeTable>().unwrap();
+        assert_eq!(words[0].name(&class.constant_pool).unwrap(), "words");
+    }
+
 }
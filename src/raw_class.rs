@@ -1,5 +1,11 @@
 use crate::access_flags::ClassAccessFlag;
-use crate::{Attribute, Constant, Field, Method};
+use crate::constant_pool::{ConstantPool, ConstantPoolBuilder};
+use crate::error::ErrorType;
+use crate::hierarchy::ClassHierarchy;
+use crate::view::ClassView;
+use crate::writer::ClassWriter;
+use crate::{Attribute, Field, Method};
+use std::borrow::Cow;
 
 /// A class file
 #[derive(Debug)]
@@ -8,15 +14,77 @@ pub struct RawClass<'c> {
     pub minor_version: u16,
     pub major_version: u16,
     pub constant_pool_count: u16,
-    pub constant_pool: Vec<Constant<'c>>,
+    pub constant_pool: ConstantPool<'c>,
     pub access_flags: ClassAccessFlag,
+    /// The bits of `access_flags` in the class file which are not defined by [`ClassAccessFlag`].
+    /// Writing the class adds them back, so classes using future or made up flags round trip
+    pub undefined_access_flags: u16,
     pub this_class: u16,
     pub super_class: u16,
     pub interface_count: u16,
+    pub interfaces: Vec<u16>,
     pub field_count: u16,
-    pub fields: Vec<Field>,
+    pub fields: Vec<Field<'c>>,
     pub methods_count: u16,
-    pub methods: Vec<Method>,
+    pub methods: Vec<Method<'c>>,
     pub attributes_count: u16,
-    pub attributes: Vec<Attribute>
+    pub attributes: Vec<Attribute<'c>>
+}
+
+impl<'c> RawClass<'c> {
+    /// A view resolving names and descriptors against the constant pool
+    pub fn view(&self) -> ClassView<'_, 'c> {
+        ClassView::new(self)
+    }
+
+    /// Serializes this class with a [`ClassWriter`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ErrorType> {
+        ClassWriter::new().write(self)
+    }
+
+    /// Resolves the internal names of the interfaces implemented by this class,
+    /// e.g. `java/lang/Runnable`
+    pub fn interface_names(&self) -> Result<Vec<Cow<'c, str>>, ErrorType> {
+        self.interfaces
+            .iter()
+            .map(|&index| self.constant_pool.class_name(index))
+            .collect()
+    }
+
+    /// Recomputes `max_stack` and `max_locals` of every method, see [`Method::compute_maxs`]
+    pub fn compute_maxs(&mut self) -> Result<(), ErrorType> {
+        let constant_pool = &self.constant_pool;
+
+        for method in self.methods.iter_mut() {
+            method.compute_maxs(constant_pool).map_err(|e| {
+                let name = constant_pool.utf8(method.name_index).unwrap_or_default();
+                e.within(format!("method {}", name))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Recomputes the `StackMapTable` of every method, see [`Method::compute_frames`].<br>
+    /// Class constants for the types in the frames are added to the constant pool
+    pub fn compute_frames(&mut self, hierarchy: &dyn ClassHierarchy) -> Result<(), ErrorType> {
+        let class_name = self.constant_pool.class_name(self.this_class)?.into_owned();
+        let mut constant_pool = ConstantPoolBuilder::from(std::mem::take(&mut self.constant_pool));
+
+        let result = self.methods.iter_mut().try_for_each(|method| {
+            method
+                .compute_frames(&class_name, &mut constant_pool, hierarchy)
+                .map_err(|e| {
+                    let name = constant_pool
+                        .constant_pool()
+                        .utf8(method.name_index)
+                        .unwrap_or_default();
+                    e.within(format!("method {}", name))
+                })
+        });
+
+        self.constant_pool = constant_pool.build();
+        self.constant_pool_count = self.constant_pool.len() as u16 + 1;
+        result
+    }
 }
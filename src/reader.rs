@@ -0,0 +1,71 @@
+use crate::error::{ErrorKind, ErrorType};
+
+/// A cursor over the bytes of a class file. Every read is bounds-checked and fails with
+/// [`ErrorKind::UnexpectedEof`] instead of panicking on truncated input
+pub(crate) struct Reader<'c> {
+    bytes: &'c [u8],
+    offset: usize
+}
+
+impl<'c> Reader<'c> {
+    pub fn new(bytes: &'c [u8]) -> Reader<'c> {
+        Reader { bytes, offset: 0 }
+    }
+
+    /// Returns the offset of the next byte to be read
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the number of bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    /// Creates an error of the given kind at the current offset
+    pub fn error(&self, kind: ErrorKind) -> ErrorType {
+        ErrorType::at(kind, self.offset)
+    }
+
+    /// Fails unless at least `len` bytes are left to read
+    pub fn ensure(&self, len: usize) -> Result<(), ErrorType> {
+        if self.remaining() < len {
+            return Err(self.error(ErrorKind::UnexpectedEof));
+        }
+        Ok(())
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'c [u8], ErrorType> {
+        self.ensure(len)?;
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    /// Skips `len` bytes
+    pub fn skip(&mut self, len: usize) -> Result<(), ErrorType> {
+        self.read_bytes(len).map(|_| ())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, ErrorType> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u16_be(&mut self) -> Result<u16, ErrorType> {
+        let b = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub fn read_u32_be(&mut self) -> Result<u32, ErrorType> {
+        let b = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub fn read_i16_be(&mut self) -> Result<i16, ErrorType> {
+        self.read_u16_be().map(|v| v as i16)
+    }
+
+    pub fn read_i32_be(&mut self) -> Result<i32, ErrorType> {
+        self.read_u32_be().map(|v| v as i32)
+    }
+}
@@ -0,0 +1,756 @@
+//! Parser for the generic signatures stored in a `Signature` attribute
+//!
+//! Signatures encode the declarations of generic classes, methods and fields, including the
+//! type arguments which are erased from descriptors, e.g. `Ljava/util/List<Ljava/lang/String;>;`
+//! for a `List<String>`.<br>
+//! See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.9.1> for more information
+
+use std::fmt;
+
+use crate::descriptor::BaseType;
+use crate::error::{ErrorKind, ErrorType};
+
+/// The type of a parameter, return value or array component
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JavaTypeSignature<'s> {
+    Base(BaseType),
+    Reference(ReferenceTypeSignature<'s>)
+}
+
+/// A class, interface, type variable or array type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReferenceTypeSignature<'s> {
+    Class(ClassTypeSignature<'s>),
+    /// The name of a type variable, e.g. `T`
+    TypeVariable(&'s str),
+    Array(Box<JavaTypeSignature<'s>>)
+}
+
+/// A possibly parameterized class or interface type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassTypeSignature<'s> {
+    /// The package in internal form, e.g. `java/util`, or an empty string for the unnamed package
+    pub package: &'s str,
+    /// The class followed by the inner classes it encloses, each with their own type arguments,
+    /// e.g. `Outer<T>` and `Inner<U>` for `Outer<T>.Inner<U>`
+    pub classes: Vec<SimpleClassTypeSignature<'s>>
+}
+
+/// A class name without its package, along with its type arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimpleClassTypeSignature<'s> {
+    pub name: &'s str,
+    pub type_arguments: Vec<TypeArgument<'s>>
+}
+
+/// A type argument of a parameterized type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeArgument<'s> {
+    /// `T`
+    Exact(ReferenceTypeSignature<'s>),
+    /// `? extends T`
+    Extends(ReferenceTypeSignature<'s>),
+    /// `? super T`
+    Super(ReferenceTypeSignature<'s>),
+    /// `?`
+    Unbounded
+}
+
+/// The declaration of a type parameter, e.g. `T extends Number & Comparable<T>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeParameter<'s> {
+    pub name: &'s str,
+    /// The class bound, absent if the type parameter is only bounded by interfaces
+    pub class_bound: Option<ReferenceTypeSignature<'s>>,
+    pub interface_bounds: Vec<ReferenceTypeSignature<'s>>
+}
+
+/// The signature of a generic class or interface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSignature<'s> {
+    pub type_parameters: Vec<TypeParameter<'s>>,
+    pub superclass: ClassTypeSignature<'s>,
+    pub interfaces: Vec<ClassTypeSignature<'s>>
+}
+
+/// The signature of a generic method or a method with generic parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSignature<'s> {
+    pub type_parameters: Vec<TypeParameter<'s>>,
+    pub params: Vec<JavaTypeSignature<'s>>,
+    /// The return type, `None` if the method returns `void`
+    pub ret: Option<JavaTypeSignature<'s>>,
+    /// The thrown class types and type variables
+    pub throws: Vec<ReferenceTypeSignature<'s>>
+}
+
+impl<'s> ReferenceTypeSignature<'s> {
+    /// Parses the signature of a field, local variable or record component
+    pub fn parse(signature: &'s str) -> Result<ReferenceTypeSignature<'s>, ErrorType> {
+        SignatureParser::parse(signature, "field signature", |parser| {
+            parser.reference_type()
+        })
+    }
+
+    /// Formats the type as it would appear in Java source code,
+    /// e.g. `java.util.Map<java.lang.String, java.util.List<? extends T>>`
+    pub fn to_java_string(&self) -> String {
+        let mut s = String::new();
+        self.write_java(&mut s);
+        s
+    }
+
+    fn write_java(&self, s: &mut String) {
+        match self {
+            ReferenceTypeSignature::Class(class_type) => class_type.write_java(s),
+            ReferenceTypeSignature::TypeVariable(name) => s.push_str(name),
+            ReferenceTypeSignature::Array(component_type) => {
+                component_type.write_java(s);
+                s.push_str("[]");
+            }
+        }
+    }
+
+    fn is_object(&self) -> bool {
+        match self {
+            ReferenceTypeSignature::Class(class_type) => match &class_type.classes[..] {
+                [class] => {
+                    class_type.package == "java/lang"
+                        && class.name == "Object"
+                        && class.type_arguments.is_empty()
+                }
+                _ => false
+            },
+            _ => false
+        }
+    }
+}
+
+impl<'s> JavaTypeSignature<'s> {
+    /// Formats the type as it would appear in Java source code
+    pub fn to_java_string(&self) -> String {
+        let mut s = String::new();
+        self.write_java(&mut s);
+        s
+    }
+
+    fn write_java(&self, s: &mut String) {
+        match self {
+            JavaTypeSignature::Base(base_type) => s.push_str(base_type.java_name()),
+            JavaTypeSignature::Reference(reference_type) => reference_type.write_java(s)
+        }
+    }
+}
+
+impl<'s> ClassTypeSignature<'s> {
+    /// The binary name of the class in internal form, e.g. `java/util/Map$Entry`
+    pub fn class_name(&self) -> String {
+        let names: Vec<_> = self.classes.iter().map(|class| class.name).collect();
+        let mut s = String::from(self.package);
+
+        if !s.is_empty() {
+            s.push('/');
+        }
+
+        s.push_str(&names.join("$"));
+        s
+    }
+
+    /// Formats the type as it would appear in Java source code
+    pub fn to_java_string(&self) -> String {
+        let mut s = String::new();
+        self.write_java(&mut s);
+        s
+    }
+
+    fn write_java(&self, s: &mut String) {
+        if !self.package.is_empty() {
+            s.push_str(&self.package.replace('/', "."));
+            s.push('.');
+        }
+
+        for (i, class) in self.classes.iter().enumerate() {
+            if i > 0 {
+                s.push('.');
+            }
+
+            s.push_str(class.name);
+
+            if !class.type_arguments.is_empty() {
+                s.push('<');
+
+                for (i, type_argument) in class.type_arguments.iter().enumerate() {
+                    if i > 0 {
+                        s.push_str(", ");
+                    }
+
+                    type_argument.write_java(s);
+                }
+
+                s.push('>');
+            }
+        }
+    }
+}
+
+impl<'s> TypeArgument<'s> {
+    fn write_java(&self, s: &mut String) {
+        match self {
+            TypeArgument::Exact(bound) => bound.write_java(s),
+            TypeArgument::Extends(bound) => {
+                s.push_str("? extends ");
+                bound.write_java(s);
+            }
+            TypeArgument::Super(bound) => {
+                s.push_str("? super ");
+                bound.write_java(s);
+            }
+            TypeArgument::Unbounded => s.push('?')
+        }
+    }
+}
+
+/// Writes type parameters in Java syntax, omitting `extends Object`
+fn write_java_type_parameters(type_parameters: &[TypeParameter], s: &mut String) {
+    if type_parameters.is_empty() {
+        return;
+    }
+
+    s.push('<');
+
+    for (i, type_parameter) in type_parameters.iter().enumerate() {
+        if i > 0 {
+            s.push_str(", ");
+        }
+
+        s.push_str(type_parameter.name);
+
+        let bounds: Vec<_> = type_parameter
+            .class_bound
+            .iter()
+            .chain(type_parameter.interface_bounds.iter())
+            .collect();
+
+        if let [bound] = &bounds[..] {
+            if bound.is_object() {
+                continue;
+            }
+        }
+
+        for (i, bound) in bounds.iter().enumerate() {
+            s.push_str(if i == 0 { " extends " } else { " & " });
+            bound.write_java(s);
+        }
+    }
+
+    s.push('>');
+}
+
+impl<'s> ClassSignature<'s> {
+    /// Parses the signature of a class or interface
+    pub fn parse(signature: &'s str) -> Result<ClassSignature<'s>, ErrorType> {
+        SignatureParser::parse(signature, "class signature", |parser| {
+            let type_parameters = parser.type_parameters()?;
+            let superclass = parser.class_type()?;
+            let mut interfaces = Vec::new();
+
+            while parser.peek().is_some() {
+                interfaces.push(parser.class_type()?);
+            }
+
+            Ok(ClassSignature {
+                type_parameters,
+                superclass,
+                interfaces
+            })
+        })
+    }
+
+    /// Formats the signature as a Java class declaration with the given name,
+    /// e.g. `Foo<T extends java.lang.Number> extends java.lang.Object implements java.lang.Runnable`
+    pub fn to_java_string(&self, name: &str) -> String {
+        let mut s = String::from(name);
+        write_java_type_parameters(&self.type_parameters, &mut s);
+
+        s.push_str(" extends ");
+        self.superclass.write_java(&mut s);
+
+        for (i, interface) in self.interfaces.iter().enumerate() {
+            s.push_str(if i == 0 { " implements " } else { ", " });
+            interface.write_java(&mut s);
+        }
+
+        s
+    }
+}
+
+impl<'s> MethodSignature<'s> {
+    /// Parses the signature of a method
+    pub fn parse(signature: &'s str) -> Result<MethodSignature<'s>, ErrorType> {
+        SignatureParser::parse(signature, "method signature", |parser| {
+            let type_parameters = parser.type_parameters()?;
+            parser.expect(b'(', "method signature without parameters")?;
+
+            let mut params = Vec::new();
+
+            while parser.peek() != Some(b')') {
+                params.push(parser.java_type()?);
+            }
+
+            parser.pos += 1;
+
+            let ret = match parser.peek() {
+                Some(b'V') => {
+                    parser.pos += 1;
+                    None
+                }
+                _ => Some(parser.java_type()?)
+            };
+
+            let mut throws = Vec::new();
+
+            while parser.peek().is_some() {
+                parser.expect(b'^', "invalid throws signature")?;
+
+                match parser.reference_type()? {
+                    ReferenceTypeSignature::Array(_) => {
+                        return Err(ErrorKind::Malformed("array type in throws signature").into());
+                    }
+                    exception => throws.push(exception)
+                }
+            }
+
+            Ok(MethodSignature {
+                type_parameters,
+                params,
+                ret,
+                throws
+            })
+        })
+    }
+
+    /// Formats the signature as a Java method declaration with the given name,
+    /// e.g. `<T> java.util.List<T> copy(T[]) throws java.io.IOException`
+    pub fn to_java_string(&self, name: &str) -> String {
+        let mut s = String::new();
+
+        if !self.type_parameters.is_empty() {
+            write_java_type_parameters(&self.type_parameters, &mut s);
+            s.push(' ');
+        }
+
+        match &self.ret {
+            Some(ret) => ret.write_java(&mut s),
+            None => s.push_str("void")
+        }
+
+        s.push(' ');
+        s.push_str(name);
+        s.push('(');
+
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                s.push_str(", ");
+            }
+
+            param.write_java(&mut s);
+        }
+
+        s.push(')');
+
+        for (i, exception) in self.throws.iter().enumerate() {
+            s.push_str(if i == 0 { " throws " } else { ", " });
+            exception.write_java(&mut s);
+        }
+
+        s
+    }
+}
+
+impl fmt::Display for JavaTypeSignature<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JavaTypeSignature::Base(base_type) => write!(f, "{}", base_type.descriptor()),
+            JavaTypeSignature::Reference(reference_type) => write!(f, "{}", reference_type)
+        }
+    }
+}
+
+impl fmt::Display for ReferenceTypeSignature<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReferenceTypeSignature::Class(class_type) => write!(f, "{}", class_type),
+            ReferenceTypeSignature::TypeVariable(name) => write!(f, "T{};", name),
+            ReferenceTypeSignature::Array(component_type) => write!(f, "[{}", component_type)
+        }
+    }
+}
+
+impl fmt::Display for ClassTypeSignature<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "L")?;
+
+        if !self.package.is_empty() {
+            write!(f, "{}/", self.package)?;
+        }
+
+        for (i, class) in self.classes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+
+            write!(f, "{}", class.name)?;
+
+            if !class.type_arguments.is_empty() {
+                write!(f, "<")?;
+
+                for type_argument in &class.type_arguments {
+                    match type_argument {
+                        TypeArgument::Exact(bound) => write!(f, "{}", bound)?,
+                        TypeArgument::Extends(bound) => write!(f, "+{}", bound)?,
+                        TypeArgument::Super(bound) => write!(f, "-{}", bound)?,
+                        TypeArgument::Unbounded => write!(f, "*")?
+                    }
+                }
+
+                write!(f, ">")?;
+            }
+        }
+
+        write!(f, ";")
+    }
+}
+
+impl fmt::Display for TypeParameter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.name)?;
+
+        if let Some(class_bound) = &self.class_bound {
+            write!(f, "{}", class_bound)?;
+        }
+
+        for interface_bound in &self.interface_bounds {
+            write!(f, ":{}", interface_bound)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn fmt_type_parameters(
+    type_parameters: &[TypeParameter],
+    f: &mut fmt::Formatter<'_>
+) -> fmt::Result {
+    if type_parameters.is_empty() {
+        return Ok(());
+    }
+
+    write!(f, "<")?;
+
+    for type_parameter in type_parameters {
+        write!(f, "{}", type_parameter)?;
+    }
+
+    write!(f, ">")
+}
+
+impl fmt::Display for ClassSignature<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_type_parameters(&self.type_parameters, f)?;
+        write!(f, "{}", self.superclass)?;
+
+        for interface in &self.interfaces {
+            write!(f, "{}", interface)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for MethodSignature<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_type_parameters(&self.type_parameters, f)?;
+        write!(f, "(")?;
+
+        for param in &self.params {
+            write!(f, "{}", param)?;
+        }
+
+        match &self.ret {
+            Some(ret) => write!(f, "){}", ret)?,
+            None => write!(f, ")V")?
+        }
+
+        for exception in &self.throws {
+            write!(f, "^{}", exception)?;
+        }
+
+        Ok(())
+    }
+}
+
+struct SignatureParser<'s> {
+    signature: &'s str,
+    pos: usize
+}
+
+impl<'s> SignatureParser<'s> {
+    /// Parses the whole `signature` with `f`, recording `kind` in the context of errors
+    fn parse<T, F>(signature: &'s str, kind: &str, f: F) -> Result<T, ErrorType>
+    where
+        F: FnOnce(&mut SignatureParser<'s>) -> Result<T, ErrorType>
+    {
+        let mut parser = SignatureParser { signature, pos: 0 };
+
+        f(&mut parser)
+            .and_then(|result| match parser.peek() {
+                None => Ok(result),
+                Some(_) => Err(ErrorKind::Malformed("trailing characters after signature").into())
+            })
+            .map_err(|e| e.within(format!("{} {}", kind, signature)))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.signature.as_bytes().get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: u8, reason: &'static str) -> Result<(), ErrorType> {
+        if self.peek() != Some(c) {
+            return Err(ErrorKind::Malformed(reason).into());
+        }
+
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Reads an identifier, which ends at any of `.;[/<>:`
+    fn identifier(&mut self) -> Result<&'s str, ErrorType> {
+        let rest = &self.signature[self.pos..];
+        let len = rest
+            .find(&['.', ';', '[', '/', '<', '>', ':'][..])
+            .unwrap_or(rest.len());
+
+        if len == 0 {
+            return Err(ErrorKind::Malformed("missing identifier in signature").into());
+        }
+
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn java_type(&mut self) -> Result<JavaTypeSignature<'s>, ErrorType> {
+        if let Some(base_type) = self.peek().and_then(BaseType::from_char) {
+            self.pos += 1;
+            return Ok(JavaTypeSignature::Base(base_type));
+        }
+
+        Ok(JavaTypeSignature::Reference(self.reference_type()?))
+    }
+
+    fn reference_type(&mut self) -> Result<ReferenceTypeSignature<'s>, ErrorType> {
+        match self.peek() {
+            Some(b'L') => Ok(ReferenceTypeSignature::Class(self.class_type()?)),
+            Some(b'T') => {
+                self.pos += 1;
+                let name = self.identifier()?;
+                self.expect(b';', "unterminated type variable in signature")?;
+                Ok(ReferenceTypeSignature::TypeVariable(name))
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                Ok(ReferenceTypeSignature::Array(Box::new(self.java_type()?)))
+            }
+            Some(_) => Err(ErrorKind::Malformed("invalid type in signature").into()),
+            None => Err(ErrorKind::Malformed("incomplete signature").into())
+        }
+    }
+
+    fn class_type(&mut self) -> Result<ClassTypeSignature<'s>, ErrorType> {
+        self.expect(b'L', "expected class type in signature")?;
+
+        let start = self.pos;
+        let mut package_end = start;
+        let mut name = self.identifier()?;
+
+        while self.peek() == Some(b'/') {
+            package_end = self.pos;
+            self.pos += 1;
+            name = self.identifier()?;
+        }
+
+        let package = &self.signature[start..package_end];
+        let mut classes = vec![self.simple_class_type(name)?];
+
+        while self.peek() == Some(b'.') {
+            self.pos += 1;
+            let name = self.identifier()?;
+            classes.push(self.simple_class_type(name)?);
+        }
+
+        self.expect(b';', "unterminated class type in signature")?;
+
+        Ok(ClassTypeSignature { package, classes })
+    }
+
+    fn simple_class_type(
+        &mut self,
+        name: &'s str
+    ) -> Result<SimpleClassTypeSignature<'s>, ErrorType> {
+        let mut type_arguments = Vec::new();
+
+        if self.peek() == Some(b'<') {
+            self.pos += 1;
+
+            while self.peek() != Some(b'>') {
+                let type_argument = match self.peek() {
+                    Some(b'*') => {
+                        self.pos += 1;
+                        TypeArgument::Unbounded
+                    }
+                    Some(b'+') => {
+                        self.pos += 1;
+                        TypeArgument::Extends(self.reference_type()?)
+                    }
+                    Some(b'-') => {
+                        self.pos += 1;
+                        TypeArgument::Super(self.reference_type()?)
+                    }
+                    _ => TypeArgument::Exact(self.reference_type()?)
+                };
+
+                type_arguments.push(type_argument);
+            }
+
+            self.pos += 1;
+
+            if type_arguments.is_empty() {
+                return Err(ErrorKind::Malformed("empty type arguments in signature").into());
+            }
+        }
+
+        Ok(SimpleClassTypeSignature {
+            name,
+            type_arguments
+        })
+    }
+
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter<'s>>, ErrorType> {
+        let mut type_parameters = Vec::new();
+
+        if self.peek() != Some(b'<') {
+            return Ok(type_parameters);
+        }
+
+        self.pos += 1;
+
+        while self.peek() != Some(b'>') {
+            let name = self.identifier()?;
+            self.expect(b':', "type parameter without a class bound")?;
+
+            let class_bound = match self.peek() {
+                Some(b':') | Some(b'>') => None,
+                _ => Some(self.reference_type()?)
+            };
+
+            let mut interface_bounds = Vec::new();
+
+            while self.peek() == Some(b':') {
+                self.pos += 1;
+                interface_bounds.push(self.reference_type()?);
+            }
+
+            type_parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds
+            });
+        }
+
+        self.pos += 1;
+
+        if type_parameters.is_empty() {
+            return Err(ErrorKind::Malformed("empty type parameters in signature").into());
+        }
+
+        Ok(type_parameters)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        ClassSignature, JavaTypeSignature, MethodSignature, ReferenceTypeSignature, TypeArgument
+    };
+    use crate::test_util::expect_pat;
+
+    #[test]
+    fn parse() {
+        // The signatures javac emits for tests/Generics.java
+        let signature = "<K::Ljava/lang/Comparable<TK;>;V:Ljava/lang/Object;>Ljava/lang/Object;Ljava/lang/Iterable<Ljava/util/Map$Entry<TK;Ljava/util/List<-TV;>;>;>;";
+        let class_signature = ClassSignature::parse(signature).unwrap();
+        assert_eq!(class_signature.to_string(), signature);
+        assert_eq!(class_signature.type_parameters.len(), 2);
+        assert!(class_signature.type_parameters[0].class_bound.is_none());
+        assert_eq!(class_signature.superclass.class_name(), "java/lang/Object");
+        assert_eq!(class_signature.interfaces[0].class_name(), "java/lang/Iterable");
+        assert_eq!(
+            class_signature.to_java_string("Generics"),
+            "Generics<K extends java.lang.Comparable<K>, V> extends java.lang.Object \
+             implements java.lang.Iterable<java.util.Map$Entry<K, java.util.List<? super V>>>"
+        );
+
+        let signature = "Ljava/util/Map<Ljava/lang/String;Ljava/util/List<+Ljava/lang/Number;>;>;";
+        let numbers = ReferenceTypeSignature::parse(signature).unwrap();
+        assert_eq!(numbers.to_string(), signature);
+        assert_eq!(
+            numbers.to_java_string(),
+            "java.util.Map<java.lang.String, java.util.List<? extends java.lang.Number>>"
+        );
+
+        expect_pat!(ReferenceTypeSignature::Class(map), &numbers, {
+            assert_eq!(map.package, "java/util");
+            assert_eq!(map.classes[0].name, "Map");
+            expect_pat!(TypeArgument::Exact(ReferenceTypeSignature::Class(list)), &map.classes[0].type_arguments[1], {
+                expect_pat!(TypeArgument::Extends(_), &list.classes[0].type_arguments[0], {});
+            });
+        });
+
+        let signature = "[LGenerics<TK;TV;>.Node<[I>;";
+        let nodes = ReferenceTypeSignature::parse(signature).unwrap();
+        assert_eq!(nodes.to_string(), signature);
+        assert_eq!(nodes.to_java_string(), "Generics<K, V>.Node<int[]>[]");
+
+        expect_pat!(ReferenceTypeSignature::Array(node), &nodes, {
+            expect_pat!(JavaTypeSignature::Reference(ReferenceTypeSignature::Class(node)), node.as_ref(), {
+                assert_eq!(node.package, "");
+                assert_eq!(node.classes.len(), 2);
+                assert_eq!(node.class_name(), "Generics$Node");
+            });
+        });
+
+        let signature = "<E:Ljava/lang/Exception;:Ljava/lang/Runnable;T:Ljava/lang/Object;>([TT;JLjava/util/Map<*TV;>;)[Ljava/util/List<TT;>;^TE;^Ljava/io/IOException;";
+        let method_signature = MethodSignature::parse(signature).unwrap();
+        assert_eq!(method_signature.to_string(), signature);
+        assert_eq!(method_signature.params.len(), 3);
+        assert_eq!(method_signature.throws, vec![
+            ReferenceTypeSignature::TypeVariable("E"),
+            ReferenceTypeSignature::parse("Ljava/io/IOException;").unwrap()
+        ]);
+        assert_eq!(
+            method_signature.to_java_string("convert"),
+            "<E extends java.lang.Exception & java.lang.Runnable, T> java.util.List<T>[] \
+             convert(T[], long, java.util.Map<?, V>) throws E, java.io.IOException"
+        );
+
+        for invalid in &["", "TT", "Ljava/util/List<>;", "Ljava/lang/String", "I", "Ljava/lang/Object;;"] {
+            assert!(ReferenceTypeSignature::parse(invalid).is_err(), "{}", invalid);
+        }
+
+        for invalid in &["()", "(I)VV", "<>()V", "()V^[Ljava/lang/Exception;", "(TT)V"] {
+            assert!(MethodSignature::parse(invalid).is_err(), "{}", invalid);
+        }
+
+        let e = ClassSignature::parse("<T:Ljava/lang/Object;>").unwrap_err();
+        assert_eq!(e.context(), ["class signature <T:Ljava/lang/Object;>"]);
+    }
+}
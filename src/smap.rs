@@ -0,0 +1,357 @@
+//! Parser for the JSR-45 source maps (SMAP) stored in a `SourceDebugExtension` attribute
+//!
+//! Compilers of languages other than Java, like Kotlin or JSP, use source maps to map the line
+//! numbers of the generated class file back to the original sources. The line numbers found in
+//! a `LineNumberTable` are *output* lines which a stratum translates to *input* lines.<br>
+//! See <https://jcp.org/en/jsr/detail?id=45> for more information
+
+use std::str;
+
+use crate::attribute::Attribute;
+use crate::error::{ErrorKind, ErrorType};
+
+/// A parsed source map
+#[derive(Debug)]
+pub struct Smap<'s> {
+    pub output_file_name: &'s str,
+    pub default_stratum: &'s str,
+    pub strata: Vec<Stratum<'s>>
+}
+
+/// A single stratum, mapping output lines to the lines of one source language
+#[derive(Debug)]
+pub struct Stratum<'s> {
+    pub id: &'s str,
+    pub files: Vec<FileInfo<'s>>,
+    pub lines: Vec<LineInfo>
+}
+
+/// An entry of a file section
+#[derive(Debug)]
+pub struct FileInfo<'s> {
+    pub id: u32,
+    pub name: &'s str,
+    /// The path of the file relative to the source roots, if present
+    pub path: Option<&'s str>
+}
+
+/// An entry of a line section.<br>
+/// Maps input line `input_start_line + n` to the output lines
+/// `output_start_line + n * output_line_increment` up to (excluding)
+/// `output_start_line + (n + 1) * output_line_increment` for every `n < repeat_count`
+#[derive(Debug)]
+pub struct LineInfo {
+    pub input_start_line: u32,
+    pub line_file_id: u32,
+    pub repeat_count: u32,
+    pub output_start_line: u32,
+    pub output_line_increment: u32
+}
+
+impl LineInfo {
+    /// Maps `output_line` to the input line, if it is covered by this entry
+    pub fn map(&self, output_line: u32) -> Option<u32> {
+        if output_line < self.output_start_line || self.output_line_increment == 0 {
+            return None;
+        }
+
+        let n = (output_line - self.output_start_line) / self.output_line_increment;
+
+        if n < self.repeat_count {
+            self.input_start_line.checked_add(n)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'s> Stratum<'s> {
+    /// Looks up the file with the given id
+    pub fn file(&self, id: u32) -> Option<&FileInfo<'s>> {
+        self.files.iter().find(|file| file.id == id)
+    }
+
+    /// Maps `output_line` to the input file and line
+    pub fn map_line(&self, output_line: u32) -> Option<(&FileInfo<'s>, u32)> {
+        self.lines.iter().find_map(|line| {
+            let input_line = line.map(output_line)?;
+            let file = self.file(line.line_file_id)?;
+            Some((file, input_line))
+        })
+    }
+}
+
+impl<'s> Smap<'s> {
+    /// Parses the contents of a `SourceDebugExtension` attribute.<br>
+    /// Fails if the source map contains a null or supplementary character, whose modified UTF-8
+    /// encoding is not valid UTF-8. Decode those with [`mutf8::decode`](crate::mutf8::decode)
+    /// and use [`Smap::parse`] instead
+    pub fn from_bytes(debug_extension: &'s [u8]) -> Result<Smap<'s>, ErrorType> {
+        Smap::parse(str::from_utf8(debug_extension)?)
+    }
+
+    /// Parses the textual representation of a source map
+    pub fn parse(text: &'s str) -> Result<Smap<'s>, ErrorType> {
+        let mut lines = text
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .enumerate();
+
+        let mut header = || match lines.next() {
+            Some((_, line)) => Ok(line),
+            None => Err(ErrorType::from(ErrorKind::Malformed(
+                "incomplete SMAP header"
+            )))
+        };
+
+        if header()? != "SMAP" {
+            return Err(ErrorKind::Malformed("missing SMAP header").into());
+        }
+
+        let output_file_name = header()?;
+        let default_stratum = header()?;
+
+        let mut strata: Vec<Stratum<'s>> = Vec::new();
+        let mut in_stratum = false;
+        let mut section = Section::None;
+        let mut line_file_id = 0;
+        let mut pending_file: Option<(u32, &'s str)> = None;
+
+        for (n, line) in lines {
+            let within = |e: ErrorType| e.within(format!("SMAP line {}", n + 1));
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if let Some((id, name)) = pending_file.take() {
+                let stratum = strata.last_mut().unwrap();
+                stratum.files.push(FileInfo {
+                    id,
+                    name,
+                    path: Some(line)
+                });
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('*') {
+                section = match header.get(..1) {
+                    Some("S") => {
+                        let id = header[1..].trim();
+                        strata.push(Stratum {
+                            id,
+                            files: Vec::new(),
+                            lines: Vec::new()
+                        });
+                        line_file_id = 0;
+                        in_stratum = true;
+                        Section::None
+                    }
+                    // ends the current stratum, Kotlin follows it with a `KotlinDebug` stratum
+                    Some("E") => {
+                        in_stratum = false;
+                        Section::None
+                    }
+                    Some("F") | Some("L") if !in_stratum => {
+                        return Err(within(
+                            ErrorKind::Malformed("SMAP section outside of a stratum").into()
+                        ));
+                    }
+                    Some("F") => Section::File,
+                    Some("L") => Section::Line,
+                    Some("O") | Some("C") => {
+                        return Err(within(
+                            ErrorKind::Malformed("embedded SMAPs are not supported").into()
+                        ));
+                    }
+                    // vendor and unknown sections are skipped
+                    _ => Section::None
+                };
+                continue;
+            }
+
+            match section {
+                Section::None => {}
+                Section::File => {
+                    let stratum = strata.last_mut().unwrap();
+                    let (with_path, entry) = match line.strip_prefix('+') {
+                        Some(entry) => (true, entry),
+                        None => (false, line)
+                    };
+                    let (id, name) = parse_file_info(entry.trim()).map_err(within)?;
+
+                    if with_path {
+                        pending_file = Some((id, name));
+                    } else {
+                        stratum.files.push(FileInfo {
+                            id,
+                            name,
+                            path: None
+                        });
+                    }
+                }
+                Section::Line => {
+                    let stratum = strata.last_mut().unwrap();
+                    let line = parse_line_info(line.trim(), line_file_id).map_err(within)?;
+
+                    line_file_id = line.line_file_id;
+                    stratum.lines.push(line);
+                }
+            }
+        }
+
+        Ok(Smap {
+            output_file_name,
+            default_stratum,
+            strata
+        })
+    }
+
+    /// Looks up the stratum with the given id
+    pub fn stratum(&self, id: &str) -> Option<&Stratum<'s>> {
+        self.strata.iter().find(|stratum| stratum.id == id)
+    }
+
+    /// Maps `output_line` to the input file and line of the given stratum
+    pub fn map_line(&self, stratum: &str, output_line: u32) -> Option<(&FileInfo<'s>, u32)> {
+        self.stratum(stratum)?.map_line(output_line)
+    }
+
+    /// Maps the instruction at `pc` of a `Code` attribute to the input file and line of the given
+    /// stratum, using the line numbers found in its `LineNumberTable`
+    pub fn map_pc(&self, stratum: &str, code: &Attribute, pc: u16) -> Option<(&FileInfo<'s>, u32)> {
+        let output_line = code.line_number_at(pc)?;
+        self.map_line(stratum, output_line as u32)
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Section {
+    None,
+    File,
+    Line
+}
+
+fn parse_number(s: &str) -> Result<u32, ErrorType> {
+    s.parse()
+        .map_err(|_| ErrorKind::Malformed("invalid number in SMAP").into())
+}
+
+/// Parses `FileID FileName`
+fn parse_file_info(entry: &str) -> Result<(u32, &str), ErrorType> {
+    let mut parts = entry.splitn(2, ' ');
+    let id = parse_number(parts.next().unwrap_or(""))?;
+
+    match parts.next() {
+        Some(name) => Ok((id, name.trim())),
+        None => Err(ErrorKind::Malformed("SMAP file info without a name").into())
+    }
+}
+
+/// Parses `InputStartLine[#LineFileID][,RepeatCount]:OutputStartLine[,OutputLineIncrement]`
+fn parse_line_info(entry: &str, line_file_id: u32) -> Result<LineInfo, ErrorType> {
+    let mut parts = entry.splitn(2, ':');
+    let input = parts.next().unwrap_or("");
+    let output = parts
+        .next()
+        .ok_or(ErrorKind::Malformed("SMAP line info without output lines"))?;
+
+    let (input, repeat_count) = match input.find(',') {
+        Some(i) => (&input[..i], parse_number(&input[i + 1..])?),
+        None => (input, 1)
+    };
+    let (input_start_line, line_file_id) = match input.find('#') {
+        Some(i) => (parse_number(&input[..i])?, parse_number(&input[i + 1..])?),
+        None => (parse_number(input)?, line_file_id)
+    };
+    let (output_start_line, output_line_increment) = match output.find(',') {
+        Some(i) => (parse_number(&output[..i])?, parse_number(&output[i + 1..])?),
+        None => (parse_number(output)?, 1)
+    };
+
+    Ok(LineInfo {
+        input_start_line,
+        line_file_id,
+        repeat_count,
+        output_start_line,
+        output_line_increment
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::Smap;
+    use crate::attribute::{Attribute, LineNumberTableEntry};
+    use crate::opcode::Instruction;
+    use crate::{CodeBuilder, ConstantPoolBuilder};
+
+    // As emitted by kotlinc for a call of an inline function declared in Util.kt
+    const KOTLIN: &str = "SMAP\nMain.kt\nKotlin\n*S Kotlin\n*F\n+ 1 Main.kt\ncom/example/MainKt\n+ 2 Util.kt\ncom/example/UtilKt\n*L\n1#1,10:1\n5#2,3:11\n*E\n*S KotlinDebug\n*F\n+ 1 Main.kt\ncom/example/MainKt\n*L\n4#1:11,3\n*E\n";
+
+    #[test]
+    fn parse_strata() {
+        let smap = Smap::parse(KOTLIN).unwrap();
+
+        assert_eq!(smap.output_file_name, "Main.kt");
+        assert_eq!(smap.default_stratum, "Kotlin");
+        assert_eq!(smap.strata.len(), 2);
+
+        let kotlin = smap.stratum("Kotlin").unwrap();
+        assert_eq!(kotlin.files[1].name, "Util.kt");
+        assert_eq!(kotlin.files[1].path, Some("com/example/UtilKt"));
+
+        let (file, line) = smap.map_line("Kotlin", 7).unwrap();
+        assert_eq!((file.name, line), ("Main.kt", 7));
+        let (file, line) = smap.map_line("Kotlin", 12).unwrap();
+        assert_eq!((file.name, line), ("Util.kt", 6));
+        assert!(smap.map_line("Kotlin", 14).is_none());
+
+        let (file, line) = smap.map_line("KotlinDebug", 13).unwrap();
+        assert_eq!((file.path, line), (Some("com/example/MainKt"), 4));
+        assert!(smap.map_line("KotlinDebug", 14).is_none());
+        assert!(smap.map_line("JSP", 1).is_none());
+
+        // blank lines are skipped, even within a section
+        let smap = Smap::parse("SMAP\nMain.kt\nKotlin\n*S Kotlin\n*F\n1 Main.kt\n*L\n1#1,2:1\n\n3#1:5\n*E\n").unwrap();
+        assert_eq!(smap.stratum("Kotlin").unwrap().lines.len(), 2);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let e = Smap::parse("SMAP\nMain.kt\nKotlin\n*S Kotlin\n*L\n1#1,x:1\n").unwrap_err();
+        assert_eq!(e.context(), ["SMAP line 6"]);
+
+        assert!(Smap::parse("SMAP\nMain.kt").is_err());
+        assert!(Smap::parse("SMAP\nMain.kt\nKotlin\n*L\n1#1,10:1\n").is_err());
+        assert!(Smap::parse("SMAP\nMain.kt\nKotlin\n*S Kotlin\n*E\n*L\n1#1,10:1\n").is_err());
+    }
+
+    #[test]
+    fn map_pc() {
+        let mut code = CodeBuilder::new();
+        code.push(Instruction::nop);
+        code.push(Instruction::nop);
+        code.push(Instruction::r#eturn);
+
+        let mut code = code.build(&mut ConstantPoolBuilder::new(), 0, 0).unwrap();
+        if let Attribute::Code { attributes, .. } = &mut code {
+            attributes.push(Attribute::LineNumberTable {
+                attribute_name_index: 0,
+                attribute_length: 10,
+                line_number_table_length: 2,
+                line_number_table: vec![
+                    LineNumberTableEntry { start_pc: 0, line_number: 3 },
+                    LineNumberTableEntry { start_pc: 2, line_number: 5 }
+                ]
+            });
+        }
+
+        assert_eq!(code.line_number_at(1), Some(3));
+        assert_eq!(code.line_number_at(2), Some(5));
+
+        let smap = Smap::parse("SMAP\nMain.java\nJava\n*S Template\n*F\n1 main.tpl\n*L\n1#1,20:1\n*E\n").unwrap();
+        let (file, line) = smap.map_pc("Template", &code, 2).unwrap();
+        assert_eq!((file.name, line), ("main.tpl", 5));
+        assert!(smap.map_pc("Java", &code, 2).is_none());
+    }
+}
@@ -0,0 +1,256 @@
+//! Types representing the frames of a `StackMapTable` attribute
+
+use crate::error::{ErrorKind, ErrorType};
+
+/// The verification type of a local variable or operand stack entry<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.4> for more information
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VerificationTypeInfo {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    /// An instance of the class referenced by the `Class` constant at `cpool_index`
+    Object {
+        cpool_index: u16
+    },
+    /// The result of the `new` instruction at `offset`, before its constructor has been invoked
+    Uninitialized {
+        offset: u16
+    }
+}
+
+/// A delta-encoded stack map frame<br>
+/// See <https://docs.oracle.com/javase/specs/jvms/se14/html/jvms-4.html#jvms-4.7.4> for more information
+#[derive(Debug, PartialEq, Eq)]
+pub enum StackMapFrame {
+    /// Same locals as the previous frame and an empty stack, `frame_type` is the offset delta
+    Same {
+        frame_type: u8
+    },
+    /// Same locals as the previous frame and a single stack item,
+    /// `frame_type - 64` is the offset delta
+    SameLocals1StackItem {
+        frame_type: u8,
+        stack: VerificationTypeInfo
+    },
+    SameLocals1StackItemExtended {
+        frame_type: u8,
+        offset_delta: u16,
+        stack: VerificationTypeInfo
+    },
+    /// The last `251 - frame_type` locals of the previous frame are absent and the stack is empty
+    Chop {
+        frame_type: u8,
+        offset_delta: u16
+    },
+    SameExtended {
+        frame_type: u8,
+        offset_delta: u16
+    },
+    /// `frame_type - 251` additional locals and an empty stack
+    Append {
+        frame_type: u8,
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>
+    },
+    Full {
+        frame_type: u8,
+        offset_delta: u16,
+        number_of_locals: u16,
+        locals: Vec<VerificationTypeInfo>,
+        number_of_stack_items: u16,
+        stack: Vec<VerificationTypeInfo>
+    }
+}
+
+impl StackMapFrame {
+    /// Returns the offset delta encoded in this frame
+    pub fn offset_delta(&self) -> u16 {
+        match self {
+            StackMapFrame::Same { frame_type } => *frame_type as u16,
+            StackMapFrame::SameLocals1StackItem { frame_type, .. } => *frame_type as u16 - 64,
+            StackMapFrame::SameLocals1StackItemExtended { offset_delta, .. }
+            | StackMapFrame::Chop { offset_delta, .. }
+            | StackMapFrame::SameExtended { offset_delta, .. }
+            | StackMapFrame::Append { offset_delta, .. }
+            | StackMapFrame::Full { offset_delta, .. } => *offset_delta
+        }
+    }
+
+    /// The number of bytes this frame occupies in a `StackMapTable` attribute
+    pub(crate) fn encoded_len(&self) -> u32 {
+        let types_len = |types: &[VerificationTypeInfo]| -> u32 {
+            types
+                .iter()
+                .map(|info| match info {
+                    VerificationTypeInfo::Object { .. }
+                    | VerificationTypeInfo::Uninitialized { .. } => 3,
+                    _ => 1
+                })
+                .sum()
+        };
+
+        match self {
+            StackMapFrame::Same { .. } => 1,
+            StackMapFrame::SameLocals1StackItem { stack, .. } => 1 + types_len(&[*stack]),
+            StackMapFrame::SameLocals1StackItemExtended { stack, .. } => 3 + types_len(&[*stack]),
+            StackMapFrame::Chop { .. } | StackMapFrame::SameExtended { .. } => 3,
+            StackMapFrame::Append { locals, .. } => 3 + types_len(locals),
+            StackMapFrame::Full { locals, stack, .. } => 7 + types_len(locals) + types_len(stack)
+        }
+    }
+}
+
+/// A stack map frame with absolute bytecode offset and the complete types of all locals
+/// and stack entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub offset: u16,
+    pub locals: Vec<VerificationTypeInfo>,
+    pub stack: Vec<VerificationTypeInfo>
+}
+
+/// Expands the delta-encoded `entries` of a `StackMapTable` attribute into frames with absolute
+/// offsets.<br>
+/// `initial_locals` are the locals of the implicit initial frame, derived from the method
+/// descriptor: `this` (unless the method is static) followed by the parameter types
+pub fn expand_frames(
+    initial_locals: &[VerificationTypeInfo],
+    entries: &[StackMapFrame]
+) -> Result<Vec<Frame>, ErrorType> {
+    let mut frames = Vec::with_capacity(entries.len());
+    let mut locals = initial_locals.to_vec();
+    let mut offset: Option<u16> = None;
+
+    for entry in entries {
+        let delta = entry.offset_delta();
+        let next = match offset {
+            None => Some(delta),
+            Some(previous) => previous
+                .checked_add(delta)
+                .and_then(|offset| offset.checked_add(1))
+        };
+        let next = next.ok_or(ErrorKind::Malformed("stack map frame offset overflows"))?;
+
+        let stack = match entry {
+            StackMapFrame::Same { .. } | StackMapFrame::SameExtended { .. } => Vec::new(),
+            StackMapFrame::SameLocals1StackItem { stack, .. }
+            | StackMapFrame::SameLocals1StackItemExtended { stack, .. } => vec![*stack],
+            StackMapFrame::Chop { frame_type, .. } => {
+                let k = (251 - frame_type) as usize;
+
+                if k > locals.len() {
+                    return Err(ErrorKind::Malformed("chop frame removes too many locals").into());
+                }
+
+                locals.truncate(locals.len() - k);
+                Vec::new()
+            }
+            StackMapFrame::Append {
+                locals: appended, ..
+            } => {
+                locals.extend_from_slice(appended);
+                Vec::new()
+            }
+            StackMapFrame::Full {
+                locals: full,
+                stack,
+                ..
+            } => {
+                locals = full.clone();
+                stack.clone()
+            }
+        };
+
+        frames.push(Frame {
+            offset: next,
+            locals: locals.clone(),
+            stack
+        });
+        offset = Some(next);
+    }
+
+    Ok(frames)
+}
+
+/// Delta-encodes `frames` into entries of a `StackMapTable` attribute, the inverse of
+/// [`expand_frames`]. `frames` must be sorted by offset, without duplicate offsets.<br>
+/// Each frame is encoded in the most compact form available
+pub fn compress_frames(
+    initial_locals: &[VerificationTypeInfo],
+    frames: &[Frame]
+) -> Result<Vec<StackMapFrame>, ErrorType> {
+    let mut entries = Vec::with_capacity(frames.len());
+    let mut locals = initial_locals;
+    let mut offset: Option<u16> = None;
+
+    for frame in frames {
+        let offset_delta = match offset {
+            None => Some(frame.offset),
+            Some(previous) => frame
+                .offset
+                .checked_sub(previous)
+                .and_then(|delta| delta.checked_sub(1))
+        };
+        let offset_delta = offset_delta.ok_or(ErrorKind::Malformed(
+            "stack map frames are not sorted by offset"
+        ))?;
+
+        let same_locals = frame.locals == locals;
+        let entry = match frame.stack.as_slice() {
+            [] if same_locals && offset_delta < 64 => StackMapFrame::Same {
+                frame_type: offset_delta as u8
+            },
+            [] if same_locals => StackMapFrame::SameExtended {
+                frame_type: 251,
+                offset_delta
+            },
+            [stack] if same_locals && offset_delta < 64 => StackMapFrame::SameLocals1StackItem {
+                frame_type: 64 + offset_delta as u8,
+                stack: *stack
+            },
+            [stack] if same_locals => StackMapFrame::SameLocals1StackItemExtended {
+                frame_type: 247,
+                offset_delta,
+                stack: *stack
+            },
+            [] if frame.locals.len() > locals.len()
+                && frame.locals.len() - locals.len() <= 3
+                && frame.locals.starts_with(locals) =>
+            {
+                StackMapFrame::Append {
+                    frame_type: 251 + (frame.locals.len() - locals.len()) as u8,
+                    offset_delta,
+                    locals: frame.locals[locals.len()..].to_vec()
+                }
+            }
+            [] if frame.locals.len() < locals.len()
+                && locals.len() - frame.locals.len() <= 3
+                && locals.starts_with(&frame.locals) =>
+            {
+                StackMapFrame::Chop {
+                    frame_type: 251 - (locals.len() - frame.locals.len()) as u8,
+                    offset_delta
+                }
+            }
+            _ => StackMapFrame::Full {
+                frame_type: 255,
+                offset_delta,
+                number_of_locals: frame.locals.len() as u16,
+                locals: frame.locals.clone(),
+                number_of_stack_items: frame.stack.len() as u16,
+                stack: frame.stack.clone()
+            }
+        };
+
+        entries.push(entry);
+        locals = &frame.locals;
+        offset = Some(frame.offset);
+    }
+
+    Ok(entries)
+}
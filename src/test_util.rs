@@ -0,0 +1,24 @@
+//! Helpers shared by the tests of all modules
+
+use std::{fs::File, io::Read, path::Path};
+
+use crate::error::ErrorType;
+
+macro_rules! expect_pat {
+    ($expected: pat, $expr: expr, $then: block) => {
+        if let $expected = $expr $then
+        else {
+            panic!("Expected {:?}, found {:?}", stringify!($expected), $expr);
+        }
+    };
+}
+
+pub(crate) use expect_pat;
+
+pub(crate) fn read_class_file(p: &str) -> Result<Vec<u8>, ErrorType> {
+    let mut f = File::open(Path::new(p)).unwrap();
+    let mut buf = Vec::with_capacity(64);
+
+    f.read_to_end(&mut buf).unwrap();
+    Ok(buf)
+}
@@ -0,0 +1,701 @@
+//! A resolved view on top of [`RawClass`]
+//!
+//! The views borrow from the parsed class and resolve names and descriptors against its
+//! constant pool on demand:
+//!
+//! ```no_run
+//! # fn run(buf: &[u8]) -> Result<(), classfmt::error::ErrorType> {
+//! use classfmt::view::Exceptions;
+//! use classfmt::ClassParser;
+//!
+//! let class = ClassParser::from_bytes(buf).parse()?;
+//! let view = class.view();
+//!
+//! for method in view.methods() {
+//!     let throws = method.attribute::<Exceptions>().map_or(0, |e| e.len());
+//!     println!("{}{} throws {} exceptions", method.name()?, method.descriptor()?, throws);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::borrow::Cow;
+
+use crate::access_flags::{ClassAccessFlag, FieldAccessFlag, MethodAccessFlag};
+use crate::annotation::{Annotation, ElementValue, ParameterAnnotations, TypeAnnotation};
+use crate::attribute::{
+    Attribute, BootstrapMethodAttribute, ExceptionTableEntry, InnerClassAttribute,
+    LineNumberTableEntry, LocalVariableTableEntry, LocalVariableTypeTableEntry, ModuleAttribute,
+    ParameterAttribute, RecordComponentInfo
+};
+use crate::bytecode::Bytecode;
+use crate::constant_pool::ConstantPool;
+use crate::error::ErrorType;
+use crate::stack_map::StackMapFrame;
+use crate::{Field, Method, RawClass};
+
+/// A kind of attribute which can be looked up with `attribute::<T>()`
+pub trait AttributeKind<'a, 'c: 'a> {
+    /// The contents of the attribute
+    type View;
+
+    /// Extracts the contents of `attribute` if it is of this kind
+    fn view(attribute: &'a Attribute<'c>) -> Option<Self::View>;
+}
+
+fn find<'a, 'c: 'a, T: AttributeKind<'a, 'c>>(attributes: &'a [Attribute<'c>]) -> Option<T::View> {
+    attributes.iter().find_map(T::view)
+}
+
+/// A class, see [`RawClass::view`]
+#[derive(Debug, Clone, Copy)]
+pub struct ClassView<'a, 'c> {
+    class: &'a RawClass<'c>
+}
+
+impl<'a, 'c> ClassView<'a, 'c> {
+    pub fn new(class: &'a RawClass<'c>) -> ClassView<'a, 'c> {
+        ClassView { class }
+    }
+
+    pub fn raw(&self) -> &'a RawClass<'c> {
+        self.class
+    }
+
+    pub fn constant_pool(&self) -> &'a ConstantPool<'c> {
+        &self.class.constant_pool
+    }
+
+    pub fn access(&self) -> ClassAccessFlag {
+        self.class.access_flags
+    }
+
+    /// The internal name of this class, e.g. `java/lang/String`
+    pub fn name(&self) -> Result<Cow<'c, str>, ErrorType> {
+        self.constant_pool().class_name(self.class.this_class)
+    }
+
+    /// The internal name of the super class, `None` for `java/lang/Object` and modules
+    pub fn super_name(&self) -> Result<Option<Cow<'c, str>>, ErrorType> {
+        match self.class.super_class {
+            0 => Ok(None),
+            index => self.constant_pool().class_name(index).map(Some)
+        }
+    }
+
+    /// The internal names of the interfaces implemented by this class
+    pub fn interfaces(&self) -> Result<Vec<Cow<'c, str>>, ErrorType> {
+        self.class.interface_names()
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = FieldView<'a, 'c>> {
+        let constant_pool = self.constant_pool();
+
+        self.class.fields.iter().map(move |field| FieldView {
+            field,
+            constant_pool
+        })
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = MethodView<'a, 'c>> {
+        let constant_pool = self.constant_pool();
+
+        self.class.methods.iter().map(move |method| MethodView {
+            method,
+            constant_pool
+        })
+    }
+
+    pub fn attributes(&self) -> &'a [Attribute<'c>] {
+        &self.class.attributes
+    }
+
+    /// Looks up the first attribute of kind `T`
+    pub fn attribute<T: AttributeKind<'a, 'c>>(&self) -> Option<T::View> {
+        find::<T>(&self.class.attributes)
+    }
+}
+
+/// A field of a [`ClassView`]
+#[derive(Debug, Clone, Copy)]
+pub struct FieldView<'a, 'c> {
+    field: &'a Field<'c>,
+    constant_pool: &'a ConstantPool<'c>
+}
+
+impl<'a, 'c> FieldView<'a, 'c> {
+    pub fn raw(&self) -> &'a Field<'c> {
+        self.field
+    }
+
+    pub fn access(&self) -> FieldAccessFlag {
+        self.field.access_flags
+    }
+
+    pub fn name(&self) -> Result<Cow<'c, str>, ErrorType> {
+        self.constant_pool.utf8(self.field.name_index)
+    }
+
+    /// The field descriptor, e.g. `[Ljava/lang/String;`
+    pub fn descriptor(&self) -> Result<Cow<'c, str>, ErrorType> {
+        self.constant_pool.utf8(self.field.descriptor_index)
+    }
+
+    pub fn attributes(&self) -> &'a [Attribute<'c>] {
+        &self.field.attributes
+    }
+
+    /// Looks up the first attribute of kind `T`
+    pub fn attribute<T: AttributeKind<'a, 'c>>(&self) -> Option<T::View> {
+        find::<T>(&self.field.attributes)
+    }
+}
+
+/// A method of a [`ClassView`]
+#[derive(Debug, Clone, Copy)]
+pub struct MethodView<'a, 'c> {
+    method: &'a Method<'c>,
+    constant_pool: &'a ConstantPool<'c>
+}
+
+impl<'a, 'c> MethodView<'a, 'c> {
+    pub fn raw(&self) -> &'a Method<'c> {
+        self.method
+    }
+
+    pub fn access(&self) -> MethodAccessFlag {
+        self.method.access_flags
+    }
+
+    pub fn name(&self) -> Result<Cow<'c, str>, ErrorType> {
+        self.constant_pool.utf8(self.method.name_index)
+    }
+
+    /// The method descriptor, e.g. `(I[Ljava/lang/String;)V`
+    pub fn descriptor(&self) -> Result<Cow<'c, str>, ErrorType> {
+        self.constant_pool.utf8(self.method.descriptor_index)
+    }
+
+    pub fn attributes(&self) -> &'a [Attribute<'c>] {
+        &self.method.attributes
+    }
+
+    /// Looks up the first attribute of kind `T`
+    pub fn attribute<T: AttributeKind<'a, 'c>>(&self) -> Option<T::View> {
+        find::<T>(&self.method.attributes)
+    }
+
+    /// The body of this method, `None` for abstract and native methods
+    pub fn code(&self) -> Option<CodeView<'a, 'c>> {
+        self.attribute::<Code>()
+    }
+}
+
+/// The contents of a `Code` attribute
+#[derive(Debug, Clone, Copy)]
+pub struct CodeView<'a, 'c> {
+    attribute: &'a Attribute<'c>,
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub bytecode: &'a Bytecode,
+    pub exception_table: &'a [ExceptionTableEntry],
+    pub attributes: &'a [Attribute<'c>]
+}
+
+impl<'a, 'c> CodeView<'a, 'c> {
+    pub fn raw(&self) -> &'a Attribute<'c> {
+        self.attribute
+    }
+
+    /// Looks up the first attribute of kind `T`
+    pub fn attribute<T: AttributeKind<'a, 'c>>(&self) -> Option<T::View> {
+        find::<T>(self.attributes)
+    }
+
+    /// See [`Attribute::line_number_at`]
+    pub fn line_number_at(&self, pc: u16) -> Option<u16> {
+        self.attribute.line_number_at(pc)
+    }
+
+    /// See [`Attribute::local_variable_at`]
+    pub fn local_variable_at(
+        &self,
+        index: u16,
+        pc: u16,
+        constant_pool: &ConstantPool
+    ) -> Option<&'a LocalVariableTableEntry> {
+        self.attribute.local_variable_at(index, pc, constant_pool)
+    }
+}
+
+// Attribute kinds for use with `attribute::<T>()`
+
+/// `Code`, viewed as [`CodeView`]
+pub enum Code {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for Code {
+    type View = CodeView<'a, 'c>;
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<CodeView<'a, 'c>> {
+        match attribute {
+            Attribute::Code {
+                max_stack,
+                max_locals,
+                code,
+                exception_table,
+                attributes,
+                ..
+            } => Some(CodeView {
+                attribute,
+                max_stack: *max_stack,
+                max_locals: *max_locals,
+                bytecode: code,
+                exception_table,
+                attributes
+            }),
+            _ => None
+        }
+    }
+}
+
+/// `ConstantValue`, viewed as the index of the constant
+pub enum ConstantValue {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for ConstantValue {
+    type View = u16;
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<u16> {
+        match attribute {
+            Attribute::ConstantValue {
+                constantvalue_index,
+                ..
+            } => Some(*constantvalue_index),
+            _ => None
+        }
+    }
+}
+
+/// `SourceFile`, viewed as the index of the file name
+pub enum SourceFile {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for SourceFile {
+    type View = u16;
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<u16> {
+        match attribute {
+            Attribute::SourceFile {
+                sourcefile_index, ..
+            } => Some(*sourcefile_index),
+            _ => None
+        }
+    }
+}
+
+/// `Signature`, viewed as the index of the signature
+pub enum Signature {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for Signature {
+    type View = u16;
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<u16> {
+        match attribute {
+            Attribute::Signature {
+                signature_index, ..
+            } => Some(*signature_index),
+            _ => None
+        }
+    }
+}
+
+/// `Exceptions`, viewed as the indices of the thrown classes
+pub enum Exceptions {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for Exceptions {
+    type View = &'a [u16];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [u16]> {
+        match attribute {
+            Attribute::Exceptions {
+                exception_index_table,
+                ..
+            } => Some(exception_index_table),
+            _ => None
+        }
+    }
+}
+
+/// `EnclosingMethod`, viewed as the indices of the class and method
+pub enum EnclosingMethod {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for EnclosingMethod {
+    type View = (u16, u16);
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<(u16, u16)> {
+        match attribute {
+            Attribute::EnclosingMethod {
+                class_index,
+                method_index,
+                ..
+            } => Some((*class_index, *method_index)),
+            _ => None
+        }
+    }
+}
+
+/// `Deprecated`
+pub enum Deprecated {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for Deprecated {
+    type View = ();
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<()> {
+        match attribute {
+            Attribute::Deprecated { .. } => Some(()),
+            _ => None
+        }
+    }
+}
+
+/// `Synthetic`
+pub enum Synthetic {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for Synthetic {
+    type View = ();
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<()> {
+        match attribute {
+            Attribute::Synthetic { .. } => Some(()),
+            _ => None
+        }
+    }
+}
+
+/// `InnerClasses`
+pub enum InnerClasses {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for InnerClasses {
+    type View = &'a [InnerClassAttribute];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [InnerClassAttribute]> {
+        match attribute {
+            Attribute::InnerClasses { classes, .. } => Some(classes),
+            _ => None
+        }
+    }
+}
+
+/// `LineNumberTable`
+pub enum LineNumberTable {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for LineNumberTable {
+    type View = &'a [LineNumberTableEntry];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [LineNumberTableEntry]> {
+        match attribute {
+            Attribute::LineNumberTable {
+                line_number_table, ..
+            } => Some(line_number_table),
+            _ => None
+        }
+    }
+}
+
+/// `LocalVariableTable`
+pub enum LocalVariableTable {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for LocalVariableTable {
+    type View = &'a [LocalVariableTableEntry];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [LocalVariableTableEntry]> {
+        match attribute {
+            Attribute::LocalVariableTable {
+                local_variable_table,
+                ..
+            } => Some(local_variable_table),
+            _ => None
+        }
+    }
+}
+
+/// `LocalVariableTypeTable`
+pub enum LocalVariableTypeTable {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for LocalVariableTypeTable {
+    type View = &'a [LocalVariableTypeTableEntry];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [LocalVariableTypeTableEntry]> {
+        match attribute {
+            Attribute::LocalVariableTypeTable {
+                local_variable_type_table,
+                ..
+            } => Some(local_variable_type_table),
+            _ => None
+        }
+    }
+}
+
+/// `StackMapTable`
+pub enum StackMapTable {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for StackMapTable {
+    type View = &'a [StackMapFrame];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [StackMapFrame]> {
+        match attribute {
+            Attribute::StackMapTable { entries, .. } => Some(entries),
+            _ => None
+        }
+    }
+}
+
+/// `BootstrapMethods`
+pub enum BootstrapMethods {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for BootstrapMethods {
+    type View = &'a [BootstrapMethodAttribute];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [BootstrapMethodAttribute]> {
+        match attribute {
+            Attribute::BootstrapMethods {
+                bootstrap_methods, ..
+            } => Some(bootstrap_methods),
+            _ => None
+        }
+    }
+}
+
+/// `MethodParameters`
+pub enum MethodParameters {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for MethodParameters {
+    type View = &'a [ParameterAttribute];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [ParameterAttribute]> {
+        match attribute {
+            Attribute::MethodParameters { parameters, .. } => Some(parameters),
+            _ => None
+        }
+    }
+}
+
+/// `RuntimeVisibleAnnotations`
+pub enum RuntimeVisibleAnnotations {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for RuntimeVisibleAnnotations {
+    type View = &'a [Annotation];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [Annotation]> {
+        match attribute {
+            Attribute::RuntimeVisibleAnnotations { annotations, .. } => Some(annotations),
+            _ => None
+        }
+    }
+}
+
+/// `RuntimeInvisibleAnnotations`
+pub enum RuntimeInvisibleAnnotations {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for RuntimeInvisibleAnnotations {
+    type View = &'a [Annotation];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [Annotation]> {
+        match attribute {
+            Attribute::RuntimeInvisibleAnnotations { annotations, .. } => Some(annotations),
+            _ => None
+        }
+    }
+}
+
+/// `RuntimeVisibleParameterAnnotations`
+pub enum RuntimeVisibleParameterAnnotations {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for RuntimeVisibleParameterAnnotations {
+    type View = &'a [ParameterAnnotations];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [ParameterAnnotations]> {
+        match attribute {
+            Attribute::RuntimeVisibleParameterAnnotations {
+                parameter_annotations,
+                ..
+            } => Some(parameter_annotations),
+            _ => None
+        }
+    }
+}
+
+/// `RuntimeInvisibleParameterAnnotations`
+pub enum RuntimeInvisibleParameterAnnotations {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for RuntimeInvisibleParameterAnnotations {
+    type View = &'a [ParameterAnnotations];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [ParameterAnnotations]> {
+        match attribute {
+            Attribute::RuntimeInvisibleParameterAnnotations {
+                parameter_annotations,
+                ..
+            } => Some(parameter_annotations),
+            _ => None
+        }
+    }
+}
+
+/// `RuntimeVisibleTypeAnnotations`
+pub enum RuntimeVisibleTypeAnnotations {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for RuntimeVisibleTypeAnnotations {
+    type View = &'a [TypeAnnotation];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [TypeAnnotation]> {
+        match attribute {
+            Attribute::RuntimeVisibleTypeAnnotations { annotations, .. } => Some(annotations),
+            _ => None
+        }
+    }
+}
+
+/// `RuntimeInvisibleTypeAnnotations`
+pub enum RuntimeInvisibleTypeAnnotations {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for RuntimeInvisibleTypeAnnotations {
+    type View = &'a [TypeAnnotation];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [TypeAnnotation]> {
+        match attribute {
+            Attribute::RuntimeInvisibleTypeAnnotations { annotations, .. } => Some(annotations),
+            _ => None
+        }
+    }
+}
+
+/// `AnnotationDefault`
+pub enum AnnotationDefault {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for AnnotationDefault {
+    type View = &'a ElementValue;
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a ElementValue> {
+        match attribute {
+            Attribute::AnnotationDefault { default_value, .. } => Some(default_value),
+            _ => None
+        }
+    }
+}
+
+/// `NestHost`, viewed as the index of the host class
+pub enum NestHost {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for NestHost {
+    type View = u16;
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<u16> {
+        match attribute {
+            Attribute::NestHost {
+                host_class_index, ..
+            } => Some(*host_class_index),
+            _ => None
+        }
+    }
+}
+
+/// `NestMembers`, viewed as the indices of the member classes
+pub enum NestMembers {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for NestMembers {
+    type View = &'a [u16];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [u16]> {
+        match attribute {
+            Attribute::NestMembers { classes, .. } => Some(classes),
+            _ => None
+        }
+    }
+}
+
+/// `PermittedSubclasses`, viewed as the indices of the permitted classes
+pub enum PermittedSubclasses {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for PermittedSubclasses {
+    type View = &'a [u16];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [u16]> {
+        match attribute {
+            Attribute::PermittedSubclasses { classes, .. } => Some(classes),
+            _ => None
+        }
+    }
+}
+
+/// `Record`
+pub enum Record {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for Record {
+    type View = &'a [RecordComponentInfo<'c>];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [RecordComponentInfo<'c>]> {
+        match attribute {
+            Attribute::Record { components, .. } => Some(components),
+            _ => None
+        }
+    }
+}
+
+/// `Module`
+pub enum Module {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for Module {
+    type View = &'a ModuleAttribute;
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a ModuleAttribute> {
+        match attribute {
+            Attribute::Module { module, .. } => Some(module),
+            _ => None
+        }
+    }
+}
+
+/// `ModulePackages`, viewed as the indices of the packages
+pub enum ModulePackages {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for ModulePackages {
+    type View = &'a [u16];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'a [u16]> {
+        match attribute {
+            Attribute::ModulePackages { package_index, .. } => Some(package_index),
+            _ => None
+        }
+    }
+}
+
+/// `ModuleMainClass`, viewed as the index of the main class
+pub enum ModuleMainClass {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for ModuleMainClass {
+    type View = u16;
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<u16> {
+        match attribute {
+            Attribute::ModuleMainClass {
+                main_class_index, ..
+            } => Some(*main_class_index),
+            _ => None
+        }
+    }
+}
+
+/// `SourceDebugExtension`, viewed as the raw modified UTF-8 contents
+pub enum SourceDebugExtension {}
+
+impl<'a, 'c: 'a> AttributeKind<'a, 'c> for SourceDebugExtension {
+    type View = &'c [u8];
+
+    fn view(attribute: &'a Attribute<'c>) -> Option<&'c [u8]> {
+        match attribute {
+            Attribute::SourceDebugExtension {
+                debug_extension, ..
+            } => Some(debug_extension),
+            _ => None
+        }
+    }
+}
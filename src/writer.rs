@@ -0,0 +1,1107 @@
+use std::convert::TryFrom;
+
+use crate::annotation::{Annotation, ElementValue, ElementValuePair, TargetInfo, TypeAnnotation};
+use crate::attribute::ModuleAttribute;
+use crate::bytecode::Bytecode;
+use crate::constant_pool::ConstantPool;
+use crate::error::{ErrorKind, ErrorType};
+use crate::opcode::{Instruction, Opcode};
+use crate::stack_map::{StackMapFrame, VerificationTypeInfo};
+use crate::{Attribute, Constant, Field, Method, RawClass};
+
+/// The class writer. Serializes a [`RawClass`] into the class file format.<br>
+/// All counts, lengths and `attribute_length` values are computed from the contents being
+/// written, the corresponding fields of the structures are ignored. Writing an unmodified class
+/// produces the bytes it was parsed from, provided `tableswitch` and `lookupswitch` padding
+/// consisted of zeros
+#[derive(Default)]
+pub struct ClassWriter {
+    bytes: Vec<u8>
+}
+
+impl ClassWriter {
+    /// Creates a new writer
+    pub fn new() -> ClassWriter {
+        ClassWriter::default()
+    }
+
+    /// Serializes `class`, returning the bytes of the class file
+    pub fn write(mut self, class: &RawClass) -> Result<Vec<u8>, ErrorType> {
+        self.write_u32_be(class.magic);
+        self.write_u16_be(class.minor_version);
+        self.write_u16_be(class.major_version);
+        self.write_constant_pool(&class.constant_pool)?;
+        self.write_u16_be(class.access_flags.bits() | class.undefined_access_flags);
+        self.write_u16_be(class.this_class);
+        self.write_u16_be(class.super_class);
+        self.write_indices(&class.interfaces)
+            .map_err(|e| e.within("interfaces"))?;
+        self.write_fields(&class.fields)?;
+        self.write_methods(&class.methods)?;
+        self.write_attributes(&class.attributes)
+            .map_err(|e| e.within("class"))?;
+
+        Ok(self.bytes)
+    }
+
+    fn write_u8(&mut self, v: u8) {
+        self.bytes.push(v);
+    }
+
+    fn write_u16_be(&mut self, v: u16) {
+        self.bytes.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn write_u32_be(&mut self, v: u32) {
+        self.bytes.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn write_i16_be(&mut self, v: i16) {
+        self.write_u16_be(v as u16);
+    }
+
+    fn write_i32_be(&mut self, v: i32) {
+        self.write_u32_be(v as u32);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Writes the number of entries of a table with a two byte length
+    fn write_count(&mut self, count: usize) -> Result<(), ErrorType> {
+        let count = u16::try_from(count)
+            .map_err(|_| ErrorType::from(ErrorKind::Malformed("table exceeds 65535 entries")))?;
+
+        self.write_u16_be(count);
+        Ok(())
+    }
+
+    /// Writes the number of entries of a table with a one byte length
+    fn write_u8_count(&mut self, count: usize) -> Result<(), ErrorType> {
+        let count = u8::try_from(count)
+            .map_err(|_| ErrorType::from(ErrorKind::Malformed("table exceeds 255 entries")))?;
+
+        self.write_u8(count);
+        Ok(())
+    }
+
+    /// Writes a table of constant pool indices preceded by its length
+    fn write_indices(&mut self, indices: &[u16]) -> Result<(), ErrorType> {
+        self.write_count(indices.len())?;
+
+        for &index in indices {
+            self.write_u16_be(index);
+        }
+
+        Ok(())
+    }
+
+    /// Reserves four bytes for a length, returning the offset to pass to
+    /// [`ClassWriter::patch_length`]
+    fn reserve_length(&mut self) -> usize {
+        self.write_u32_be(0);
+        self.bytes.len()
+    }
+
+    /// Fills in the length reserved at `start` with the number of bytes written since
+    fn patch_length(&mut self, start: usize) -> Result<u32, ErrorType> {
+        let length = u32::try_from(self.bytes.len() - start)
+            .map_err(|_| ErrorType::from(ErrorKind::Malformed("attribute exceeds 4GB")))?;
+
+        self.bytes[start - 4..start].copy_from_slice(&length.to_be_bytes());
+        Ok(length)
+    }
+
+    fn write_constant_pool(&mut self, constant_pool: &ConstantPool) -> Result<(), ErrorType> {
+        self.write_count(constant_pool.len() + 1)
+            .map_err(|e| e.within("constant pool"))?;
+
+        for (i, constant) in constant_pool.iter() {
+            self.write_constant(constant)
+                .map_err(|e| e.within(format!("constant pool entry #{}", i)))?;
+        }
+
+        Ok(())
+    }
+
+    fn write_constant(&mut self, constant: &Constant) -> Result<(), ErrorType> {
+        match constant {
+            Constant::Class { tag, name_index }
+            | Constant::Module { tag, name_index }
+            | Constant::Package { tag, name_index } => {
+                self.write_u8(*tag as u8);
+                self.write_u16_be(*name_index);
+            }
+            Constant::Fieldref {
+                tag,
+                class_index,
+                name_and_type_index
+            }
+            | Constant::Methodref {
+                tag,
+                class_index,
+                name_and_type_index
+            }
+            | Constant::InterfaceMethodref {
+                tag,
+                class_index,
+                name_and_type_index
+            } => {
+                self.write_u8(*tag as u8);
+                self.write_u16_be(*class_index);
+                self.write_u16_be(*name_and_type_index);
+            }
+            Constant::String { tag, string_index } => {
+                self.write_u8(*tag as u8);
+                self.write_u16_be(*string_index);
+            }
+            Constant::Integer { tag, value } => {
+                self.write_u8(*tag as u8);
+                self.write_i32_be(*value);
+            }
+            Constant::Float { tag, bytes } => {
+                self.write_u8(*tag as u8);
+                self.write_bytes(bytes);
+            }
+            Constant::Long {
+                tag,
+                high_bytes,
+                low_bytes
+            }
+            | Constant::Double {
+                tag,
+                high_bytes,
+                low_bytes
+            } => {
+                self.write_u8(*tag as u8);
+                self.write_u32_be(*high_bytes);
+                self.write_u32_be(*low_bytes);
+            }
+            Constant::NameAndType {
+                tag,
+                name_index,
+                descriptor_index
+            } => {
+                self.write_u8(*tag as u8);
+                self.write_u16_be(*name_index);
+                self.write_u16_be(*descriptor_index);
+            }
+            Constant::Utf8 { tag, bytes, .. } => {
+                self.write_u8(*tag as u8);
+                self.write_count(bytes.len()).map_err(|_| {
+                    ErrorType::from(ErrorKind::Malformed("Utf8 constant exceeds 65535 bytes"))
+                })?;
+                self.write_bytes(bytes);
+            }
+            Constant::MethodHandle {
+                tag,
+                reference_kind,
+                reference_index
+            } => {
+                self.write_u8(*tag as u8);
+                self.write_u8(*reference_kind as u8);
+                self.write_u16_be(*reference_index);
+            }
+            Constant::MethodType {
+                tag,
+                descriptor_index
+            } => {
+                self.write_u8(*tag as u8);
+                self.write_u16_be(*descriptor_index);
+            }
+            Constant::Dynamic {
+                tag,
+                bootstrap_method_attr_index,
+                name_and_type_index
+            } => {
+                self.write_u8(*tag as u8);
+                self.write_u16_be(*bootstrap_method_attr_index);
+                self.write_u16_be(*name_and_type_index);
+            }
+            Constant::InvokeDynamic {
+                tag,
+                bootstrap_method_attr_index,
+                name_index
+            } => {
+                self.write_u8(*tag as u8);
+                self.write_u16_be(*bootstrap_method_attr_index);
+                self.write_u16_be(*name_index);
+            }
+            Constant::Unusable => {}
+        }
+
+        Ok(())
+    }
+
+    fn write_fields(&mut self, fields: &[Field]) -> Result<(), ErrorType> {
+        self.write_count(fields.len())
+            .map_err(|e| e.within("fields"))?;
+
+        for (i, field) in fields.iter().enumerate() {
+            self.write_u16_be(field.access_flags.bits() | field.undefined_access_flags);
+            self.write_u16_be(field.name_index);
+            self.write_u16_be(field.descriptor_index);
+            self.write_attributes(&field.attributes)
+                .map_err(|e| e.within(format!("field #{}", i)))?;
+        }
+
+        Ok(())
+    }
+
+    fn write_methods(&mut self, methods: &[Method]) -> Result<(), ErrorType> {
+        self.write_count(methods.len())
+            .map_err(|e| e.within("methods"))?;
+
+        for (i, method) in methods.iter().enumerate() {
+            self.write_u16_be(method.access_flags.bits() | method.undefined_access_flags);
+            self.write_u16_be(method.name_index);
+            self.write_u16_be(method.descriptor_index);
+            self.write_attributes(&method.attributes)
+                .map_err(|e| e.within(format!("method #{}", i)))?;
+        }
+
+        Ok(())
+    }
+
+    fn write_attributes(&mut self, attributes: &[Attribute]) -> Result<(), ErrorType> {
+        self.write_count(attributes.len())?;
+
+        for (i, attribute) in attributes.iter().enumerate() {
+            self.write_attribute(attribute)
+                .map_err(|e| e.within(format!("attribute #{}", i)))?;
+        }
+
+        Ok(())
+    }
+
+    fn write_attribute(&mut self, attribute: &Attribute) -> Result<(), ErrorType> {
+        let attribute_name_index = match attribute {
+            Attribute::ConstantValue {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::Code {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::InnerClasses {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::SourceFile {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::Signature {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::Exceptions {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::EnclosingMethod {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::Synthetic {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::Deprecated {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::LineNumberTable {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::SourceDebugExtension {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::LocalVariableTable {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::LocalVariableTypeTable {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::BootstrapMethods {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::MethodParameters {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::NestMembers {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::NestHost {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::Record {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::PermittedSubclasses {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::StackMapTable {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::RuntimeVisibleAnnotations {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::RuntimeInvisibleAnnotations {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::RuntimeVisibleParameterAnnotations {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::RuntimeInvisibleParameterAnnotations {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::RuntimeVisibleTypeAnnotations {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::RuntimeInvisibleTypeAnnotations {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::AnnotationDefault {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::Module {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::ModulePackages {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::ModuleMainClass {
+                attribute_name_index,
+                ..
+            }
+            | Attribute::Unknown {
+                attribute_name_index,
+                ..
+            } => *attribute_name_index
+        };
+
+        self.write_u16_be(attribute_name_index);
+        let start = self.reserve_length();
+
+        match attribute {
+            Attribute::ConstantValue {
+                constantvalue_index,
+                ..
+            } => self.write_u16_be(*constantvalue_index),
+            Attribute::Code {
+                max_stack,
+                max_locals,
+                code,
+                exception_table,
+                attributes,
+                ..
+            } => {
+                self.write_u16_be(*max_stack);
+                self.write_u16_be(*max_locals);
+                self.write_code(code)?;
+                self.write_count(exception_table.len())?;
+
+                for entry in exception_table {
+                    self.write_u16_be(entry.start_pc);
+                    self.write_u16_be(entry.end_pc);
+                    self.write_u16_be(entry.handler_pc);
+                    self.write_u16_be(entry.catch_type);
+                }
+
+                self.write_attributes(attributes)?;
+            }
+            Attribute::InnerClasses { classes, .. } => {
+                self.write_count(classes.len())?;
+
+                for class in classes {
+                    self.write_u16_be(class.inner_class_info_index);
+                    self.write_u16_be(class.outer_class_info_index);
+                    self.write_u16_be(class.inner_name_index);
+                    self.write_u16_be(
+                        class.inner_class_access_flags.bits()
+                            | class.undefined_inner_class_access_flags
+                    );
+                }
+            }
+            Attribute::SourceFile {
+                sourcefile_index, ..
+            } => self.write_u16_be(*sourcefile_index),
+            Attribute::Signature {
+                signature_index, ..
+            } => self.write_u16_be(*signature_index),
+            Attribute::Exceptions {
+                exception_index_table,
+                ..
+            } => self.write_indices(exception_index_table)?,
+            Attribute::EnclosingMethod {
+                class_index,
+                method_index,
+                ..
+            } => {
+                self.write_u16_be(*class_index);
+                self.write_u16_be(*method_index);
+            }
+            Attribute::Synthetic { .. } | Attribute::Deprecated { .. } => {}
+            Attribute::LineNumberTable {
+                line_number_table, ..
+            } => {
+                self.write_count(line_number_table.len())?;
+
+                for entry in line_number_table {
+                    self.write_u16_be(entry.start_pc);
+                    self.write_u16_be(entry.line_number);
+                }
+            }
+            Attribute::SourceDebugExtension {
+                debug_extension, ..
+            } => self.write_bytes(debug_extension),
+            Attribute::LocalVariableTable {
+                local_variable_table,
+                ..
+            } => {
+                self.write_count(local_variable_table.len())?;
+
+                for entry in local_variable_table {
+                    self.write_u16_be(entry.start_pc);
+                    self.write_u16_be(entry.length);
+                    self.write_u16_be(entry.name_index);
+                    self.write_u16_be(entry.descriptor_index);
+                    self.write_u16_be(entry.index);
+                }
+            }
+            Attribute::LocalVariableTypeTable {
+                local_variable_type_table,
+                ..
+            } => {
+                self.write_count(local_variable_type_table.len())?;
+
+                for entry in local_variable_type_table {
+                    self.write_u16_be(entry.start_pc);
+                    self.write_u16_be(entry.length);
+                    self.write_u16_be(entry.name_index);
+                    self.write_u16_be(entry.signature_index);
+                    self.write_u16_be(entry.index);
+                }
+            }
+            Attribute::BootstrapMethods {
+                bootstrap_methods, ..
+            } => {
+                self.write_count(bootstrap_methods.len())?;
+
+                for method in bootstrap_methods {
+                    self.write_u16_be(method.bootstrap_method_ref);
+                    self.write_indices(&method.bootstrap_arguments)?;
+                }
+            }
+            Attribute::MethodParameters { parameters, .. } => {
+                self.write_u8_count(parameters.len())?;
+
+                for parameter in parameters {
+                    self.write_u16_be(parameter.name_index);
+                    self.write_u16_be(
+                        parameter.access_flags.bits() | parameter.undefined_access_flags
+                    );
+                }
+            }
+            Attribute::NestMembers { classes, .. }
+            | Attribute::PermittedSubclasses { classes, .. } => self.write_indices(classes)?,
+            Attribute::NestHost {
+                host_class_index, ..
+            } => self.write_u16_be(*host_class_index),
+            Attribute::Record { components, .. } => {
+                self.write_count(components.len())?;
+
+                for (i, component) in components.iter().enumerate() {
+                    self.write_u16_be(component.name_index);
+                    self.write_u16_be(component.descriptor_index);
+                    self.write_attributes(&component.attributes)
+                        .map_err(|e| e.within(format!("record component #{}", i)))?;
+                }
+            }
+            Attribute::StackMapTable { entries, .. } => self.write_stack_map_frames(entries)?,
+            Attribute::RuntimeVisibleAnnotations { annotations, .. }
+            | Attribute::RuntimeInvisibleAnnotations { annotations, .. } => {
+                self.write_annotations(annotations)?
+            }
+            Attribute::RuntimeVisibleParameterAnnotations {
+                parameter_annotations,
+                ..
+            }
+            | Attribute::RuntimeInvisibleParameterAnnotations {
+                parameter_annotations,
+                ..
+            } => {
+                self.write_u8_count(parameter_annotations.len())?;
+
+                for parameter in parameter_annotations {
+                    self.write_annotations(&parameter.annotations)?;
+                }
+            }
+            Attribute::RuntimeVisibleTypeAnnotations { annotations, .. }
+            | Attribute::RuntimeInvisibleTypeAnnotations { annotations, .. } => {
+                self.write_count(annotations.len())?;
+
+                for annotation in annotations {
+                    self.write_type_annotation(annotation)?;
+                }
+            }
+            Attribute::AnnotationDefault { default_value, .. } => {
+                self.write_element_value(default_value)?
+            }
+            Attribute::Module { module, .. } => self.write_module_attribute(module)?,
+            Attribute::ModulePackages { package_index, .. } => self.write_indices(package_index)?,
+            Attribute::ModuleMainClass {
+                main_class_index, ..
+            } => self.write_u16_be(*main_class_index),
+            Attribute::Unknown { info, .. } => self.write_bytes(info)
+        }
+
+        self.patch_length(start)?;
+        Ok(())
+    }
+
+    fn write_module_attribute(&mut self, module: &ModuleAttribute) -> Result<(), ErrorType> {
+        self.write_u16_be(module.module_name_index);
+        self.write_u16_be(module.module_flags.bits() | module.undefined_module_flags);
+        self.write_u16_be(module.module_version_index);
+
+        self.write_count(module.requires.len())?;
+
+        for requires in &module.requires {
+            self.write_u16_be(requires.requires_index);
+            self.write_u16_be(requires.requires_flags.bits() | requires.undefined_requires_flags);
+            self.write_u16_be(requires.requires_version_index);
+        }
+
+        self.write_count(module.exports.len())?;
+
+        for exports in &module.exports {
+            self.write_u16_be(exports.exports_index);
+            self.write_u16_be(exports.exports_flags.bits() | exports.undefined_exports_flags);
+            self.write_indices(&exports.exports_to_index)?;
+        }
+
+        self.write_count(module.opens.len())?;
+
+        for opens in &module.opens {
+            self.write_u16_be(opens.opens_index);
+            self.write_u16_be(opens.opens_flags.bits() | opens.undefined_opens_flags);
+            self.write_indices(&opens.opens_to_index)?;
+        }
+
+        self.write_indices(&module.uses_index)?;
+        self.write_count(module.provides.len())?;
+
+        for provides in &module.provides {
+            self.write_u16_be(provides.provides_index);
+            self.write_indices(&provides.provides_with_index)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_stack_map_frames(&mut self, entries: &[StackMapFrame]) -> Result<(), ErrorType> {
+        self.write_count(entries.len())?;
+
+        for entry in entries {
+            match entry {
+                StackMapFrame::Same { frame_type } => self.write_u8(*frame_type),
+                StackMapFrame::SameLocals1StackItem { frame_type, stack } => {
+                    self.write_u8(*frame_type);
+                    self.write_verification_type_info(stack);
+                }
+                StackMapFrame::SameLocals1StackItemExtended {
+                    frame_type,
+                    offset_delta,
+                    stack
+                } => {
+                    self.write_u8(*frame_type);
+                    self.write_u16_be(*offset_delta);
+                    self.write_verification_type_info(stack);
+                }
+                StackMapFrame::Chop {
+                    frame_type,
+                    offset_delta
+                }
+                | StackMapFrame::SameExtended {
+                    frame_type,
+                    offset_delta
+                } => {
+                    self.write_u8(*frame_type);
+                    self.write_u16_be(*offset_delta);
+                }
+                StackMapFrame::Append {
+                    offset_delta,
+                    locals,
+                    ..
+                } => {
+                    // The frame type encodes the number of appended locals
+                    if locals.is_empty() || locals.len() > 3 {
+                        return Err(ErrorKind::Malformed(
+                            "append frame must add between one and three locals"
+                        )
+                        .into());
+                    }
+
+                    self.write_u8(251 + locals.len() as u8);
+                    self.write_u16_be(*offset_delta);
+
+                    for local in locals {
+                        self.write_verification_type_info(local);
+                    }
+                }
+                StackMapFrame::Full {
+                    frame_type,
+                    offset_delta,
+                    locals,
+                    stack,
+                    ..
+                } => {
+                    self.write_u8(*frame_type);
+                    self.write_u16_be(*offset_delta);
+                    self.write_verification_type_infos(locals)?;
+                    self.write_verification_type_infos(stack)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_verification_type_infos(
+        &mut self,
+        types: &[VerificationTypeInfo]
+    ) -> Result<(), ErrorType> {
+        self.write_count(types.len())?;
+
+        for info in types {
+            self.write_verification_type_info(info);
+        }
+
+        Ok(())
+    }
+
+    fn write_verification_type_info(&mut self, info: &VerificationTypeInfo) {
+        match info {
+            VerificationTypeInfo::Top => self.write_u8(0),
+            VerificationTypeInfo::Integer => self.write_u8(1),
+            VerificationTypeInfo::Float => self.write_u8(2),
+            VerificationTypeInfo::Double => self.write_u8(3),
+            VerificationTypeInfo::Long => self.write_u8(4),
+            VerificationTypeInfo::Null => self.write_u8(5),
+            VerificationTypeInfo::UninitializedThis => self.write_u8(6),
+            VerificationTypeInfo::Object { cpool_index } => {
+                self.write_u8(7);
+                self.write_u16_be(*cpool_index);
+            }
+            VerificationTypeInfo::Uninitialized { offset } => {
+                self.write_u8(8);
+                self.write_u16_be(*offset);
+            }
+        }
+    }
+
+    fn write_annotations(&mut self, annotations: &[Annotation]) -> Result<(), ErrorType> {
+        self.write_count(annotations.len())?;
+
+        for annotation in annotations {
+            self.write_annotation(annotation)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_annotation(&mut self, annotation: &Annotation) -> Result<(), ErrorType> {
+        self.write_u16_be(annotation.type_index);
+        self.write_element_value_pairs(&annotation.element_value_pairs)
+    }
+
+    fn write_element_value_pairs(&mut self, pairs: &[ElementValuePair]) -> Result<(), ErrorType> {
+        self.write_count(pairs.len())?;
+
+        for pair in pairs {
+            self.write_u16_be(pair.element_name_index);
+            self.write_element_value(&pair.value)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_element_value(&mut self, value: &ElementValue) -> Result<(), ErrorType> {
+        match value {
+            ElementValue::Const {
+                tag,
+                const_value_index
+            } => {
+                self.write_u8(*tag);
+                self.write_u16_be(*const_value_index);
+            }
+            ElementValue::Enum {
+                type_name_index,
+                const_name_index
+            } => {
+                self.write_u8(b'e');
+                self.write_u16_be(*type_name_index);
+                self.write_u16_be(*const_name_index);
+            }
+            ElementValue::Class { class_info_index } => {
+                self.write_u8(b'c');
+                self.write_u16_be(*class_info_index);
+            }
+            ElementValue::Annotation { annotation_value } => {
+                self.write_u8(b'@');
+                self.write_annotation(annotation_value)?;
+            }
+            ElementValue::Array { values, .. } => {
+                self.write_u8(b'[');
+                self.write_count(values.len())?;
+
+                for value in values {
+                    self.write_element_value(value)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_type_annotation(&mut self, annotation: &TypeAnnotation) -> Result<(), ErrorType> {
+        self.write_u8(annotation.target_type);
+
+        match &annotation.target_info {
+            TargetInfo::TypeParameter {
+                type_parameter_index
+            } => self.write_u8(*type_parameter_index),
+            TargetInfo::Supertype { supertype_index } => self.write_u16_be(*supertype_index),
+            TargetInfo::TypeParameterBound {
+                type_parameter_index,
+                bound_index
+            } => {
+                self.write_u8(*type_parameter_index);
+                self.write_u8(*bound_index);
+            }
+            TargetInfo::Empty => {}
+            TargetInfo::FormalParameter {
+                formal_parameter_index
+            } => self.write_u8(*formal_parameter_index),
+            TargetInfo::Throws { throws_type_index } => self.write_u16_be(*throws_type_index),
+            TargetInfo::Localvar { table, .. } => {
+                self.write_count(table.len())?;
+
+                for entry in table {
+                    self.write_u16_be(entry.start_pc);
+                    self.write_u16_be(entry.length);
+                    self.write_u16_be(entry.index);
+                }
+            }
+            TargetInfo::Catch {
+                exception_table_index
+            } => self.write_u16_be(*exception_table_index),
+            TargetInfo::Offset { offset } => self.write_u16_be(*offset),
+            TargetInfo::TypeArgument {
+                offset,
+                type_argument_index
+            } => {
+                self.write_u16_be(*offset);
+                self.write_u8(*type_argument_index);
+            }
+        }
+
+        self.write_u8_count(annotation.target_path.path.len())?;
+
+        for entry in &annotation.target_path.path {
+            self.write_u8(entry.type_path_kind);
+            self.write_u8(entry.type_argument_index);
+        }
+
+        self.write_u16_be(annotation.type_index);
+        self.write_element_value_pairs(&annotation.element_value_pairs)
+    }
+
+    /// Writes `code_length` followed by the encoded instructions
+    fn write_code(&mut self, code: &Bytecode) -> Result<(), ErrorType> {
+        let start = self.reserve_length();
+
+        for (i, instruction) in code.instructions().iter().enumerate() {
+            let pc = self.bytes.len() - start;
+            self.write_instruction(instruction, start)
+                .map_err(|e| e.within(format!("instruction #{} at pc {}", i, pc)))?;
+        }
+
+        self.patch_length(start)?;
+        Ok(())
+    }
+
+    /// Writes a single instruction. `start` is the offset of the first instruction of the method,
+    /// which `tableswitch` and `lookupswitch` padding is relative to
+    fn write_instruction(
+        &mut self,
+        instruction: &Instruction,
+        start: usize
+    ) -> Result<(), ErrorType> {
+        self.write_u8(instruction.opcode() as u8);
+
+        match instruction {
+            Instruction::aload { index }
+            | Instruction::astore { index }
+            | Instruction::dload { index }
+            | Instruction::dstore { index }
+            | Instruction::fload { index }
+            | Instruction::fstore { index }
+            | Instruction::iload { index }
+            | Instruction::istore { index }
+            | Instruction::lload { index }
+            | Instruction::lstore { index }
+            | Instruction::ret { index } => self.write_u8(*index),
+            Instruction::ldc { cp_index } => self.write_u8(*cp_index),
+            Instruction::anewarray { cp_index }
+            | Instruction::checkcast { cp_index }
+            | Instruction::getfield { cp_index }
+            | Instruction::getstatic { cp_index }
+            | Instruction::instanceof { cp_index }
+            | Instruction::invokespecial { cp_index }
+            | Instruction::invokestatic { cp_index }
+            | Instruction::invokevirtual { cp_index }
+            | Instruction::ldc_w { cp_index }
+            | Instruction::ldc2_w { cp_index }
+            | Instruction::new { cp_index }
+            | Instruction::putfield { cp_index }
+            | Instruction::putstatic { cp_index } => self.write_u16_be(*cp_index),
+            Instruction::invokedynamic { cp_index } => {
+                self.write_u16_be(*cp_index);
+                self.write_u16_be(0);
+            }
+            Instruction::invokeinterface { cp_index, count } => {
+                self.write_u16_be(*cp_index);
+                self.write_u8(*count);
+                self.write_u8(0);
+            }
+            Instruction::multianewarray {
+                cp_index,
+                dimensions
+            } => {
+                self.write_u16_be(*cp_index);
+                self.write_u8(*dimensions);
+            }
+            Instruction::bipush { value } => self.write_u8(*value as u8),
+            Instruction::sipush { value } => self.write_i16_be(*value),
+            Instruction::newarray { atype } => self.write_u8(*atype),
+            Instruction::iinc { index, r#const } => {
+                self.write_u8(*index);
+                self.write_u8(*r#const as u8);
+            }
+            Instruction::goto_w { offset } | Instruction::jsr_w { offset } => {
+                self.write_i32_be(*offset)
+            }
+            Instruction::tableswitch {
+                default,
+                low,
+                high,
+                jump_offsets
+            } => {
+                if i64::from(*high) - i64::from(*low) + 1 != jump_offsets.len() as i64 {
+                    return Err(ErrorKind::Malformed(
+                        "tableswitch jump offsets do not match low and high"
+                    )
+                    .into());
+                }
+
+                self.write_switch_padding(start);
+                self.write_i32_be(*default);
+                self.write_i32_be(*low);
+                self.write_i32_be(*high);
+
+                for &offset in jump_offsets {
+                    self.write_i32_be(offset);
+                }
+            }
+            Instruction::lookupswitch {
+                default,
+                match_offset_pairs,
+                ..
+            } => {
+                let npairs = i32::try_from(match_offset_pairs.len()).map_err(|_| {
+                    ErrorType::from(ErrorKind::Malformed("lookupswitch has too many pairs"))
+                })?;
+
+                self.write_switch_padding(start);
+                self.write_i32_be(*default);
+                self.write_i32_be(npairs);
+
+                for &(r#match, offset) in match_offset_pairs {
+                    self.write_i32_be(r#match);
+                    self.write_i32_be(offset);
+                }
+            }
+            Instruction::wide { opcode, index } => {
+                self.write_u8(*opcode as u8);
+                self.write_u16_be(*index);
+            }
+            Instruction::wide_iinc { index, r#const } => {
+                self.write_u8(Opcode::iinc as u8);
+                self.write_u16_be(*index);
+                self.write_i16_be(*r#const);
+            }
+            Instruction::goto { offset }
+            | Instruction::if_acmpeq { offset }
+            | Instruction::if_acmpne { offset }
+            | Instruction::if_icmpeq { offset }
+            | Instruction::if_icmpne { offset }
+            | Instruction::if_icmplt { offset }
+            | Instruction::if_icmpge { offset }
+            | Instruction::if_icmpgt { offset }
+            | Instruction::if_icmple { offset }
+            | Instruction::ifeq { offset }
+            | Instruction::ifne { offset }
+            | Instruction::iflt { offset }
+            | Instruction::ifge { offset }
+            | Instruction::ifgt { offset }
+            | Instruction::ifle { offset }
+            | Instruction::ifnonnull { offset }
+            | Instruction::ifnull { offset }
+            | Instruction::jsr { offset } => self.write_i16_be(*offset),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn write_switch_padding(&mut self, start: usize) {
+        let pc = self.bytes.len() - start;
+
+        for _ in 0..(4 - pc % 4) % 4 {
+            self.write_u8(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ClassWriter;
+    use crate::access_flags::ClassAccessFlag;
+    use crate::opcode::Instruction;
+    use crate::test_util::{expect_pat, read_class_file};
+    use crate::{Attribute, ClassParser};
+    use std::fs;
+
+    #[test]
+    fn roundtrip() {
+        let dirs = ["./tests", "./tests/module", "./tests/module/com/example/app"];
+        let mut written = 0;
+
+        for dir in dirs.iter() {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+
+                if path.extension().and_then(|ext| ext.to_str()) != Some("class") {
+                    continue;
+                }
+
+                let buf = read_class_file(path.to_str().unwrap()).unwrap();
+                let class = ClassParser::from_bytes(&buf)
+                    .parse()
+                    .unwrap();
+
+                assert!(class.to_bytes().unwrap() == buf, "{}", path.display());
+                written += 1;
+            }
+        }
+
+        assert!(written > 20);
+    }
+
+    #[test]
+    fn undefined_flags() {
+        let mut buf = read_class_file("./tests/Hello.class").unwrap();
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        // access_flags is directly followed by this_class and super_class
+        let header: Vec<u8> = [class.access_flags.bits(), class.this_class, class.super_class]
+            .iter()
+            .flat_map(|value| value.to_be_bytes().to_vec())
+            .collect();
+        let offset = buf
+            .windows(header.len())
+            .position(|window| window == header.as_slice())
+            .unwrap();
+        buf[offset] |= 0x01;
+
+        let class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+        assert_eq!(class.undefined_access_flags, 0x0100);
+        assert!(class.access_flags.contains(ClassAccessFlag::SUPER));
+        assert!(class.to_bytes().unwrap() == buf);
+    }
+
+    #[test]
+    fn modified() {
+        let buf = read_class_file("./tests/Hello.class").unwrap();
+        let mut class = ClassParser::from_bytes(&buf)
+            .parse()
+            .unwrap();
+
+        // drop the SourceFile attribute and the LineNumberTable of every method, every attribute
+        // takes up 6 bytes for its name and length, followed by its contents
+        let mut removed = 0;
+
+        class.attributes.retain(|attribute| match attribute {
+            Attribute::SourceFile { attribute_length, .. } => {
+                removed += 6 + *attribute_length as usize;
+                false
+            }
+            _ => true
+        });
+
+        for method in class.methods.iter_mut() {
+            for attribute in method.attributes.iter_mut() {
+                if let Attribute::Code { attributes, .. } = attribute {
+                    for attribute in attributes.drain(..) {
+                        expect_pat!(Attribute::LineNumberTable { attribute_length, .. }, &attribute, {
+                            removed += 6 + *attribute_length as usize;
+                        });
+                    }
+                }
+            }
+        }
+
+        let bytes = ClassWriter::new().write(&class).unwrap();
+        assert_eq!(bytes.len(), buf.len() - removed);
+
+        let class = ClassParser::from_bytes(&bytes)
+            .parse()
+            .unwrap();
+        assert_eq!(class.attributes_count, 0);
+
+        let main = class.methods.iter().find(|method| class.constant_pool.utf8(method.name_index).unwrap() == "main").unwrap();
+        expect_pat!(Attribute::Code { attribute_length, code_length, exception_table_length: 0, attributes_count: 0, code, .. }, &main.attributes[0], {
+            assert_eq!(*attribute_length, 2 + 2 + 4 + *code_length + 2 + 2);
+            expect_pat!(Instruction::getstatic { .. }, &code[0], {});
+        });
+    }
+}
@@ -0,0 +1,33 @@
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+
+@Annotations.Path(value = "/items", priority = 2, targets = {ElementType.TYPE, ElementType.METHOD}, type = String.class)
+@Annotations.Marker
+class Annotations {
+
+    @Retention(RetentionPolicy.RUNTIME)
+    @interface Inject {
+    }
+
+    @Retention(RetentionPolicy.RUNTIME)
+    @interface Path {
+        String value() default "/";
+
+        int priority() default 5;
+
+        ElementType[] targets() default {};
+
+        Class<?> type() default Object.class;
+    }
+
+    @interface Marker {
+    }
+
+    @Inject
+    private Object service;
+
+    @Inject
+    void setService(@Marker Object service, @Inject String name) {
+    }
+}
@@ -0,0 +1,10 @@
+class Constants {
+    private static final long LONG = 0x1234567890ABCDEFL;
+    private static final double DOUBLE = 2.5;
+    private static final float FLOAT = 1.5f;
+    private static final String AFTER = "after wide constants";
+
+    public static void main(String[] args) {
+        Runnable r = () -> {};
+    }
+}
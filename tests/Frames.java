@@ -0,0 +1,48 @@
+public class Frames {
+    static class Animal {
+        Animal(int legs) {}
+    }
+
+    static class Dog extends Animal {
+        Dog(int legs) {
+            super(legs);
+        }
+    }
+
+    static class Cat extends Animal {
+        Cat() {
+            super(4);
+        }
+    }
+
+    private final long seed;
+
+    Frames(boolean random) {
+        this(random ? System.nanoTime() : 42L);
+    }
+
+    Frames(long seed) {
+        this.seed = seed;
+    }
+
+    static Animal pick(boolean dog) {
+        Animal animal = dog ? new Dog(dog ? 4 : 3) : new Cat();
+        return animal;
+    }
+
+    static int count(Animal[] animals, long limit) {
+        int dogs = 0;
+
+        for (Animal animal : animals) {
+            try {
+                if (animal instanceof Dog && limit-- > 0) {
+                    dogs++;
+                }
+            } catch (IllegalStateException e) {
+                return -1;
+            }
+        }
+
+        return dogs;
+    }
+}
@@ -0,0 +1,14 @@
+import java.io.IOException;
+import java.util.List;
+import java.util.Map;
+
+public abstract class Generics<K extends Comparable<K>, V> implements Iterable<Map.Entry<K, List<? super V>>> {
+
+    public Map<String, List<? extends Number>> numbers;
+
+    public Generics<K, V>.Node<int[]>[] nodes;
+
+    public abstract <E extends Exception & Runnable, T> List<T>[] convert(T[] values, long count, Map<?, V> map) throws E, IOException;
+
+    public class Node<N> {}
+}
@@ -0,0 +1,11 @@
+@SuppressWarnings("rawtypes")
+class Interfaces implements Runnable, Comparable {
+    private int value;
+
+    public void run() {
+    }
+
+    public int compareTo(Object o) {
+        return 0;
+    }
+}
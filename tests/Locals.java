@@ -0,0 +1,14 @@
+import java.util.ArrayList;
+import java.util.List;
+
+public class Locals {
+
+    public static List<String> repeat(String word, int times) {
+        List<String> words = new ArrayList<>();
+        for (int i = 0; i < times; i++) {
+            words.add(word);
+        }
+        long total = words.size();
+        return total > 0 ? words : null;
+    }
+}
@@ -0,0 +1,71 @@
+import java.util.function.LongSupplier;
+
+public class Maxs {
+    private long total;
+    private static double scale = 1.5;
+
+    public synchronized long add(long a, double b, int c) {
+        total += a * (long) (b * scale) + c;
+        return total;
+    }
+
+    public static int classify(String s, int n) {
+        switch (n) {
+            case 1: return s.length();
+            case 2: return 7;
+            case 3: return -1;
+            default:
+                break;
+        }
+
+        switch (s) {
+            case "alpha": return 10;
+            case "beta": return 20;
+            default: return n > 1000 ? 0 : 1;
+        }
+    }
+
+    public static String guarded(Object o) {
+        try {
+            return String.valueOf(o.hashCode());
+        } catch (NullPointerException | IllegalStateException e) {
+            return "none " + e;
+        } finally {
+            System.out.println(o);
+        }
+    }
+
+    public static long[][] grid(int w, int h) {
+        long[][] grid = new long[w][h];
+        LongSupplier supplier = () -> (long) w * h;
+
+        for (int i = 0; i < w; i++) {
+            for (int j = 0; j < h; j++) {
+                grid[i][j] = supplier.getAsLong() + i - j;
+            }
+        }
+
+        return grid;
+    }
+
+    public static double wide(double d0, double d1, double d2, double d3) {
+        double a = d0, b = d1, c = d2, e = d3, f = a * b, g = c * e, h = f + g, i = h / 2;
+        double j = i, k = j, l = k, m = l, n = m, o = n, p = o, q = p, r = q, s = r;
+        double t = s, u = t, v = u, w = v, x = w, y = x, z = y;
+        double a1 = z, a2 = a1, a3 = a2, a4 = a3, a5 = a4, a6 = a5, a7 = a6, a8 = a7;
+        double b1 = a8, b2 = b1, b3 = b2, b4 = b3, b5 = b4, b6 = b5, b7 = b6, b8 = b7;
+        double c1 = b8, c2 = c1, c3 = c2, c4 = c3, c5 = c4, c6 = c5, c7 = c6, c8 = c7;
+        double e1 = c8, e2 = e1, e3 = e2, e4 = e3, e5 = e4, e6 = e5, e7 = e6, e8 = e7;
+        double f1 = e8, f2 = f1, f3 = f2, f4 = f3, f5 = f4, f6 = f5, f7 = f6, f8 = f7;
+        double g1 = f8, g2 = g1, g3 = g2, g4 = g3, g5 = g4, g6 = g5, g7 = g6, g8 = g7;
+        double h1 = g8, h2 = h1, h3 = h2, h4 = h3, h5 = h4, h6 = h5, h7 = h6, h8 = h7;
+        double i1 = h8, i2 = i1, i3 = i2, i4 = i3, i5 = i4, i6 = i5, i7 = i6, i8 = i7;
+        double j1 = i8, j2 = j1, j3 = j2, j4 = j3, j5 = j4, j6 = j5, j7 = j6, j8 = j7;
+        double k1 = j8, k2 = k1, k3 = k2, k4 = k3, k5 = k4, k6 = k5, k7 = k6, k8 = k7;
+        double l1 = k8, l2 = l1, l3 = l2, l4 = l3, l5 = l4, l6 = l5, l7 = l6, l8 = l7;
+        double m1 = l8, m2 = m1, m3 = m2, m4 = m3, m5 = m4, m6 = m5, m7 = m6, m8 = m7;
+        double n1 = m8, n2 = n1, n3 = n2, n4 = n3, n5 = n4, n6 = n5, n7 = n6, n8 = n7;
+        double o1 = n8, o2 = o1, o3 = o2, o4 = o3, o5 = o4, o6 = o5, o7 = o6, o8 = o7;
+        return o8 + 1;
+    }
+}
@@ -0,0 +1,16 @@
+import java.io.IOException;
+import java.util.List;
+
+public class Outline<T extends Comparable<T>> {
+
+    @Deprecated
+    public List<T> items;
+
+    public void load() throws IOException, InterruptedException {}
+
+    public Runnable task() {
+        return new Runnable() {
+            public void run() {}
+        };
+    }
+}
@@ -0,0 +1,13 @@
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.util.List;
+
+public sealed interface Shapes permits Shapes.Circle, Shapes.Polygon {
+
+    @Retention(RetentionPolicy.RUNTIME)
+    @interface Positive {}
+
+    record Circle(double radius) implements Shapes {}
+
+    record Polygon(List<Circle> vertices, @Positive int sides) implements Shapes {}
+}
@@ -0,0 +1,18 @@
+class StackMap {
+    static int sum(int count, String label) {
+        long acc = 0;
+
+        for (int i = 0; i < count; i++) {
+            acc += i;
+        }
+
+        if (label == null) {
+            return -1;
+        }
+        return (int) acc;
+    }
+
+    static Object pick(boolean flag) {
+        return flag ? "yes" : Integer.valueOf(0);
+    }
+}
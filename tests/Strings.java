@@ -0,0 +1,8 @@
+public class Strings {
+
+    public static final String NUL = "a\0b";
+
+    public static final String EMOJI = "smile \uD83D\uDE00";
+
+    public static final String LONE_SURROGATE = "\uD800!";
+}
@@ -0,0 +1,20 @@
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+import java.util.List;
+
+class TypeAnnotations {
+
+    @Target(ElementType.TYPE_USE)
+    @Retention(RetentionPolicy.RUNTIME)
+    @interface Nullable {
+    }
+
+    private @Nullable String name;
+
+    @Nullable String find(List<@Nullable String> values) {
+        @Nullable String local = values.isEmpty() ? null : values.get(0);
+        return local;
+    }
+}
@@ -0,0 +1,6 @@
+package com.example.app;
+
+public class Main implements Runnable {
+    public void run() {
+    }
+}
@@ -0,0 +1,10 @@
+module com.example.app {
+    requires transitive java.logging;
+    requires static java.sql;
+
+    exports com.example.app;
+    opens com.example.app to java.logging;
+
+    uses java.sql.Driver;
+    provides java.lang.Runnable with com.example.app.Main;
+}